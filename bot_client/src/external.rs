@@ -0,0 +1,293 @@
+// Drives an engine process that speaks UCI, so the bot can play with any engine
+// https://backscattering.de/chess/uci/
+
+use shared::error::bot::ExternalEngineError;
+use std::io::{BufRead as _, Write as _};
+
+// Engines can be slow to start, loading their tables or networks
+const HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+// On top of the time the engine was given, for it to notice and answer
+const MOVE_MARGIN: std::time::Duration = std::time::Duration::from_secs(5);
+// How long it has to quit before it gets killed
+const QUIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+pub struct ExternalEngine {
+    child: std::process::Child,
+    stdin: std::process::ChildStdin,
+    // Filled by a thread that reads the engine's output, so the reads can time out
+    lines: std::sync::mpsc::Receiver<String>,
+    name: Option<String>,
+    // What the engine said it can play, from its `option` lines
+    can_chess960: bool,
+    variants: Vec<String>,
+    // The rules it was last told to play with
+    chess960: bool,
+    variant: shared::chess::Variant,
+}
+
+impl ExternalEngine {
+    /// Starts the engine and waits for it to be ready
+    pub fn spawn(program: &str, args: &[String]) -> Result<Self, ExternalEngineError> {
+        let mut child = std::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .map_err(ExternalEngineError::Spawn)?;
+
+        // Both are piped
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, lines) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for line in std::io::BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Self {
+            child,
+            stdin,
+            lines,
+            name: None,
+            can_chess960: false,
+            variants: Vec::new(),
+            chess960: false,
+            variant: shared::chess::Variant::Standard,
+        };
+
+        engine.send("uci")?;
+        let mut name = None;
+        let mut options = Vec::new();
+        engine.wait_for("uci", HANDSHAKE_TIMEOUT, |line| {
+            if let Some(id) = line.strip_prefix("id name ") {
+                name = Some(id.to_string());
+            }
+            if let Some(option) = line.strip_prefix("option name ") {
+                options.push(option.to_string());
+            }
+            (line == "uciok").then_some(())
+        })?;
+        engine.name = name;
+
+        for option in options {
+            if option.starts_with("UCI_Chess960 ") {
+                engine.can_chess960 = true;
+            } else if let Some(combo) = option.strip_prefix("UCI_Variant ") {
+                // "type combo default chess var chess var crazyhouse ..."
+                let tokens = combo.split_whitespace().collect::<Vec<&str>>();
+                engine.variants = tokens
+                    .windows(2)
+                    .filter(|pair| pair[0] == "var")
+                    .map(|pair| pair[1].to_string())
+                    .collect();
+            }
+        }
+
+        engine.new_game()?;
+
+        Ok(engine)
+    }
+
+    /// What the engine calls itself
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn new_game(&mut self) -> Result<(), ExternalEngineError> {
+        self.send("ucinewgame")?;
+        self.send("isready")?;
+        self.wait_for("isready", HANDSHAKE_TIMEOUT, |line| {
+            (line == "readyok").then_some(())
+        })
+    }
+
+    /// Asks the engine what to play, `None` if it says there is no legal move
+    pub fn best_move(
+        &mut self,
+        board: &shared::chess::Board,
+        think_time: std::time::Duration,
+    ) -> Result<Option<shared::chess::ChessMove>, ExternalEngineError> {
+        self.go(
+            board,
+            &format!("go movetime {}", think_time.as_millis()),
+            think_time,
+        )
+    }
+
+    /// Same, but the engine spends the time left on the clocks the way it wants
+    pub fn best_move_on_clock(
+        &mut self,
+        board: &shared::chess::Board,
+        clock: &shared::game::Clock,
+    ) -> Result<Option<shared::chess::ChessMove>, ExternalEngineError> {
+        use shared::chess::Color;
+
+        let millis = |color| clock.remaining(color).unwrap_or_default().as_millis();
+        let increment = clock.control().increment().as_millis();
+        let go = format!(
+            "go wtime {} btime {} winc {increment} binc {increment}",
+            millis(Color::White),
+            millis(Color::Black)
+        );
+
+        let remaining = clock.remaining(board.next_to_play()).unwrap_or_default();
+        self.go(board, &go, remaining)
+    }
+
+    /// Sends the position and the `go` command, `think_time` is the longest the engine can take
+    fn go(
+        &mut self,
+        board: &shared::chess::Board,
+        go: &str,
+        think_time: std::time::Duration,
+    ) -> Result<Option<shared::chess::ChessMove>, ExternalEngineError> {
+        self.send(&format!("position fen {}", board.to_fen()))?;
+        self.send(go)?;
+
+        let uci = self.wait_for("go", think_time + MOVE_MARGIN, |line| {
+            let mut tokens = line.split_whitespace();
+            (tokens.next() == Some("bestmove"))
+                .then(|| tokens.next().unwrap_or_default().to_string())
+        })?;
+
+        // Null moves
+        if uci == "0000" || uci == "(none)" {
+            return Ok(None);
+        }
+
+        shared::chess::ChessMove::from_uci(&uci, board)
+            .map(Some)
+            .map_err(|_| ExternalEngineError::BestMove(uci))
+    }
+
+    /// Answers a `GameInfoUpdate`, with a move if it's `color`'s turn to play
+    /// The engine plays on the game's clock if it has one, and takes `think_time` otherwise
+    pub fn answer(
+        &mut self,
+        game: &shared::game::Game,
+        color: shared::chess::Color,
+        think_time: std::time::Duration,
+    ) -> Result<Option<shared::message::ClientMessage>, ExternalEngineError> {
+        let shared::game::State::Playing { board } = game.state() else {
+            return Ok(None);
+        };
+
+        if board.next_to_play() != color {
+            return Ok(None);
+        }
+
+        let chess960 = matches!(game.options().setup, shared::game::Setup::Chess960(_));
+        self.set_rules(chess960, board.variant())?;
+
+        let best_move = if game.options().time_control.is_realtime() {
+            self.best_move_on_clock(board, game.clock())?
+        } else {
+            self.best_move(board, think_time)?
+        };
+
+        Ok(best_move.map(shared::message::ClientMessage::MakeMove))
+    }
+
+    /// Tells the engine the rules before it gets a position that needs them, the FEN alone isn't enough
+    /// Fails if the engine didn't say it knows them
+    pub fn set_rules(
+        &mut self,
+        chess960: bool,
+        variant: shared::chess::Variant,
+    ) -> Result<(), ExternalEngineError> {
+        // Nothing is changed unless both can be
+        if chess960 && !self.can_chess960 {
+            return Err(ExternalEngineError::Unsupported(String::from("Chess960")));
+        }
+        let name = uci_variant(variant);
+        if variant != self.variant && !self.variants.iter().any(|known| known == name) {
+            return Err(ExternalEngineError::Unsupported(variant.to_string()));
+        }
+
+        if chess960 != self.chess960 {
+            self.send(&format!("setoption name UCI_Chess960 value {chess960}"))?;
+            self.chess960 = chess960;
+        }
+        if variant != self.variant {
+            self.send(&format!("setoption name UCI_Variant value {name}"))?;
+            self.variant = variant;
+        }
+
+        Ok(())
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), ExternalEngineError> {
+        trace!("Engine < {line}");
+        writeln!(self.stdin, "{line}")?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    /// Reads lines until `f` finds what it's looking for, `command` is what was asked, for the errors
+    fn wait_for<T>(
+        &mut self,
+        command: &'static str,
+        timeout: std::time::Duration,
+        mut f: impl FnMut(&str) -> Option<T>,
+    ) -> Result<T, ExternalEngineError> {
+        let deadline = std::time::Instant::now() + timeout;
+
+        loop {
+            let left = deadline.saturating_duration_since(std::time::Instant::now());
+
+            match self.lines.recv_timeout(left) {
+                Ok(line) => {
+                    trace!("Engine > {line}");
+                    if let Some(found) = f(&line) {
+                        return Ok(found);
+                    }
+                }
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                    return Err(ExternalEngineError::Timeout(command))
+                }
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err(ExternalEngineError::Exited)
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+
+        let deadline = std::time::Instant::now() + QUIT_TIMEOUT;
+        while std::time::Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        warn!("The engine did not quit, killing it");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The names the multi-variant engines use, like Fairy-Stockfish
+fn uci_variant(variant: shared::chess::Variant) -> &'static str {
+    use shared::chess::Variant;
+
+    match variant {
+        Variant::Standard => "chess",
+        Variant::KingOfTheHill => "kingofthehill",
+        Variant::ThreeCheck => "3check",
+        Variant::Antichess => "antichess",
+        Variant::Horde => "horde",
+        Variant::Crazyhouse => "crazyhouse",
+    }
+}
@@ -1,8 +1,8 @@
-use shared::chess;
-
 #[macro_use]
 extern crate log;
 
+const USAGE: &str = "Usage: bot_client [--level <level>] [--personality <personality>] [--seed <number>] [--book <file>] [--engine <program> [args]]";
+
 fn handle_send_error(res: Result<networking::socket::Header, networking::socket::SocketError>) {
     res.unwrap();
 }
@@ -56,120 +56,253 @@ fn handle_server_message(
     }
 }
 
-// This is dogshit rn but it's for testing
-fn move_gen(
-    client: &mut networking::Socket<shared::message::ServerMessage, shared::message::ClientMessage>,
-    board: &shared::chess::Board,
-    bot_color: shared::chess::Color,
-) {
-    let all_pieces = [
-        shared::chess::Piece::Pawn,
-        shared::chess::Piece::Knight,
-        shared::chess::Piece::Bishop,
-        shared::chess::Piece::Rook,
-        shared::chess::Piece::Queen,
-        shared::chess::Piece::King,
-    ];
-    'outer: loop {
-        let moving_piece = random::pick(&all_pieces);
-        let mut all_piece_position = Vec::new();
-        {
-            for file in 0..8 {
-                for rank in 0..8 {
-                    if board.read((file, rank).into()) == Some((bot_color, moving_piece)) {
-                        all_piece_position.push((file as i8, rank as i8))
-                    }
+// How long the bot thinks about each move, at most when it plays on a clock
+const THINK_TIME: std::time::Duration = std::time::Duration::from_secs(1);
+
+// What picks the bot's moves
+enum Brain {
+    BuiltIn(shared::engine::Engine),
+    // Any UCI engine, given on the command line
+    External(bot_client::external::ExternalEngine),
+}
+
+impl Brain {
+    fn from_args() -> Self {
+        let mut args = std::env::args().skip(1);
+
+        let mut level = shared::engine::Level::default();
+        let mut personality = shared::engine::Personality::default();
+        let mut seed = None;
+        let mut book = None;
+        let mut external = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--level" => level = parse_arg(&arg, args.next()),
+                "--personality" => personality = parse_arg(&arg, args.next()),
+                "--seed" => seed = Some(parse_arg(&arg, args.next())),
+                "--book" => book = Some(parse_arg::<String>(&arg, args.next())),
+                "--engine" => {
+                    let Some(program) = args.next() else {
+                        exit_with_usage("--engine needs the engine's program");
+                    };
+                    // Everything after it is for the engine
+                    external = Some((program, args.by_ref().collect::<Vec<String>>()));
                 }
+                _ => exit_with_usage(&format!("Unknown argument '{arg}'")),
             }
         }
-        if all_piece_position.is_empty() {
-            debug!("No position for piece: {moving_piece}");
-            continue;
+
+        if let Some((program, args)) = external {
+            if book.is_some() {
+                warn!("The external engine plays with its own book, --book is ignored");
+            }
+            return match bot_client::external::ExternalEngine::spawn(&program, &args) {
+                Ok(engine) => {
+                    info!("Playing with {}", engine.name().unwrap_or(&program));
+                    Self::External(engine)
+                }
+                Err(e) => {
+                    error!("{e}");
+                    std::process::exit(1);
+                }
+            };
         }
-        let moving_piece_pos = random::pick(&all_piece_position);
 
-        let possible_moves = moving_piece.pseudo_legal_relative_moves();
+        // Logged so a game can be played again
+        let seed = seed.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64
+        });
+        info!("Playing at level {level} as {personality} with seed {seed}");
 
-        let mv = random::pick(possible_moves);
+        let mut engine = shared::engine::Engine::default();
+        engine.set_profile(shared::engine::Profile::new(level, personality, seed));
 
-        let bot_chess_move = shared::chess::ChessMove::new(
-            shared::chess::Position::from_index(moving_piece_pos.0 as u8, moving_piece_pos.1 as u8)
-                .unwrap(),
-            {
-                let mut target = moving_piece_pos;
-                match bot_color {
-                    chess::Color::Black => {
-                        target.0 -= mv.x;
-                        target.1 -= mv.y;
-                    }
-                    chess::Color::White => {
-                        target.0 += mv.x;
-                        target.1 += mv.y;
-                    }
+        // Looked for in resources/external
+        if let Some(path) = book {
+            match shared::engine::Book::load(&path) {
+                Ok(book) => {
+                    info!("Playing with the book {path} ({} entries)", book.len());
+                    engine.set_book(Some(std::sync::Arc::new(book)));
                 }
-                let target = shared::chess::Position::from_index(target.0 as u8, target.1 as u8);
-                if target.is_none() {
-                    continue;
+                Err(e) => {
+                    error!("Could not load the book {path}: {e}");
+                    std::process::exit(1);
                 }
-                target.unwrap()
-            },
-            moving_piece,
-            bot_color,
-        );
-
-        if bot_chess_move.is_legal(board) {
-            client
-                .send(shared::message::ClientMessage::MakeMove(bot_chess_move))
-                .unwrap();
-        } else {
-            continue;
+            }
         }
 
-        'inner: loop {
-            let Ok((_header, msg)) = client.try_recv() else {
-                continue 'inner;
-            };
+        Self::BuiltIn(engine)
+    }
 
-            if let shared::message::ServerMessage::MoveResponse { chess_move, valid } = msg {
-                assert_eq!(bot_chess_move, chess_move);
-                if valid {
-                    debug!("Moving {moving_piece}");
-                    break 'outer;
-                } else {
-                    warn!("Move wasn't right: {moving_piece}: {chess_move:?}");
-                    break;
+    /// The profile games created by the bot ask for
+    fn profile(&self) -> Option<shared::engine::Profile> {
+        match self {
+            Brain::BuiltIn(engine) => Some(*engine.profile()),
+            Brain::External(_) => None,
+        }
+    }
+
+    /// Plays the way the game asks, if it does
+    fn adopt(&mut self, game: &shared::game::Game) {
+        let Some(profile) = game.options().bot else {
+            return;
+        };
+
+        match self {
+            Brain::BuiltIn(engine) => {
+                if *engine.profile() != profile {
+                    info!("Game {} asks for {profile:?}", game.id());
+                    engine.set_profile(profile);
+                }
+            }
+            Brain::External(_) => warn!(
+                "Game {} asks for {profile:?}, the external engine can't follow it",
+                game.id()
+            ),
+        }
+    }
+
+    /// What to send after the game got updated, a move if it's the bot's turn
+    fn answer(
+        &mut self,
+        game: &shared::game::Game,
+        bot_color: shared::chess::Color,
+    ) -> Option<shared::message::ClientMessage> {
+        match self {
+            Brain::BuiltIn(engine) => {
+                let shared::game::State::Playing { board } = game.state() else {
+                    return None;
+                };
+                if board.next_to_play() != bot_color {
+                    return None;
+                }
+
+                let limits = shared::engine::Limits::game(game, bot_color, THINK_TIME);
+                let (chess_move, info) = engine.search(board, limits)?;
+                debug!(
+                    "Depth {}, score {}, {} nodes in {:?}",
+                    info.depth, info.score, info.nodes, info.time
+                );
+                Some(shared::message::ClientMessage::MakeMove(chess_move))
+            }
+            Brain::External(engine) => match engine.answer(game, bot_color, THINK_TIME) {
+                Ok(message) => message,
+                Err(e) => {
+                    // The bot can't play without it
+                    error!("{e}");
+                    std::process::exit(1);
                 }
+            },
+        }
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(flag: &str, value: Option<String>) -> T
+where
+    T::Err: std::fmt::Display,
+{
+    let Some(value) = value else {
+        exit_with_usage(&format!("{flag} needs a value"));
+    };
+
+    value
+        .parse()
+        .unwrap_or_else(|e| exit_with_usage(&format!("Invalid value for {flag}: {e}")))
+}
+
+fn exit_with_usage(error: &str) -> ! {
+    error!("{USAGE}\n{error}");
+    std::process::exit(1);
+}
+
+fn move_gen(
+    client: &mut networking::Socket<shared::message::ServerMessage, shared::message::ClientMessage>,
+    brain: &mut Brain,
+    game: &shared::game::Game,
+    bot_color: shared::chess::Color,
+) {
+    let Some(message) = brain.answer(game, bot_color) else {
+        // The game is over, the server will tell us
+        return;
+    };
+
+    client.send(message.clone()).unwrap();
+
+    loop {
+        let Ok((_header, msg)) = client.try_recv() else {
+            continue;
+        };
+
+        if let shared::message::ServerMessage::MoveResponse { chess_move, valid } = msg {
+            assert_eq!(
+                shared::message::ClientMessage::MakeMove(chess_move),
+                message
+            );
+            if let (true, shared::game::State::Playing { board }) = (valid, game.state()) {
+                debug!("Playing {}", chess_move.to_san(board));
+            } else {
+                warn!("Move wasn't right: {chess_move:?}");
             }
+            break;
         }
     }
 }
 
 fn game_state(
     client: &mut networking::Socket<shared::message::ServerMessage, shared::message::ClientMessage>,
+    brain: &mut Brain,
     mut game: shared::game::Game,
     bot_id: shared::id::Id,
-) -> ! {
-    let bot_color = find_bot_color(&game, bot_id);
+) {
+    brain.adopt(&game);
 
     debug!("Bot is ready");
+    // Only known once the game started, a created game waits for its opponent first
+    let mut bot_color = None;
     loop {
         // std::thread::sleep(std::time::Duration::from_secs_f32(0.5));
         if let Ok((_header, message)) = client.try_recv() {
             handle_server_message(message, client, &mut game)
         }
-        let shared::game::Game {
-            id: _,
-            players: _,
-            state: shared::game::State::Playing { board },
-        } = &mut game
-        else {
-            todo!()
+        let (board, bot_color) = match game.state() {
+            shared::game::State::Playing { board } => (
+                board,
+                *bot_color.get_or_insert_with(|| find_bot_color(&game, bot_id)),
+            ),
+            shared::game::State::GameStart => continue,
+            shared::game::State::GameEnd { winner, reason } => {
+                let result = match winner {
+                    Some(winner) if *winner == bot_id => "won",
+                    Some(_) => "lost",
+                    None => "drew",
+                };
+                info!("Game {} is over, the bot {result} ({reason})", game.id());
+                break;
+            }
+            shared::game::State::Waiting | shared::game::State::PlayerDisconnected
+                if bot_color.is_none() =>
+            {
+                continue
+            }
+            shared::game::State::Waiting | shared::game::State::PlayerDisconnected => {
+                info!("Game {} stopped, the opponent is gone", game.id());
+                break;
+            }
         };
 
         if board.next_to_play() != bot_color {
             continue;
         }
-        move_gen(client, board, bot_color);
+        move_gen(client, brain, &game, bot_color);
+    }
+
+    // Back to the lobby, to wait for the next command
+    if let Err(e) = client.send(shared::message::ClientMessage::LeaveGameRequest) {
+        error!("Could not leave game {} due to: {e}", game.id());
     }
 }
 
@@ -221,6 +354,8 @@ fn main() {
     let config = logger::LoggerConfig::new().set_level(log::LevelFilter::Debug);
     logger::init(config, Some("log/bot_client.log"));
 
+    let mut brain = Brain::from_args();
+
     let stream = std::net::TcpStream::connect(shared::DEFAULT_ADDRESS).unwrap();
     stream.set_nonblocking(true).unwrap();
     let mut client = networking::Socket::<
@@ -260,7 +395,13 @@ fn main() {
         match *parts.first().unwrap() {
             "quit" => break,
             "create" => {
-                if let Err(e) = client.send(shared::message::ClientMessage::GameCreateRequest) {
+                let options = shared::game::Options {
+                    bot: brain.profile(),
+                    ..Default::default()
+                };
+                if let Err(e) =
+                    client.send(shared::message::ClientMessage::GameCreateRequest(options))
+                {
                     error!("Could not create game due to {e}");
                 }
                 debug!("Wating for server to send game code ..");
@@ -268,7 +409,7 @@ fn main() {
                 debug!("Joined game: {game:?}");
                 let updated_game = wait_for_game_info_update(&mut client, game.id);
                 debug!("Got updated game: {game:?}");
-                game_state(&mut client, updated_game, bot_id);
+                game_state(&mut client, &mut brain, updated_game, bot_id);
             }
             "join" => {
                 let p = parts.get(1).unwrap();
@@ -291,7 +432,7 @@ fn main() {
                 let updated_game = wait_for_game_info_update(&mut client, game_id);
                 debug!("Got updated game: {game:?}");
 
-                game_state(&mut client, updated_game, bot_id);
+                game_state(&mut client, &mut brain, updated_game, bot_id);
             }
             _ => warn!("Could not understand your message"),
         }
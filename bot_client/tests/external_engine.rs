@@ -0,0 +1,213 @@
+// Drives the fake engine script through the adapter, so no real engine is needed
+#![cfg(unix)]
+
+use bot_client::external::ExternalEngine;
+use shared::{
+    chess::{Board, ChessMove, Color, Variant},
+    error::bot::ExternalEngineError,
+    game::{Clock, Game, Options, Setup, State, TimeControl},
+    message::ClientMessage,
+};
+
+const FAKE_ENGINE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fake_engine.sh");
+const THINK_TIME: std::time::Duration = std::time::Duration::from_millis(100);
+
+fn spawn(
+    answer: &str,
+    log: Option<&std::path::Path>,
+) -> Result<ExternalEngine, ExternalEngineError> {
+    let mut args = vec![FAKE_ENGINE.to_string(), answer.to_string()];
+    if let Some(log) = log {
+        args.push(log.display().to_string());
+    }
+
+    ExternalEngine::spawn("sh", &args)
+}
+
+// What the engine received
+fn log_file(test: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "bot_client_fake_engine_{test}_{}.log",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn playing(board: Board) -> Game {
+    Game::new(
+        shared::id::Id::new(),
+        [None, None],
+        State::Playing { board },
+    )
+}
+
+#[test]
+fn plays() {
+    let log = log_file("plays");
+    let mut engine = spawn("e2e4", Some(&log)).unwrap();
+    assert_eq!(engine.name(), Some("Fake engine"));
+
+    let board = Board::default();
+    let answer = engine
+        .answer(&playing(board.clone()), Color::White, THINK_TIME)
+        .unwrap();
+
+    let expected = ChessMove::from_uci("e2e4", &board).unwrap();
+    assert_eq!(answer, Some(ClientMessage::MakeMove(expected)));
+
+    // Quits when dropped
+    drop(engine);
+
+    let received = std::fs::read_to_string(&log).unwrap();
+    println!("{received}");
+    let received = received.lines().collect::<Vec<&str>>();
+    assert_eq!(
+        received,
+        [
+            "uci",
+            "ucinewgame",
+            "isready",
+            "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "go movetime 100",
+            "quit"
+        ]
+    );
+    let _ = std::fs::remove_file(&log);
+}
+
+#[test]
+fn plays_on_the_clock() {
+    let log = log_file("clock");
+    let mut engine = spawn("e2e4", Some(&log)).unwrap();
+
+    let time_control = TimeControl::Increment {
+        base: std::time::Duration::from_secs(60),
+        increment: std::time::Duration::from_secs(2),
+    };
+    let game = playing(Board::default())
+        .with_options(Options {
+            time_control,
+            ..Default::default()
+        })
+        .with_clock(Clock::new(time_control));
+
+    let answer = engine.answer(&game, Color::White, THINK_TIME).unwrap();
+    assert!(matches!(answer, Some(ClientMessage::MakeMove(_))));
+
+    drop(engine);
+    let received = std::fs::read_to_string(&log).unwrap();
+    println!("{received}");
+    assert!(received.contains("go wtime 60000 btime 60000 winc 2000 binc 2000\n"));
+    assert!(!received.contains("movetime"));
+    let _ = std::fs::remove_file(&log);
+}
+
+#[test]
+fn plays_variants() {
+    let log = log_file("variants");
+    let mut engine = spawn("e2e4", Some(&log)).unwrap();
+
+    let crazyhouse = Board::from_variant(Variant::Crazyhouse);
+    let answer = engine.answer(&playing(crazyhouse), Color::White, THINK_TIME);
+    assert!(matches!(answer, Ok(Some(ClientMessage::MakeMove(_)))));
+
+    // Back to standard rules, on a Chess960 setup
+    let game = playing(Board::chess960(0).unwrap()).with_options(Options {
+        setup: Setup::Chess960(Some(0)),
+        ..Default::default()
+    });
+    let answer = engine.answer(&game, Color::White, THINK_TIME);
+    assert!(matches!(answer, Ok(Some(ClientMessage::MakeMove(_)))));
+
+    // The engine didn't list it
+    let horde = Board::from_variant(Variant::Horde);
+    let answer = engine.answer(&playing(horde), Color::White, THINK_TIME);
+    assert!(
+        matches!(&answer, Err(ExternalEngineError::Unsupported(name)) if name == "Horde"),
+        "{answer:?}"
+    );
+
+    drop(engine);
+    let received = std::fs::read_to_string(&log).unwrap();
+    println!("{received}");
+    let setoptions = received
+        .lines()
+        .filter(|line| line.starts_with("setoption"))
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        setoptions,
+        [
+            "setoption name UCI_Variant value crazyhouse",
+            "setoption name UCI_Chess960 value true",
+            "setoption name UCI_Variant value chess",
+        ]
+    );
+    let _ = std::fs::remove_file(&log);
+}
+
+#[test]
+fn plays_black() {
+    let mut board = Board::default();
+    board
+        .make_move(&ChessMove::from_uci("e2e4", &board).unwrap())
+        .unwrap();
+
+    let mut engine = spawn("e7e5", None).unwrap();
+    let Some(ClientMessage::MakeMove(chess_move)) = engine
+        .answer(&playing(board), Color::Black, THINK_TIME)
+        .unwrap()
+    else {
+        panic!("The engine should have played");
+    };
+
+    assert_eq!(chess_move.color, Color::Black);
+    assert_eq!(chess_move.to_uci(), "e7e5");
+}
+
+#[test]
+fn waits_for_its_turn() {
+    let log = log_file("waits");
+    let mut engine = spawn("e2e4", Some(&log)).unwrap();
+
+    let answer = engine.answer(&playing(Board::default()), Color::Black, THINK_TIME);
+    assert!(matches!(answer, Ok(None)));
+
+    let waiting = Game::new(shared::id::Id::new(), [None, None], State::Waiting);
+    let answer = engine.answer(&waiting, Color::White, THINK_TIME);
+    assert!(matches!(answer, Ok(None)));
+
+    drop(engine);
+    let received = std::fs::read_to_string(&log).unwrap();
+    assert!(!received.contains("go"));
+    let _ = std::fs::remove_file(&log);
+}
+
+#[test]
+fn bad_answers() {
+    // Not legal there
+    let mut engine = spawn("e2e5", None).unwrap();
+    let answer = engine.best_move(&Board::default(), THINK_TIME);
+    assert!(
+        matches!(&answer, Err(ExternalEngineError::BestMove(uci)) if uci == "e2e5"),
+        "{answer:?}"
+    );
+
+    // No move at all, it's stalemate
+    let mut engine = spawn("0000", None).unwrap();
+    let stalemate = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    assert!(matches!(engine.best_move(&stalemate, THINK_TIME), Ok(None)));
+}
+
+#[test]
+fn broken_engines() {
+    let mut engine = spawn("crash", None).unwrap();
+    let answer = engine.best_move(&Board::default(), THINK_TIME);
+    assert!(
+        matches!(answer, Err(ExternalEngineError::Exited)),
+        "{answer:?}"
+    );
+
+    let missing = ExternalEngine::spawn("this-engine-does-not-exist", &[]);
+    assert!(matches!(missing, Err(ExternalEngineError::Spawn(_))));
+}
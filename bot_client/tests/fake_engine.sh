@@ -0,0 +1,39 @@
+#!/bin/sh
+# Pretends to be a UCI engine for the tests
+# It always answers `go` with the move given as first argument, and writes what it receives to the file given as second
+# "crash" as the move exits on `go` instead
+
+answer="$1"
+log="$2"
+
+while read -r line; do
+    if [ -n "$log" ]; then
+        echo "$line" >> "$log"
+    fi
+
+    case "$line" in
+        uci)
+            echo "id name Fake engine"
+            echo "id author Nobody"
+            echo "option name Hash type spin default 16 min 1 max 1024"
+            echo "option name UCI_Chess960 type check default false"
+            echo "option name UCI_Variant type combo default chess var chess var crazyhouse var 3check"
+            echo "uciok"
+            ;;
+        isready)
+            echo "readyok"
+            ;;
+        go*)
+            case "$answer" in
+                crash) exit 1 ;;
+                *)
+                    echo "info depth 1 score cp 0"
+                    echo "bestmove $answer"
+                    ;;
+            esac
+            ;;
+        quit)
+            exit 0
+            ;;
+    esac
+done
@@ -78,9 +78,7 @@ impl State {
             shared::game::State::Playing {
                 board: _, /* hmm */
             } => Playing::new(client, game.id(), my_id).into(),
-            shared::game::State::GameEnd {
-                winner: _, /* hmm */
-            } => GameEnd::new(client, game, my_id).into(),
+            shared::game::State::GameEnd { .. } => GameEnd::new(client, game, my_id).into(),
         }
         // Might not be a bad idea to include those in the .new declaration
     }
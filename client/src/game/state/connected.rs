@@ -1,3 +1,9 @@
+// Games created with the "Blitz" button
+const TIMED_GAME: shared::game::TimeControl = shared::game::TimeControl::Increment {
+    base: std::time::Duration::from_secs(5 * 60),
+    increment: std::time::Duration::from_secs(3),
+};
+
 pub struct Connected {
     ui: crate::ui::UiManager,
     client: crate::game::Client,
@@ -118,7 +124,84 @@ impl Connected {
                 if el.clicked_this_frame() {
                     debug!("I wanna create a new game");
                     self.client
-                        .send(shared::message::ClientMessage::GameCreateRequest)
+                        .send(shared::message::ClientMessage::GameCreateRequest(
+                            Default::default(),
+                        ))
+                        .unwrap();
+                }
+            }
+
+            if let Some(el) = self
+                .ui
+                .try_get_element("Bot_game_create_button")
+                .and_then(|el| el.try_inner_mut::<crate::ui::element::Button>())
+            {
+                if el.clicked_this_frame() {
+                    debug!("I wanna play against a bot");
+                    self.client
+                        .send(shared::message::ClientMessage::BotGameCreateRequest(
+                            Default::default(),
+                        ))
+                        .unwrap();
+                }
+            }
+
+            if let Some(el) = self
+                .ui
+                .try_get_element("Chess960_game_create_button")
+                .and_then(|el| el.try_inner_mut::<crate::ui::element::Button>())
+            {
+                if el.clicked_this_frame() {
+                    debug!("I wanna create a Chess960 game");
+                    self.client
+                        .send(shared::message::ClientMessage::GameCreateRequest(
+                            shared::game::Options {
+                                setup: shared::game::Setup::Chess960(None),
+                                ..Default::default()
+                            },
+                        ))
+                        .unwrap();
+                }
+            }
+
+            for variant in shared::chess::ALL_VARIANTS
+                .into_iter()
+                .filter(|variant| *variant != shared::chess::Variant::Standard)
+            {
+                let Some(el) = self
+                    .ui
+                    .try_get_element(format!("{variant:?}_game_create_button"))
+                    .and_then(|el| el.try_inner_mut::<crate::ui::element::Button>())
+                else {
+                    continue;
+                };
+                if el.clicked_this_frame() {
+                    debug!("I wanna create a {variant} game");
+                    self.client
+                        .send(shared::message::ClientMessage::GameCreateRequest(
+                            shared::game::Options {
+                                variant,
+                                ..Default::default()
+                            },
+                        ))
+                        .unwrap();
+                }
+            }
+
+            if let Some(el) = self
+                .ui
+                .try_get_element("Timed_game_create_button")
+                .and_then(|el| el.try_inner_mut::<crate::ui::element::Button>())
+            {
+                if el.clicked_this_frame() {
+                    debug!("I wanna create a {TIMED_GAME} game");
+                    self.client
+                        .send(shared::message::ClientMessage::GameCreateRequest(
+                            shared::game::Options {
+                                time_control: TIMED_GAME,
+                                ..Default::default()
+                            },
+                        ))
                         .unwrap();
                 }
             }
@@ -296,7 +379,7 @@ fn create_games_ui(ui_mgr: &mut crate::ui::UiManager, games: &[shared::game::Gam
     ui_mgr.add_element(
         ui::element::Element::new_text(
             "New game button text",
-            new_b_pos,
+            new_b_pos.clone(),
             new_b_size.w() * 0.1,
             ui::Style::new(render::Color::default(), None, None),
             vec![("Create new", render::Color::random_rgb()).into()],
@@ -304,6 +387,114 @@ fn create_games_ui(ui_mgr: &mut crate::ui::UiManager, games: &[shared::game::Gam
         group_name,
     );
 
+    // The other create buttons go next to it, 3 per row so they all fit on screen
+    let create_b_pos = |index: usize| {
+        new_b_pos.clone()
+            + ui::Vector::new(
+                (MagicValue::ScreenSizeW * 0.01 + new_b_size.w()) * (index % 3) as f64,
+                (MagicValue::ScreenSizeH * 0.03 + new_b_size.h()) * (index / 3) as f64,
+            )
+    };
+
+    // And one to play against the server
+    let bot_b_pos = create_b_pos(1);
+
+    ui_mgr.add_element(
+        ui::element::Element::new_button(
+            "Bot_game_create_button",
+            bot_b_pos.clone(),
+            new_b_size.wh(),
+            card_style.into(),
+        ),
+        group_name,
+    );
+    ui_mgr.add_element(
+        ui::element::Element::new_text(
+            "Bot game button text",
+            bot_b_pos.clone(),
+            new_b_size.w() * 0.1,
+            ui::Style::new(render::Color::default(), None, None),
+            vec![("Play vs bot", render::Color::random_rgb()).into()],
+        ),
+        group_name,
+    );
+
+    // And one for a random Chess960 setup
+    let chess960_b_pos = create_b_pos(2);
+
+    ui_mgr.add_element(
+        ui::element::Element::new_button(
+            "Chess960_game_create_button",
+            chess960_b_pos.clone(),
+            new_b_size.wh(),
+            card_style.into(),
+        ),
+        group_name,
+    );
+    ui_mgr.add_element(
+        ui::element::Element::new_text(
+            "Chess960 game button text",
+            chess960_b_pos.clone(),
+            new_b_size.w() * 0.1,
+            ui::Style::new(render::Color::default(), None, None),
+            vec![("Chess960", render::Color::random_rgb()).into()],
+        ),
+        group_name,
+    );
+
+    // And one per variant
+    let variants = shared::chess::ALL_VARIANTS
+        .into_iter()
+        .filter(|variant| *variant != shared::chess::Variant::Standard);
+    let mut next_index = 3;
+    for variant in variants {
+        let variant_b_pos = create_b_pos(next_index);
+        next_index += 1;
+
+        ui_mgr.add_element(
+            ui::element::Element::new_button(
+                format!("{variant:?}_game_create_button"),
+                variant_b_pos.clone(),
+                new_b_size.wh(),
+                card_style.into(),
+            ),
+            group_name,
+        );
+        ui_mgr.add_element(
+            ui::element::Element::new_text(
+                format!("{variant:?} game button text"),
+                variant_b_pos.clone(),
+                new_b_size.w() * 0.1,
+                ui::Style::new(render::Color::default(), None, None),
+                vec![(variant.to_string(), render::Color::random_rgb()).into()],
+            ),
+            group_name,
+        );
+    }
+
+    // And one with a clock
+    let timed_b_pos = create_b_pos(next_index);
+
+    ui_mgr.add_element(
+        ui::element::Element::new_button(
+            "Timed_game_create_button",
+            timed_b_pos.clone(),
+            new_b_size.wh(),
+            card_style.into(),
+        ),
+        group_name,
+    );
+    ui_mgr.add_element(
+        ui::element::Element::new_text(
+            "Timed game button text",
+            timed_b_pos,
+            new_b_size.w() * 0.1,
+            ui::Style::new(render::Color::default(), None, None),
+            vec![(format!("Blitz {TIMED_GAME}"), render::Color::random_rgb()).into()],
+        ),
+        group_name,
+    );
+
     // Adding a refresh button
     let refresh_button_size = ui::Vector::new(card_size.x() * 0.1, card_size.x() * 0.1);
     let refresh_button_vertical_margin = ui::Vector::new(0., card_size.h() * 0.1);
@@ -11,6 +11,13 @@ impl GameEnd {
         my_id: shared::id::Id,
     ) -> Self {
         debug!("Creating GameEnd State");
+        if let shared::game::State::GameEnd { winner, reason } = game.state() {
+            match winner {
+                Some(winner) if *winner == my_id => info!("You won ({reason})"),
+                Some(_) => info!("You lost ({reason})"),
+                None => info!("Draw ({reason})"),
+            }
+        }
         Self {
             client,
             current_game: crate::networking::Future::new(
@@ -49,7 +56,7 @@ impl super::StateMachine for GameEnd {
         if self.current_game.changed()
             && !matches!(
                 self.current_game.inner().unwrap().state(),
-                shared::game::State::Playing { .. }
+                shared::game::State::GameEnd { .. }
             )
         {
             return super::State::from_shared_state(
@@ -63,6 +70,6 @@ impl super::StateMachine for GameEnd {
     }
 
     fn draw(self, _: &mut crate::render::RenderRequest) -> super::State {
-        todo!()
+        self.into()
     }
 }
@@ -2,6 +2,9 @@
 const BOARD_UI_GROUP: &str = "board";
 const BOARD_SPRITE_UI_GROUP: &str = "board_sprite";
 const BOARD_INDICATOR_GROUP: &str = "indicator";
+const PROMOTION_UI_GROUP: &str = "promotion";
+const POCKET_UI_GROUP: &str = "pocket";
+const CLOCK_UI_GROUP: &str = "clock";
 
 pub struct Playing {
     ui: crate::ui::UiManager,
@@ -9,6 +12,12 @@ pub struct Playing {
     current_game: crate::networking::Future<shared::game::Game>,
     // current_board: crate::networking::Future<shared::chess::Board>,
     current_drag: Option<crate::ui::Id>,
+    // A pawn move that is waiting for the player to pick a promotion
+    pending_promotion: Option<shared::chess::ChessMove>,
+    // A crazyhouse piece picked from the pocket, it goes on the next square clicked
+    pending_drop: Option<shared::chess::Piece>,
+    // When the last game update came, the running clock goes down from there
+    clock_received: std::time::Instant,
     my_id: shared::id::Id,
 }
 
@@ -37,6 +46,9 @@ impl Playing {
                 },
             ),
             current_drag: None,
+            pending_promotion: None,
+            pending_drop: None,
+            clock_received: std::time::Instant::now(),
             my_id,
         }
     }
@@ -84,6 +96,8 @@ impl super::StateMachine for Playing {
             id: _,
             players,
             state: shared::game::State::Playing { board },
+            clock,
+            ..
         } = /*implicit &mut */ current_game
         else {
             // This should never occur as i check it just above
@@ -106,17 +120,54 @@ impl super::StateMachine for Playing {
 
         // The game just got received
         if current_game_changed {
+            self.clock_received = std::time::Instant::now();
+
             // if self.ui.get_group(BOARD_UI_GROUP).is_none() {
             create_board(&mut self.ui, my_color);
 
-            create_board_pieces(&mut self.ui, board)
+            create_board_pieces(&mut self.ui, board);
             // }
+
+            if board.variant().has_pockets() {
+                create_pockets(&mut self.ui, board, my_color);
+            }
         }
 
         self.ui.update(ggctx);
 
+        display_clocks(
+            &mut self.ui,
+            &clock.at(self.clock_received.elapsed()),
+            my_color,
+        );
+
         display_move_indicator(&mut self.ui, board, my_color, self.current_drag.as_ref());
 
+        if let Some(pending_move) = self.pending_promotion {
+            // The board is locked until the player picks a piece
+            if let Some(promotion) = get_promotion_choice(&mut self.ui) {
+                let _ = self.ui.remove_group(PROMOTION_UI_GROUP);
+                self.pending_promotion = None;
+
+                let chess_move = shared::chess::ChessMove {
+                    promotion: Some(promotion),
+                    ..pending_move
+                };
+                if let Err(e) = self
+                    .client
+                    .send(shared::message::ClientMessage::MakeMove(chess_move))
+                {
+                    warn!("Could not send move request to server due to: {e}");
+                }
+            }
+            return self.into();
+        }
+
+        if let Some(piece) = get_pocket_choice(&mut self.ui, board, my_color) {
+            debug!("Picked a {piece:?} to drop");
+            self.pending_drop = Some(piece);
+        }
+
         match get_current_move_delta(&mut self.current_drag, &mut self.ui) {
             Ok(Some((start, end))) => 'block: {
                 if board.next_to_play() != my_color {
@@ -127,8 +178,22 @@ impl super::StateMachine for Playing {
                 let start =
                     shared::chess::Position::from_index(start.0 as u8, start.1 as u8).unwrap();
                 let end = shared::chess::Position::from_index(end.0 as u8, end.1 as u8).unwrap();
-                debug!("{start} -> {end}",);
-                let Some((scolor, spiece)) = board.read(start) else {
+
+                if let Some(piece) = self.pending_drop.take() {
+                    let chess_move = shared::chess::ChessMove::drop(piece, my_color, end);
+
+                    // The server checks it, there might not be one left by now
+                    debug!("Dropping a {piece:?} on {end}");
+                    if let Err(e) = self
+                        .client
+                        .send(shared::message::ClientMessage::MakeMove(chess_move))
+                    {
+                        warn!("Could not send move request to server due to: {e}");
+                    }
+                    break 'block;
+                }
+
+                let Some((scolor, spiece)) = board.piece_at(start) else {
                     warn!("{start} is an empty square");
                     break 'block;
                 };
@@ -138,14 +203,25 @@ impl super::StateMachine for Playing {
                     break 'block;
                 }
 
-                // let Some((ecolor, epiece)) = board.read(start) else{
+                // let Some((ecolor, epiece)) = board.piece_at(start) else{
                 //     warn!("{end} is an empty square");
                 //     break 'block;
                 // };
 
-                if let Err(e) = self.client.send(shared::message::ClientMessage::MakeMove(
-                    shared::chess::ChessMove::new(start, end, spiece, scolor, None),
-                )) {
+                let chess_move = shared::chess::ChessMove::new(start, end, spiece, scolor, None);
+
+                if chess_move.needs_promotion() {
+                    // Ask the player what the pawn becomes before sending anything
+                    create_promotion_picker(&mut self.ui, scolor);
+                    self.pending_promotion = Some(chess_move);
+                    break 'block;
+                }
+
+                debug!("Playing {}", chess_move.to_san(board));
+                if let Err(e) = self
+                    .client
+                    .send(shared::message::ClientMessage::MakeMove(chess_move))
+                {
                     warn!("Could not send move request to server due to: {e}");
                     break 'block;
                 }
@@ -195,20 +271,22 @@ fn display_move_indicator(
 
     let pos = shared::chess::Position::from_index(pos_index.0 as u8, pos_index.1 as u8).unwrap();
     let pos_index = (pos.file().to_index(), pos.rank().to_index());
-    let Some((p_color, piece)) = board.read(pos) else {
+    let Some((p_color, piece)) = board.piece_at(pos) else {
         return;
     };
 
     debug!("Base pos: {pos_index:?}");
 
     // let mvs = piece.pseudo_legal_relative_moves();
-    let mvs = shared::chess::movegen::all_legals(piece, pos, board)
+    let mut mvs = shared::chess::movegen::all_legals(piece, pos, board)
         .unwrap()
         .iter()
         .map(|mv|{
             mv.target - mv.origin
         })
         .collect::<Vec<shared::chess::RelativeChessMove>>();
+    // Promotions give one move per piece on the same square
+    mvs.dedup();
 
     for mv in mvs.clone() {
         let temp = (pos_index.0 as i8 + mv.x, pos_index.1 as i8 + mv.y);
@@ -222,12 +300,20 @@ fn display_move_indicator(
             continue;
         };
 
-        let (color2, piece2) = board.read(pos_index.into()).unwrap();
+        let (color2, piece2) = board.piece_at(pos_index.into()).unwrap();
         assert_eq!(p_color, color2);
         assert_eq!(piece, piece2);
 
         let chess_move = shared::chess::ChessMove::new(pos_index.into(), mv_pos, piece, color, None);
 
+        // A promotion is legal for every piece, checking one of them is enough
+        let chess_move = shared::chess::ChessMove {
+            promotion: chess_move
+                .needs_promotion()
+                .then_some(shared::chess::Piece::Queen),
+            ..chess_move
+        };
+
         let style = if chess_move.is_legal(board) {
             crate::ui::Style::new(
                 crate::render::Color::from_rgb(0, 255, 0),
@@ -274,6 +360,231 @@ fn display_move_indicator(
     }
 }
 
+fn create_promotion_picker(ui: &mut crate::ui::UiManager, color: shared::chess::Color) {
+    use crate::{
+        assets::sprite::SpriteId,
+        render::Color,
+        ui::{element::Element, style, value, Style, Vector},
+    };
+
+    let _ = ui.remove_group(PROMOTION_UI_GROUP);
+
+    let board_size =
+        (value::MagicValue::ScreenSizeW * 0.5 + value::MagicValue::ScreenSizeW * 0.5) * 0.5;
+
+    let square_size = Vector::new(board_size.clone() / 8., board_size / 8.);
+
+    let style = style::Bundle::new(
+        Style::new(
+            Color::from_hex("#e3c16f"),
+            None,
+            Some(style::Border::new(Color::from_hex("#000000"), 5.)),
+        ),
+        Some(Style::new(
+            Color::from_hex("#e3c16faa"),
+            None,
+            Some(style::Border::new(Color::from_hex("#000000"), 5.)),
+        )),
+        Some(Style::new(
+            Color::from_hex("#e3c16f55"),
+            None,
+            Some(style::Border::new(Color::from_hex("#000000"), 5.)),
+        )),
+    );
+
+    // One square per piece, in a row at the center of the board
+    for (i, piece) in shared::chess::PROMOTION_PIECES.iter().enumerate() {
+        let center = Vector::new(
+            value::MagicValue::ScreenSizeW * 0.5 + square_size.x() * (i as f64 - 1.5),
+            value::MagicValue::ScreenSizeH * 0.5,
+        );
+
+        ui.add_element(
+            Element::new_button(
+                format!("promotion_{piece:?}"),
+                center.clone(),
+                square_size.clone(),
+                style,
+            ),
+            PROMOTION_UI_GROUP,
+        );
+
+        ui.add_element(
+            Element::new_image(
+                format!("promotion_sprite_{piece:?}"),
+                center,
+                square_size.clone() * 0.8,
+                Style::default(),
+                SpriteId::ChessPiece(color, *piece),
+            ),
+            PROMOTION_UI_GROUP,
+        );
+    }
+}
+
+/// Returns the piece picked by the player this frame, if any
+fn get_promotion_choice(ui: &mut crate::ui::UiManager) -> Option<shared::chess::Piece> {
+    shared::chess::PROMOTION_PIECES.into_iter().find(|piece| {
+        ui.try_get_element(format!("promotion_{piece:?}"))
+            .and_then(|element| element.try_inner::<crate::ui::element::Button>())
+            .map(|button| button.clicked_this_frame())
+            .unwrap_or(false)
+    })
+}
+
+/// Both players' time, the opponent's above the board and the player's under it
+fn display_clocks(
+    ui: &mut crate::ui::UiManager,
+    clock: &shared::game::Clock,
+    my_color: shared::chess::Color,
+) {
+    use crate::ui::{element::Element, value, Style, Vector};
+
+    for color in [my_color, !my_color] {
+        // Games without a limit have no clock to show
+        let Some(remaining) = clock.remaining(color) else {
+            continue;
+        };
+
+        let id = format!("clock_{color:?}");
+        if ui.try_get_element(id.clone()).is_none() {
+            let board_size =
+                (value::MagicValue::ScreenSizeW * 0.5 + value::MagicValue::ScreenSizeW * 0.5) * 0.5;
+            let direction = if color == my_color { 1. } else { -1. };
+
+            ui.add_element(
+                Element::new_text(
+                    id.clone(),
+                    Vector::new(
+                        value::MagicValue::ScreenSizeW * 0.5,
+                        value::MagicValue::ScreenSizeH * 0.5 + board_size * 0.55 * direction,
+                    ),
+                    30.,
+                    Style::default(),
+                    Vec::new(),
+                ),
+                CLOCK_UI_GROUP,
+            );
+        }
+
+        // Correspondence games count in hours
+        let secs = remaining.as_secs();
+        let text = if secs >= 60 * 60 {
+            format!("{}h{:02}", secs / (60 * 60), secs / 60 % 60)
+        } else {
+            format!("{}:{:02}", secs / 60, secs % 60)
+        };
+
+        ui.get_element(id)
+            .inner_mut::<crate::ui::element::Text>()
+            .replace_bits(vec![text.into()]);
+    }
+}
+
+/// The pieces each player can drop, next to the board, the player's own pocket is at the bottom
+fn create_pockets(
+    ui: &mut crate::ui::UiManager,
+    board: &shared::chess::Board,
+    my_color: shared::chess::Color,
+) {
+    use crate::{
+        assets::sprite::SpriteId,
+        render::Color,
+        ui::{element::Element, style, value, Style, Vector},
+    };
+
+    let _ = ui.remove_group(POCKET_UI_GROUP);
+
+    let board_size =
+        (value::MagicValue::ScreenSizeW * 0.5 + value::MagicValue::ScreenSizeW * 0.5) * 0.5;
+
+    let square_size = Vector::new(board_size.clone() / 8., board_size / 8.);
+
+    let style = style::Bundle::new(
+        Style::new(Color::from_hex("#e3c16f"), None, None),
+        Some(Style::new(
+            Color::from_hex("#e3c16faa"),
+            None,
+            Some(style::Border::new(Color::from_hex("#000000"), 5.)),
+        )),
+        Some(Style::new(
+            Color::from_hex("#e3c16f55"),
+            None,
+            Some(style::Border::new(Color::from_hex("#000000"), 5.)),
+        )),
+    );
+
+    for color in [my_color, !my_color] {
+        // Going up from the bottom of the board for the player, down from the top for the opponent
+        let direction = if color == my_color { 1. } else { -1. };
+
+        for (i, piece) in shared::chess::POCKET_PIECES.iter().enumerate() {
+            let count = board.pocket(color, *piece);
+            if count == 0 {
+                continue;
+            }
+
+            let center = Vector::new(
+                value::MagicValue::ScreenSizeW * 0.5 + square_size.x() * 5.,
+                value::MagicValue::ScreenSizeH * 0.5
+                    + square_size.y() * (3.5 - i as f64) * direction,
+            );
+
+            // Only the player's own pieces can be picked
+            if color == my_color {
+                ui.add_element(
+                    Element::new_button(
+                        format!("pocket_{color:?}_{piece:?}"),
+                        center.clone(),
+                        square_size.clone(),
+                        style,
+                    ),
+                    POCKET_UI_GROUP,
+                );
+            }
+
+            ui.add_element(
+                Element::new_image(
+                    format!("pocket_sprite_{color:?}_{piece:?}"),
+                    center.clone(),
+                    square_size.clone() * 0.8,
+                    Style::default(),
+                    SpriteId::ChessPiece(color, *piece),
+                ),
+                POCKET_UI_GROUP,
+            );
+
+            ui.add_element(
+                Element::new_text(
+                    format!("pocket_count_{color:?}_{piece:?}"),
+                    Vector::new(center.x() + square_size.x() * 0.75, center.y()),
+                    20.,
+                    Style::default(),
+                    vec![format!("{count}").into()],
+                ),
+                POCKET_UI_GROUP,
+            );
+        }
+    }
+}
+
+/// Returns the piece the player took out of their pocket this frame, if any
+fn get_pocket_choice(
+    ui: &mut crate::ui::UiManager,
+    board: &shared::chess::Board,
+    my_color: shared::chess::Color,
+) -> Option<shared::chess::Piece> {
+    // The buttons can be older than the board, only the pieces still in the pocket can be picked
+    shared::chess::POCKET_PIECES.into_iter().find(|piece| {
+        board.pocket(my_color, *piece) > 0
+            && ui
+                .try_get_element(format!("pocket_{my_color:?}_{piece:?}"))
+                .and_then(|element| element.try_inner::<crate::ui::element::Button>())
+                .map(|button| button.clicked_this_frame())
+                .unwrap_or(false)
+    })
+}
+
 // Returns the chess position (indexes) given by the current square
 fn get_pos_from_id(id: &crate::ui::Id) -> (i8, i8) {
     let id = id.replace("board_square_", "").replace('x', "");
@@ -380,7 +691,7 @@ fn create_board_pieces(ui: &mut crate::ui::UiManager, board: &shared::chess::Boa
     for j in 0..8 {
         for i in 0..8 {
             let position = shared::chess::Position::from_index(i, j).unwrap();
-            if let Some((color, piece)) = board.read(position) {
+            if let Some((color, piece)) = board.piece_at(position) {
                 let sprite_id = SpriteId::ChessPiece(color, piece);
 
                 let id = format!("board_square_{i}x{j}");
@@ -0,0 +1,278 @@
+// An engine that sits in a game like a connected player would
+// It gets the same messages as a client and answers with the same `MakeMove`, so its moves are checked like any
+// other, the search runs on its own thread to not slow down the server's loop
+
+// How long the bot thinks about each move, at most when it plays on a clock
+const THINK_TIME: std::time::Duration = std::time::Duration::from_secs(1);
+// Shared by all the bots, in resources/external
+pub const BOOK_PATH: &str = "books/default.bin";
+
+/// For the games that don't say how the bot has to play, the seed is logged so the game can be replayed
+pub fn default_profile() -> shared::engine::Profile {
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    debug!("New bot with seed {seed}");
+
+    shared::engine::Profile::new(
+        shared::engine::Level::Medium,
+        shared::engine::Personality::Balanced,
+        seed,
+    )
+}
+
+/// The bots play without a book if there is none
+pub fn load_book() -> Option<std::sync::Arc<shared::engine::Book>> {
+    match shared::engine::Book::load(BOOK_PATH) {
+        Ok(book) => {
+            debug!("Loaded the bots' book, {} entries", book.len());
+            Some(std::sync::Arc::new(book))
+        }
+        Err(e) => {
+            warn!("The bots will play without a book: {e}");
+            None
+        }
+    }
+}
+
+pub struct Bot {
+    id: shared::id::Id,
+    sender: std::sync::mpsc::Sender<shared::message::ServerMessage>,
+    receiver: std::sync::mpsc::Receiver<shared::message::ClientMessage>,
+    // Stops the running search when the bot is dropped
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    handle: std::thread::JoinHandle<()>,
+}
+
+impl Bot {
+    pub fn new(
+        profile: shared::engine::Profile,
+        book: Option<std::sync::Arc<shared::engine::Book>>,
+    ) -> Self {
+        let id = shared::id::Id::new();
+
+        let mut engine = shared::engine::Engine::default();
+        engine.set_profile(profile);
+        engine.set_book(book);
+        let stop = engine.stop_flag();
+
+        let (sender, server_messages) = std::sync::mpsc::channel();
+        let (client_sender, receiver) = std::sync::mpsc::channel();
+
+        let handle = std::thread::spawn(move || {
+            // Ends when the bot is dropped, or when the game stopped listening
+            let _ = run(id, engine, server_messages, client_sender);
+            debug!("Bot ({id}) stopped");
+        });
+
+        Self {
+            id,
+            sender,
+            receiver,
+            stop,
+            handle,
+        }
+    }
+
+    pub fn id(&self) -> shared::id::Id {
+        self.id
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.handle.is_finished()
+    }
+
+    pub fn try_recv(
+        &mut self,
+    ) -> Result<shared::message::ClientMessage, std::sync::mpsc::TryRecvError> {
+        self.receiver.try_recv()
+    }
+
+    pub fn send(
+        &mut self,
+        msg: shared::message::ServerMessage,
+    ) -> Result<(), std::boxed::Box<std::sync::mpsc::SendError<shared::message::ServerMessage>>>
+    {
+        Ok(self.sender.send(msg)?)
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        // The thread sees the closed channel once the search returns
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+fn run(
+    id: shared::id::Id,
+    mut engine: shared::engine::Engine,
+    server_messages: std::sync::mpsc::Receiver<shared::message::ServerMessage>,
+    client_sender: std::sync::mpsc::Sender<shared::message::ClientMessage>,
+) -> Result<(), std::sync::mpsc::SendError<shared::message::ClientMessage>> {
+    // The game sends the same position more than once, it's only answered the first time
+    let mut answered = None;
+    let mut game_id = None;
+
+    while let Ok(msg) = server_messages.recv() {
+        match msg {
+            shared::message::ServerMessage::GameInfoUpdate(update_id, game) => {
+                game_id = Some(update_id);
+
+                let shared::game::State::Playing { board } = game.state() else {
+                    continue;
+                };
+
+                let Some(color) = game
+                    .players()
+                    .iter()
+                    .flatten()
+                    .find(|player| player.id == id)
+                    .and_then(|player| player.color)
+                else {
+                    continue;
+                };
+
+                let ply = (board.fullmove_number(), board.next_to_play());
+                if board.next_to_play() != color || answered == Some(ply) {
+                    continue;
+                }
+                answered = Some(ply);
+
+                // The game checks for the end itself
+                let limits = shared::engine::Limits::game(&game, color, THINK_TIME);
+                let Some((chess_move, info)) = engine.search(board, limits) else {
+                    continue;
+                };
+                debug!(
+                    "Bot ({id}) plays {}, depth {}, score {}, {} nodes in {:?}",
+                    chess_move.to_san(board),
+                    info.depth,
+                    info.score,
+                    info.nodes,
+                    info.time
+                );
+                client_sender.send(shared::message::ClientMessage::MakeMove(chess_move))?;
+            }
+            shared::message::ServerMessage::MoveResponse {
+                chess_move,
+                valid: false,
+            } => {
+                error!("Bot ({id}) played an invalid move: {chess_move:?}");
+                // Its board was wrong, it asks for the game again and thinks on the one it gets
+                answered = None;
+                if let Some(game_id) = game_id {
+                    client_sender.send(shared::message::ClientMessage::GameInfoRequest(game_id))?;
+                }
+            }
+            shared::message::ServerMessage::GameJoin(_) => engine.new_game(),
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::{
+        chess::{Board, Color},
+        game::{Game, Player, State},
+        message::{ClientMessage, ServerMessage},
+    };
+
+    fn wait_for_move(bot: &mut super::Bot) -> Option<ClientMessage> {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while std::time::Instant::now() < deadline {
+            if let Ok(msg) = bot.try_recv() {
+                return Some(msg);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        None
+    }
+
+    #[test]
+    fn plays_its_turn_once() {
+        let mut bot = super::Bot::new(shared::engine::Level::Beginner.profile(), None);
+        let board = Board::default();
+
+        let id = bot.id();
+        let player = |color| Some(Player::new(id, String::from("Bot"), Some(color)));
+        let playing = |color| {
+            Game::new(
+                shared::id::Id::new(),
+                [player(color), None],
+                State::Playing {
+                    board: board.clone(),
+                },
+            )
+        };
+
+        // Black doesn't play first
+        let game = playing(Color::Black);
+        bot.send(ServerMessage::GameInfoUpdate(game.id(), game))
+            .unwrap();
+
+        // The same update comes twice
+        let game = playing(Color::White);
+        for _ in 0..2 {
+            bot.send(ServerMessage::GameInfoUpdate(game.id(), game.clone()))
+                .unwrap();
+        }
+
+        let Some(ClientMessage::MakeMove(chess_move)) = wait_for_move(&mut bot) else {
+            panic!("The bot should have played");
+        };
+        assert_eq!(chess_move.color, Color::White);
+        assert!(board.legal_moves().contains(&chess_move));
+
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert!(bot.try_recv().is_err());
+        assert!(bot.is_running());
+    }
+
+    #[test]
+    fn plays_again_after_a_rejected_move() {
+        let mut bot = super::Bot::new(shared::engine::Level::Beginner.profile(), None);
+        let game = Game::new(
+            shared::id::Id::new(),
+            [
+                Some(Player::new(
+                    bot.id(),
+                    String::from("Bot"),
+                    Some(Color::White),
+                )),
+                None,
+            ],
+            State::Playing {
+                board: Board::default(),
+            },
+        );
+
+        bot.send(ServerMessage::GameInfoUpdate(game.id(), game.clone()))
+            .unwrap();
+        let Some(ClientMessage::MakeMove(chess_move)) = wait_for_move(&mut bot) else {
+            panic!("The bot should have played");
+        };
+
+        // The server refused it, the bot wants to see the game again
+        bot.send(ServerMessage::MoveResponse {
+            chess_move,
+            valid: false,
+        })
+        .unwrap();
+        assert_eq!(
+            wait_for_move(&mut bot),
+            Some(ClientMessage::GameInfoRequest(game.id()))
+        );
+
+        bot.send(ServerMessage::GameInfoUpdate(game.id(), game))
+            .unwrap();
+        assert!(matches!(
+            wait_for_move(&mut bot),
+            Some(ClientMessage::MakeMove(_))
+        ));
+    }
+}
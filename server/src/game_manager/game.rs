@@ -4,18 +4,37 @@ pub struct Game {
     // player2: Option<super::Player>,
     players: [Option<super::Player>; 2],
     state: super::State,
+    // Every accepted move, to save the game as PGN when it ends
+    moves: Vec<shared::chess::ChessMove>,
+    // What the player that created it asked for
+    options: shared::game::Options,
+    // Runs with the options' time control, the players' time is only counted while playing
+    timer: shared::game::Timer,
     lobby_sender: std::sync::mpsc::Sender<super::Player>,
 }
 
 impl Game {
     #[allow(clippy::new_without_default)]
-    pub fn new(lobby_sender: std::sync::mpsc::Sender<super::Player>) -> Self {
+    pub fn new(
+        lobby_sender: std::sync::mpsc::Sender<super::Player>,
+        mut options: shared::game::Options,
+    ) -> Self {
+        // Picked once, so every round of this game starts from the same setup, and the players can see its number
+        if let shared::game::Setup::Chess960(number) = &mut options.setup {
+            if !number.is_some_and(|number| number < shared::chess::CHESS960_SETUPS) {
+                *number = Some(random::get_inc(0, shared::chess::CHESS960_SETUPS - 1));
+            }
+        }
+
         Self {
             id: shared::id::Id::new(),
             // player1: None,
             // player2: None,
             players: [None, None],
             state: super::State::default(),
+            moves: Vec::new(),
+            timer: shared::game::Timer::new(options.time_control),
+            options,
             lobby_sender,
         }
     }
@@ -60,7 +79,8 @@ impl Game {
 
     pub fn is_active(&self) -> bool {
         // self.player1.is_some() || self.player2.is_some()
-        self.players.iter().any(|player| player.is_some())
+        // A bot left alone has no one to play with
+        self.players.iter().flatten().any(|player| !player.is_bot())
     }
 
     pub fn is_full(&self) -> bool {
@@ -68,6 +88,49 @@ impl Game {
         !self.players.iter().any(|player| player.is_none())
     }
 
+    /// Where the pieces stand when a round starts
+    fn start_board(&self) -> shared::chess::Board {
+        self.options
+            .start_board()
+            .unwrap_or_else(|| shared::chess::Board::from_variant(self.options.variant))
+    }
+
+    /// The game so far, with the players' names
+    pub fn pgn(
+        &self,
+        outcome: shared::pgn::Outcome,
+    ) -> Result<shared::pgn::Pgn, shared::error::pgn::PgnError> {
+        let player = |color| {
+            self.players
+                .iter()
+                .flatten()
+                .find(|player| player.color() == Some(color))
+                .map(shared::game::Player::from)
+                .unwrap_or_default()
+        };
+
+        let mut pgn = shared::pgn::Pgn::from_position(
+            &player(shared::chess::Color::White),
+            &player(shared::chess::Color::Black),
+            self.start_board(),
+        );
+        if let Some(time_control) = self.options.time_control.to_pgn() {
+            pgn = pgn.with_time_control(&time_control);
+        }
+        // The other variants have their own tag
+        if let (shared::game::Setup::Chess960(_), shared::chess::Variant::Standard) =
+            (self.options.setup, self.options.variant)
+        {
+            pgn.set_tag("Variant", "Chess960");
+        }
+        for chess_move in &self.moves {
+            pgn.push(*chess_move)?;
+        }
+        pgn.set_outcome(outcome);
+
+        Ok(pgn)
+    }
+
     fn set_state(&mut self, new_state: super::State) {
         debug!("Game {} state -> {:?}", self.id, new_state.variant_name());
         self.state = new_state;
@@ -112,8 +175,11 @@ impl Game {
     fn update_state(&mut self) {
         let game_image = shared::game::Game::from(&*self);
         let mut broad_update = false;
+        let mut game_end = None;
         match &mut self.state {
             super::State::PlayerDisconnected => {
+                // Nobody is there to play, the next round starts new clocks anyway
+                self.timer.stop();
                 // Explanation of why not `.flatten` can be found at Playing variant match
                 for player_opt in self.players.iter_mut() {
                     let Some(_player) = player_opt else {
@@ -229,9 +295,10 @@ impl Game {
                     }
                 }
 
-                self.set_state(super::State::Playing {
-                    board: shared::chess::Board::default(),
-                });
+                self.moves.clear();
+                let board = self.start_board();
+                self.timer.start(board.next_to_play());
+                self.set_state(super::State::Playing { board });
             }
             super::State::Playing { board } => {
                 use shared::message::{ClientMessage, ServerMessage};
@@ -247,6 +314,18 @@ impl Game {
                     It will never be caught as it's filtered out by flatten, insead just add a let else.
                 */
 
+                // The clocks give back the time the moves spend on the network
+                for player in self.players.iter_mut().flatten() {
+                    if let Some(color) = player.color() {
+                        self.timer.set_rtt(color, player.rtt());
+                    }
+                }
+
+                // The moves that come after the flag fell are refused
+                if let Some(flagged) = self.timer.flagged() {
+                    game_end = Some(shared::game::EndReason::from_timeout(board, flagged));
+                }
+
                 for player_opt in self.players.iter_mut() {
                     // Check if the player is a Some()
                     let Some(player) = player_opt else {
@@ -287,13 +366,25 @@ impl Game {
                                 break;
                             }
                             ClientMessage::MakeMove(chess_move) => {
-                                // Check validity
-
-                                let res = board.make_move(&chess_move);
-
-                                if res.is_ok() {
-                                    debug!("Move played: {chess_move:?}");
+                                // Check validity, no move can be played once the game is over, nor for the other player
+                                let res = if game_end.is_some()
+                                    || player.color() != Some(chess_move.color)
+                                {
+                                    Err(())
+                                } else {
+                                    // SAN depends on the position before the move, but is only written once it's known to be legal
+                                    let before = board.clone();
+                                    board
+                                        .make_move(&chess_move)
+                                        .map(|_| chess_move.to_san(&before))
+                                };
+
+                                if let Ok(san) = &res {
+                                    debug!("Move played: {san}");
+                                    self.timer.press();
+                                    self.moves.push(chess_move);
                                     broad_update = true;
+                                    game_end = shared::game::EndReason::from_board(board);
                                 }
 
                                 // Send validity to the player
@@ -315,7 +406,58 @@ impl Game {
                     }
                 }
             }
-            super::State::GameEnd { winner: _ } => {}
+            super::State::GameEnd { .. } => {
+                use shared::message::{ClientMessage, ServerMessage};
+
+                // The players look at the result until they leave
+                for player_opt in self.players.iter_mut() {
+                    let Some(player) = player_opt else {
+                        continue;
+                    };
+
+                    let player_id = player.id();
+
+                    while let Ok(msg) = player.try_recv() {
+                        match msg {
+                            ClientMessage::GameInfoRequest(game_id) => {
+                                let answer = if game_id == self.id {
+                                    ServerMessage::GameInfoUpdate(self.id, game_image.clone())
+                                } else {
+                                    ServerMessage::GameInfoUpdateFail(
+                                        game_id,
+                                        String::from("Wrong game !"),
+                                    )
+                                };
+                                if let Err(e) = player.send(answer) {
+                                    error!("Failled to send game update to player ({player_id}) due to: {e}")
+                                }
+                            }
+                            ClientMessage::LeaveGameRequest => {
+                                if let Err(e) = player.send(ServerMessage::GameLeave) {
+                                    error!("Could not send Gameleave confirmation to player ({}) due to {e}", player_id);
+                                }
+                                if let Err(e) = self.lobby_sender.send(player_opt.take().unwrap()) {
+                                    error!(
+                                        "Could not send back player ({}) to lobby due to {e}",
+                                        player_id
+                                    )
+                                }
+                                break;
+                            }
+                            // Too late, the game is over
+                            ClientMessage::MakeMove(chess_move) => {
+                                if let Err(e) = player.send(ServerMessage::MoveResponse {
+                                    chess_move,
+                                    valid: false,
+                                }) {
+                                    error!("Could not refuse the move of player ({player_id}) due to: {e}")
+                                }
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+            }
         }
 
         // Broadcast update
@@ -340,6 +482,25 @@ impl Game {
                 }
             }
         }
+
+        // The state update is sent to both players, with the reason
+        if let Some((reason, winner_color)) = game_end {
+            let winner = winner_color.and_then(|color| {
+                self.players
+                    .iter()
+                    .flatten()
+                    .find(|player| player.color() == Some(color))
+                    .map(|player| player.id())
+            });
+
+            debug!("Game {} ended: {reason}, winner: {winner:?}", self.id);
+            self.timer.stop();
+            match self.pgn(shared::pgn::Outcome::from_winner(winner_color)) {
+                Ok(pgn) => info!("Game {} PGN:\n{pgn}", self.id),
+                Err(e) => error!("Game {} could not be written as PGN: {e}", self.id),
+            }
+            self.set_state(super::State::GameEnd { winner, reason });
+        }
     }
 }
 
@@ -356,6 +517,8 @@ impl From<&Game> for shared::game::Game {
                 .unwrap(),
             server_game.state.clone(),
         )
+        .with_options(server_game.options.clone())
+        .with_clock(server_game.timer.clock())
     }
 }
 
@@ -372,5 +535,7 @@ impl From<&mut Game> for shared::game::Game {
                 .unwrap(),
             server_game.state.clone(),
         )
+        .with_options(server_game.options.clone())
+        .with_clock(server_game.timer.clock())
     }
 }
@@ -1,3 +1,4 @@
+mod bot;
 mod game;
 mod player;
 mod state;
@@ -13,6 +14,9 @@ pub struct GameManager {
     // used to send back player to the lobby
     lobby_receiver: std::sync::mpsc::Receiver<Player>,
     lobby_sender: std::sync::mpsc::Sender<Player>,
+
+    // Opening book of the bots
+    book: Option<std::sync::Arc<shared::engine::Book>>,
 }
 
 impl GameManager {
@@ -24,11 +28,13 @@ impl GameManager {
             players: Vec::new(),
             lobby_receiver: receiver,
             lobby_sender: sender,
+            book: bot::load_book(),
         }
     }
 
-    fn create_new_game(&mut self) -> &mut Game {
-        self.games.push(Game::new(self.lobby_sender.clone()));
+    fn create_new_game(&mut self, options: shared::game::Options) -> &mut Game {
+        self.games
+            .push(Game::new(self.lobby_sender.clone(), options));
         self.games.last_mut().unwrap() // Unless big problem, this will never panic
     }
 
@@ -233,11 +239,12 @@ impl GameManager {
                             error!("Player ({player_id}) requested a info update on game ({game_id}) but server failled to send the data: {e}", player_id = player.id())
                         }
                     }
-                    shared::message::ClientMessage::GameCreateRequest => {
-                        debug!("Player ({player_id}) requested the creation of a game");
+                    shared::message::ClientMessage::GameCreateRequest(mut options) => {
+                        debug!("Player ({player_id}) requested the creation of a game with {options:?}");
                         let moved_player = self.players.swap_remove(player_index);
 
-                        let game = self.create_new_game();
+                        options.time_control = options.time_control.clamped();
+                        let game = self.create_new_game(options);
 
                         // Here it's fine to use swap remove as the index doesn't move
                         // We only lose the player list order, which isn't important imo
@@ -250,6 +257,32 @@ impl GameManager {
 
                         break;
                     }
+                    shared::message::ClientMessage::BotGameCreateRequest(mut options) => {
+                        debug!(
+                            "Player ({player_id}) requested a game against a bot with {options:?}"
+                        );
+                        let moved_player = self.players.swap_remove(player_index);
+                        removed = true;
+
+                        options.time_control = options.time_control.clamped();
+                        // Saved in the options so the players can see it, the client's one is only a wish
+                        let profile = options
+                            .bot
+                            .map_or_else(bot::default_profile, shared::engine::Profile::clamped);
+                        options.bot = Some(profile);
+                        let book = self.book.clone();
+                        let game = self.create_new_game(options);
+
+                        if let Err(e) = game.connect_player(moved_player) {
+                            error!("Could not connect player ({player_id}) due to: {e}");
+                            break;
+                        }
+                        if let Err(e) = game.connect_player(Player::new_bot(profile, book)) {
+                            error!("Could not add a bot to the game of player ({player_id}) due to: {e}");
+                        }
+
+                        break;
+                    }
                     shared::message::ClientMessage::LeaveGameRequest => {
                         // The player is not in a game, but i can see a world where it's just states that are not synched
                         // So let's just fix that by fake removing it from an imaginary game
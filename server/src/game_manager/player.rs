@@ -1,11 +1,18 @@
 pub struct Player {
     // id: shared::id::Id,
-    client:
-        crate::networking::Client<shared::message::ClientMessage, shared::message::ServerMessage>,
+    connection: Connection,
     name: String,
     color: Option<shared::chess::Color>,
 }
 
+// Where the player's messages come from
+enum Connection {
+    Client(
+        crate::networking::Client<shared::message::ClientMessage, shared::message::ServerMessage>,
+    ),
+    Bot(super::bot::Bot),
+}
+
 impl Player {
     pub fn new(
         client: crate::networking::Client<
@@ -15,26 +22,60 @@ impl Player {
     ) -> Self {
         Self {
             name: format!("Player{}", client.id()),
-            client,
+            connection: Connection::Client(client),
+            color: None,
+        }
+    }
+
+    /// A player played by the engine, on the server
+    pub fn new_bot(
+        profile: shared::engine::Profile,
+        book: Option<std::sync::Arc<shared::engine::Book>>,
+    ) -> Self {
+        let bot = super::bot::Bot::new(profile, book);
+        Self {
+            name: format!("Bot{}", bot.id()),
+            connection: Connection::Bot(bot),
             color: None,
         }
     }
 
     pub fn id(&self) -> shared::id::Id {
-        self.client.id()
+        match &self.connection {
+            Connection::Client(client) => client.id(),
+            Connection::Bot(bot) => bot.id(),
+        }
     }
     pub fn name(&self) -> String {
         self.name.clone()
     }
 
+    pub fn is_bot(&self) -> bool {
+        matches!(self.connection, Connection::Bot(_))
+    }
+
     pub fn is_connected(&self) -> bool {
-        self.client.is_connected() && self.client.is_running()
+        match &self.connection {
+            Connection::Client(client) => client.is_connected() && client.is_running(),
+            Connection::Bot(bot) => bot.is_running(),
+        }
+    }
+
+    /// The last round trip time measured with that player, bots are on the server so they have none
+    pub fn rtt(&mut self) -> std::time::Duration {
+        match &mut self.connection {
+            Connection::Client(client) => client.stats().get_rtt(),
+            Connection::Bot(_) => std::time::Duration::ZERO,
+        }
     }
 
     pub fn try_recv(
         &mut self,
     ) -> Result<shared::message::ClientMessage, std::sync::mpsc::TryRecvError> {
-        self.client.try_recv()
+        match &mut self.connection {
+            Connection::Client(client) => client.try_recv(),
+            Connection::Bot(bot) => bot.try_recv(),
+        }
     }
 
     pub fn send(
@@ -42,7 +83,14 @@ impl Player {
         msg: shared::message::ServerMessage,
     ) -> Result<(), std::boxed::Box<std::sync::mpsc::SendError<shared::message::ServerMessage>>>
     {
-        Ok(self.client.send(msg)?)
+        match &mut self.connection {
+            Connection::Client(client) => Ok(client.send(msg)?),
+            Connection::Bot(bot) => bot.send(msg),
+        }
+    }
+
+    pub fn color(&self) -> Option<shared::chess::Color> {
+        self.color
     }
 
     pub fn set_color(&mut self, color: shared::chess::Color) {
@@ -13,7 +13,8 @@ impl<R: networking::Message + 'static, W: networking::Message + 'static> Client<
                 bps: networking::stats::config::BpsConfig { enabled: false },
                 rtt: networking::stats::config::RttConfig {
                     enabled: true,
-                    ping_request_delay: std::time::Duration::from_secs(10),
+                    // Often enough for the clocks' lag compensation to follow the link
+                    ping_request_delay: std::time::Duration::from_secs(1),
                 },
             },
             keep_msg_while_disconnected: false,
@@ -37,6 +38,11 @@ impl<R: networking::Message + 'static, W: networking::Message + 'static> Client<
         self.id
     }
 
+    pub fn stats(&mut self) -> &networking::NetworkStats<R, W> {
+        // needs mutable as it updates before reading
+        self.controller.stats()
+    }
+
     pub fn try_recv(&mut self) -> Result<R, std::sync::mpsc::TryRecvError> {
         match self.controller.try_recv()? {
             networking::proxy::ProxyMessage::Forward(msg) => Ok(msg),
@@ -0,0 +1,90 @@
+// Move generation benchmarks, run them with `cargo bench -p shared`
+//
+// Before the attack tables, the generator walked the relative moves loaded from RON and checked every ray with
+// `Board::read`. Release build, same machine:
+//
+//                                 RON + rays          attack tables
+//     perft 4, start position     175 ms (1.1M n/s)   35 ms (5.6M n/s)
+//     perft 3, Kiwipete           58 ms (1.7M n/s)    8 ms (11.7M n/s)
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use shared::chess::{BitBoard, Board, Position, STARTING_FEN};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn perft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft");
+    group.sample_size(20);
+
+    for (name, fen, depth) in [
+        ("start position, depth 4", STARTING_FEN, 4),
+        ("kiwipete, depth 3", KIWIPETE, 3),
+    ] {
+        let board = Board::from_fen(fen).unwrap();
+        group.bench_function(name, |b| b.iter(|| board.perft(black_box(depth))));
+    }
+
+    group.finish();
+}
+
+fn legal_moves(c: &mut Criterion) {
+    let board = Board::from_fen(KIWIPETE).unwrap();
+    c.bench_function("legal moves, kiwipete", |b| {
+        b.iter(|| black_box(&board).legal_moves())
+    });
+}
+
+/// What the old generator did for sliders, one square at a time
+fn ray_walk(pos: Position, occupancy: BitBoard) -> BitBoard {
+    let mut out = BitBoard::default();
+
+    for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+        let (mut x, mut y) = (pos.file().to_index() as i8, pos.rank().to_index() as i8);
+
+        loop {
+            x += dx;
+            y += dy;
+            let Some(next) = Position::from_index(x as u8, y as u8).filter(|_| x >= 0 && y >= 0)
+            else {
+                break;
+            };
+
+            out.set(next);
+            if occupancy.read(next) {
+                break;
+            }
+        }
+    }
+
+    out
+}
+
+fn rook_attacks(c: &mut Criterion) {
+    let board = Board::from_fen(KIWIPETE).unwrap();
+    let occupancy = board.occupancy();
+    let squares = (0..64)
+        .map(|i| Position::from_index(i % 8, i / 8).unwrap())
+        .collect::<Vec<Position>>();
+
+    let mut group = c.benchmark_group("rook attacks, every square");
+
+    group.bench_function("ray walk", |b| {
+        b.iter(|| {
+            for pos in &squares {
+                black_box(ray_walk(*pos, black_box(occupancy)));
+            }
+        })
+    });
+    group.bench_function("attack tables", |b| {
+        b.iter(|| {
+            for pos in &squares {
+                black_box(BitBoard::rook_attacks(*pos, black_box(occupancy)));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, perft, legal_moves, rook_attacks);
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+// Makes an opening book for the bots out of a directory of PGN files
+//
+//     cargo run --release -p shared --bin make_book -- <pgn directory> <book> [plies]
+//
+// The bots look for their books in `resources/external`, only the first `plies` moves of each game are kept (20 by
+// default)
+
+use shared::engine::BookBuilder;
+
+const USAGE: &str = "Usage: make_book <pgn directory> <book> [plies]";
+const DEFAULT_PLIES: usize = 20;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+
+    let (Some(dir), Some(output)) = (args.next(), args.next()) else {
+        eprintln!("{USAGE}");
+        std::process::exit(1);
+    };
+
+    let plies = match args.next().map(|plies| plies.parse::<usize>()) {
+        None => DEFAULT_PLIES,
+        Some(Ok(plies)) => plies,
+        Some(Err(e)) => {
+            eprintln!("{USAGE}\nThe number of plies is invalid: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut builder = BookBuilder::new(plies);
+    let games = match builder.add_dir(std::path::Path::new(&dir)) {
+        Ok(games) => games,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    let book = builder.build();
+    if let Err(e) = std::fs::write(&output, book.to_bytes()) {
+        eprintln!("Could not write {output}: {e}");
+        std::process::exit(1);
+    }
+
+    println!("{games} games, {} entries written to {output}", book.len());
+}
@@ -0,0 +1,57 @@
+// Prints the perft of every legal move, to diff the move generation against a reference engine
+// The arguments and the output follow what perftree (https://github.com/agausmann/perftree) expects:
+//
+//     cargo run --release -p shared --bin perft -- <depth> [fen] [moves]
+//
+// The moves are written in UCI notation ("e2e4 e7e5"), they are played from the FEN before counting
+
+use shared::chess::{Board, ChessMove, STARTING_FEN};
+
+const USAGE: &str = "Usage: perft <depth> [fen] [moves]";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+
+    let Some(depth) = args
+        .next()
+        .and_then(|depth| depth.parse::<u32>().ok())
+        .filter(|depth| *depth > 0)
+    else {
+        eprintln!("{USAGE}\nThe depth has to be a number bigger than 0");
+        std::process::exit(1);
+    };
+
+    let fen = args.next().unwrap_or(STARTING_FEN.to_string());
+    let mut board = match Board::from_fen(&fen) {
+        Ok(board) => board,
+        Err(e) => {
+            eprintln!("{USAGE}\nCould not read the FEN: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    // perftree gives all the moves in a single argument
+    let moves = args.collect::<Vec<String>>().join(" ");
+    for uci in moves.split_whitespace() {
+        let played = ChessMove::from_uci(uci, &board)
+            .map_err(|e| e.to_string())
+            .and_then(|mv| {
+                board
+                    .make_move(&mv)
+                    .map_err(|()| format!("The move '{uci}' could not be played"))
+            });
+
+        if let Err(e) = played {
+            eprintln!("{USAGE}\n{e}");
+            std::process::exit(1);
+        }
+    }
+
+    let divide = board.divide(depth);
+
+    for (mv, nodes) in &divide {
+        println!("{mv} {nodes}");
+    }
+    println!();
+    println!("{}", divide.iter().map(|(_, nodes)| nodes).sum::<u64>());
+}
@@ -0,0 +1,210 @@
+// Questions about a position that don't need the move generation, for the engine and for anyone looking at a board
+// https://www.chessprogramming.org/Static_Exchange_Evaluation
+
+// Worth of the pieces for the exchanges, indexed by `Piece::to_index`, the king is only taken last
+const SEE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 20_000];
+
+fn see_value(piece: super::Piece) -> i32 {
+    SEE_VALUES[piece.to_index()]
+}
+
+impl super::Board {
+    /// Pieces of the given color attacking `square`, whatever is standing on it
+    pub fn attackers_of(&self, square: super::Position, color: super::Color) -> super::BitBoard {
+        super::movegen::attackers(self, square, color, self.occupancy())
+    }
+
+    /// The enemy pieces giving check to the player that has to play, two of them for a double check
+    pub fn checkers(&self) -> super::BitBoard {
+        super::movegen::checkers(self, self.next_to_play())
+    }
+
+    /// Pieces of the given color that can't leave the line between their king and an enemy slider
+    pub fn pinned_pieces(&self, color: super::Color) -> super::BitBoard {
+        let mut out = super::BitBoard::default();
+        for (pos, _) in super::movegen::pins(self, color) {
+            out.set(pos);
+        }
+        out
+    }
+
+    /// Squares attacked by the piece on `square`, including the ones of its own color, empty if there is none
+    pub fn attacks_from(&self, square: super::Position) -> super::BitBoard {
+        let Some((color, piece)) = self.piece_at(square) else {
+            return super::BitBoard::default();
+        };
+
+        super::movegen::attacks_from(piece, color, square, self.occupancy())
+    }
+
+    /// Material won by the player making the move once all the captures on its target square are done, in centipawns
+    /// Both sides always take back with their least valuable piece and stop when it would lose material, pins are
+    /// ignored
+    pub fn see(&self, mv: &super::ChessMove) -> i32 {
+        let target = mv.target;
+        let mut occupancy = self.occupancy();
+
+        let captured = if mv.is_en_passant(self) {
+            // The eaten pawn is behind the target square
+            occupancy.unset(super::Position::from_file_rank(
+                target.file(),
+                mv.origin.rank(),
+            ));
+            Some(super::Piece::Pawn)
+        } else {
+            self.piece_at(target).map(|(_, piece)| piece)
+        };
+
+        let mut on_square = mv.piece;
+        let mut first = captured.map(see_value).unwrap_or(0);
+        if let Some(promotion) = mv.promotion {
+            first += see_value(promotion) - see_value(super::Piece::Pawn);
+            on_square = promotion;
+        }
+
+        // What each side would have after each capture if the other one stopped there
+        let mut gains = vec![first];
+        occupancy.unset(mv.origin);
+        let mut side = !mv.color;
+
+        loop {
+            // Removing the pieces from the occupancy lets the sliders behind them join in
+            let attackers = super::movegen::attackers(self, target, side, occupancy) & occupancy;
+            let Some((piece, from)) = super::piece::ALL_PIECES.into_iter().find_map(|piece| {
+                (attackers & self.get_bb(piece))
+                    .positions()
+                    .next()
+                    .map(|pos| (piece, pos))
+            }) else {
+                break;
+            };
+
+            gains.push(see_value(on_square) - gains[gains.len() - 1]);
+            occupancy.unset(from);
+            on_square = piece;
+            side = !side;
+        }
+
+        // Going back, each side can choose to not take
+        while gains.len() > 1 {
+            let last = gains.pop().unwrap();
+            let previous = gains.last_mut().unwrap();
+            *previous = -(-*previous).max(last);
+        }
+
+        gains[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{BitBoard, Board, ChessMove, Color, Position};
+
+    fn squares(names: &[&str]) -> BitBoard {
+        let mut out = BitBoard::default();
+        for name in names {
+            out.set(Position::from_algebraic(name).unwrap());
+        }
+        out
+    }
+
+    fn exchange(fen: &str, uci: &str) -> i32 {
+        let board = Board::from_fen(fen).unwrap();
+        let mv = ChessMove::from_uci(uci, &board).unwrap();
+        let see = board.see(&mv);
+        println!("{fen} {uci}: {see}");
+        see
+    }
+
+    #[test]
+    fn attackers_of() {
+        // The bishop on h1 is behind the e4 pawn
+        let board = Board::from_fen("3rk3/8/1n6/3p4/4P3/2N5/8/3RK2B w - - 0 1").unwrap();
+        let d5 = Position::from_algebraic("d5").unwrap();
+
+        assert_eq!(
+            board.attackers_of(d5, Color::White),
+            squares(&["e4", "c3", "d1"])
+        );
+        assert_eq!(board.attackers_of(d5, Color::Black), squares(&["d8", "b6"]));
+
+        // Defenders count too, the bishop sees the pawn that blocked it
+        let e4 = Position::from_algebraic("e4").unwrap();
+        assert_eq!(board.attackers_of(e4, Color::White), squares(&["c3", "h1"]));
+        assert_eq!(board.attackers_of(e4, Color::Black), squares(&["d5"]));
+
+        // Nothing needs to stand on the square
+        let e5 = Position::from_algebraic("e5").unwrap();
+        assert!(board.attackers_of(e5, Color::White).is_empty());
+        assert!(board.attackers_of(e5, Color::Black).is_empty());
+    }
+
+    #[test]
+    fn checkers() {
+        let board = Board::default();
+        assert!(!board.is_in_check());
+        assert!(board.checkers().is_empty());
+
+        let board = Board::from_fen("4k3/8/8/8/8/3n4/8/4K3 w - - 0 1").unwrap();
+        assert!(board.is_in_check());
+        assert_eq!(board.checkers(), squares(&["d3"]));
+
+        let board = Board::from_fen("4k3/8/8/8/1b6/8/8/r3K3 w - - 0 1").unwrap();
+        assert!(board.is_in_check());
+        assert_eq!(board.checkers(), squares(&["a1", "b4"]));
+
+        let board = Board::from_fen("4k3/8/3N4/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert!(board.is_in_check());
+        assert_eq!(board.checkers(), squares(&["d6"]));
+    }
+
+    #[test]
+    fn pinned_pieces() {
+        // Two pieces between the h4 bishop and the king, none of them is pinned
+        let board = Board::from_fen("4k3/4r3/8/8/1b5b/4R1P1/3N1P2/4K3 w - - 0 1").unwrap();
+
+        assert_eq!(board.pinned_pieces(Color::White), squares(&["d2", "e3"]));
+        assert_eq!(board.pinned_pieces(Color::Black), squares(&["e7"]));
+    }
+
+    #[test]
+    fn attacks_from() {
+        let board = Board::from_fen("4k3/4r3/8/8/1b5b/4R1P1/3N1P2/4K3 w - - 0 1").unwrap();
+        let at = |name| board.attacks_from(Position::from_algebraic(name).unwrap());
+
+        // Stops on the first piece, whatever its color
+        assert_eq!(
+            at("e3"),
+            squares(&["e1", "e2", "e4", "e5", "e6", "e7", "d3", "c3", "b3", "a3", "f3", "g3"])
+        );
+        assert_eq!(at("d2"), squares(&["b1", "b3", "c4", "e4", "f3", "f1"]));
+        assert_eq!(at("f2"), squares(&["e3", "g3"]));
+        assert_eq!(
+            at("e7"),
+            squares(&["e8", "e6", "e5", "e4", "e3", "d7", "c7", "b7", "a7", "f7", "g7", "h7"])
+        );
+        assert!(at("a1").is_empty());
+    }
+
+    #[test]
+    fn see() {
+        // Hanging
+        assert_eq!(exchange("4k3/8/8/3p4/8/8/8/3QK3 w - - 0 1", "d1d5"), 100);
+        // The queen is taken back by a pawn
+        assert_eq!(exchange("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1", "d1d5"), -800);
+        // Pawn for pawn
+        assert_eq!(exchange("4k3/8/4p3/3p4/4P3/8/8/4K3 w - - 0 1", "e4d5"), 0);
+        // Nothing to take and nothing attacking the square
+        assert_eq!(exchange("4k3/8/8/8/8/8/3R4/3QK3 w - - 0 1", "d2d5"), 0);
+
+        // The rook alone loses itself for a pawn, with the other one behind it the pawn is won
+        assert_eq!(exchange("3rk3/8/8/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"), -400);
+        assert_eq!(exchange("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"), 100);
+
+        // After the rook takes back, the knight is taken back by the queen
+        assert_eq!(exchange("3rk3/8/8/3p4/8/2N5/8/3QK3 w - - 0 1", "c3d5"), 100);
+
+        assert_eq!(exchange("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 100);
+        assert_eq!(exchange("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8q"), 1300);
+    }
+}
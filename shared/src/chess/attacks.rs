@@ -0,0 +1,406 @@
+// Precomputed attacks
+// The knight, king and pawn tables are built at compile time
+// The sliders use fancy magic bitboards, or BMI2's PEXT when the target supports it, their tables are built on first use
+// https://www.chessprogramming.org/Magic_Bitboards
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+const KNIGHT_ATTACKS: [u64; 64] = leaper_table(&[
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+]);
+
+const KING_ATTACKS: [u64; 64] = leaper_table(&[
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+]);
+
+const WHITE_PAWN_ATTACKS: [u64; 64] = leaper_table(&[(-1, 1), (1, 1)]);
+const BLACK_PAWN_ATTACKS: [u64; 64] = leaper_table(&[(-1, -1), (1, -1)]);
+
+// Found with the `find_magics` test, they are only used when PEXT is not available
+#[rustfmt::skip]
+const ROOK_MAGICS: [u64; 64] = [
+    0x1080004008801020, 0x0840092002c03000, 0x1900200010400900, 0x0880100008000480,
+    0x4200100420080200, 0x8100020100080400, 0x0200040110886200, 0x0200008040220411,
+    0x0404800084400220, 0x0000401000402000, 0x0086001081220440, 0x0408800800100280,
+    0x000a001201040820, 0x8848800200840080, 0x4001000100040200, 0x0442000102105084,
+    0x9080010020804100, 0x0040404000201009, 0x0000808010002009, 0x2200090021d00100,
+    0x0008008008040080, 0x0004004002010040, 0x0011040008015042, 0x00000a0001768104,
+    0x0000800080204009, 0x2010004140002001, 0x9800200280100080, 0x1000100080080080,
+    0x0442000a00049020, 0x2100040080020080, 0x0800120400900148, 0x0010040a00128541,
+    0x2800804000800030, 0x1010002000400041, 0x4000200011004100, 0x0610008410800800,
+    0x0400802402800800, 0xc100020080800400, 0x0002000802000401, 0x0182085882000401,
+    0x0220204000808000, 0x2860100040024022, 0x0001002004110040, 0x99101042000a0020,
+    0x0004080004008080, 0x0010040002008080, 0x2012004881020004, 0x8300842444820011,
+    0x0088403882010200, 0x0820400080210100, 0x0110910040a00300, 0x0801100280080480,
+    0x0242009008200600, 0x1002000489500200, 0x0040800200010080, 0x0091800041000080,
+    0x0000209300488001, 0x04c1002414824001, 0x020020000b001041, 0x7000100004200901,
+    0x8002002004100802, 0x30010002084c0007, 0x0888221800813004, 0x4000002840840112,
+];
+
+#[rustfmt::skip]
+const BISHOP_MAGICS: [u64; 64] = [
+    0xa010041108003100, 0x006082020a002900, 0x6810010619200000, 0x08281a0520000408,
+    0x0001104001000400, 0x0018901008048400, 0x00040a0210245280, 0x000200210808a402,
+    0x9140048410821200, 0x0800091010820041, 0x20504804832202c0, 0x0100091401081000,
+    0x8021011140000012, 0x0810020804450400, 0x208b0542109008a2, 0x0080084a08040204,
+    0x0040e2a80811244c, 0x2505022008008108, 0x0430220100420040, 0x010a040420220040,
+    0x1105000290400000, 0x0093001200822120, 0x4000a62048043004, 0x280120048a015004,
+    0x006090002a020814, 0x44042000240800d0, 0x01102800040a4400, 0x1004080080220040,
+    0x0001001011004024, 0x0010044000805040, 0x0914041200820100, 0x0004821012821480,
+    0x0024040500c05021, 0x0088611002080200, 0x0116080a00040020, 0x4000020080080080,
+    0x2450450140840040, 0x0000880201484100, 0x0222020404020092, 0x8081110600002e00,
+    0x2842101105000801, 0x1100809008001025, 0x00020202221c0400, 0x0422014022009020,
+    0x0210046102100c00, 0xc004008082029102, 0x00aa461801101200, 0x0404080080201108,
+    0x020542108c205002, 0x0410544804100100, 0x0040910841100000, 0x0400200042021100,
+    0x00004204850400c0, 0x0200100410a42102, 0x1040020801210102, 0x0805040410420000,
+    0x2884804130100200, 0x800c262201242000, 0x1058000194108800, 0x0014221054420204,
+    0x0104000012a02200, 0x0200881003300100, 0x0140400202840100, 0x0402020801010201,
+];
+
+lazy_static::lazy_static! {
+    static ref ROOK_TABLE: SliderTable = SliderTable::new(&ROOK_DIRECTIONS, &ROOK_MAGICS);
+    static ref BISHOP_TABLE: SliderTable = SliderTable::new(&BISHOP_DIRECTIONS, &BISHOP_MAGICS);
+}
+
+/// Every attack set of a slider, for every square and every occupancy that matters
+struct SliderTable {
+    magics: [Magic; 64],
+    attacks: Vec<u64>,
+}
+
+struct Magic {
+    // Squares that can block the slider, the edges don't matter as the ray stops there anyway
+    mask: u64,
+    // Only the multiply and shift index reads them, PEXT only needs the mask
+    #[cfg_attr(all(target_arch = "x86_64", target_feature = "bmi2"), allow(dead_code))]
+    magic: u64,
+    #[cfg_attr(all(target_arch = "x86_64", target_feature = "bmi2"), allow(dead_code))]
+    shift: u32,
+    // Where the square's attacks start in the table
+    offset: usize,
+}
+
+impl super::BitBoard {
+    pub fn knight_attacks(pos: super::Position) -> Self {
+        Self::from(KNIGHT_ATTACKS[pos.to_index() as usize])
+    }
+
+    pub fn king_attacks(pos: super::Position) -> Self {
+        Self::from(KING_ATTACKS[pos.to_index() as usize])
+    }
+
+    /// The two squares in front of the pawn, diagonally
+    pub fn pawn_attacks(pos: super::Position, color: super::Color) -> Self {
+        let table = match color {
+            super::Color::White => &WHITE_PAWN_ATTACKS,
+            super::Color::Black => &BLACK_PAWN_ATTACKS,
+        };
+        Self::from(table[pos.to_index() as usize])
+    }
+
+    /// The rays stop on the first piece of `occupancy`, that square included
+    pub fn bishop_attacks(pos: super::Position, occupancy: super::BitBoard) -> Self {
+        Self::from(BISHOP_TABLE.attacks(pos.to_index() as usize, u64::from(occupancy)))
+    }
+
+    /// The rays stop on the first piece of `occupancy`, that square included
+    pub fn rook_attacks(pos: super::Position, occupancy: super::BitBoard) -> Self {
+        Self::from(ROOK_TABLE.attacks(pos.to_index() as usize, u64::from(occupancy)))
+    }
+
+    pub fn queen_attacks(pos: super::Position, occupancy: super::BitBoard) -> Self {
+        Self::bishop_attacks(pos, occupancy) | Self::rook_attacks(pos, occupancy)
+    }
+
+    /// Squares strictly between two positions, empty if they are not on the same line
+    pub fn between(a: super::Position, b: super::Position) -> Self {
+        let (a_bb, b_bb) = (Self::from(a), Self::from(b));
+
+        if !(Self::rook_attacks(a, Self::default()) & b_bb).is_empty() {
+            Self::rook_attacks(a, b_bb) & Self::rook_attacks(b, a_bb)
+        } else if !(Self::bishop_attacks(a, Self::default()) & b_bb).is_empty() {
+            Self::bishop_attacks(a, b_bb) & Self::bishop_attacks(b, a_bb)
+        } else {
+            Self::default()
+        }
+    }
+}
+
+impl SliderTable {
+    fn new(directions: &[(i8, i8)], magics: &[u64; 64]) -> Self {
+        let mut attacks = Vec::new();
+
+        let magics = std::array::from_fn(|square| {
+            let mask = relevant_occupancy(square, directions);
+            let bits = mask.count_ones();
+
+            let magic = Magic {
+                mask,
+                magic: magics[square],
+                shift: 64 - bits,
+                offset: attacks.len(),
+            };
+
+            attacks.resize(attacks.len() + (1 << bits), 0);
+
+            for occupancy in subsets(mask) {
+                attacks[magic.offset + magic.index(occupancy)] =
+                    slide(square, directions, occupancy);
+            }
+
+            magic
+        });
+
+        Self { magics, attacks }
+    }
+
+    fn attacks(&self, square: usize, occupancy: u64) -> u64 {
+        let magic = &self.magics[square];
+        self.attacks[magic.offset + magic.index(occupancy)]
+    }
+}
+
+impl Magic {
+    #[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+    fn index(&self, occupancy: u64) -> usize {
+        // SAFETY: The target is compiled with BMI2
+        unsafe { std::arch::x86_64::_pext_u64(occupancy, self.mask) as usize }
+    }
+
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+    fn index(&self, occupancy: u64) -> usize {
+        ((occupancy & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+const fn leaper_table(offsets: &[(i8, i8)]) -> [u64; 64] {
+    let mut table = [0; 64];
+
+    // No for loops in const fns
+    let mut square = 0;
+    while square < 64 {
+        let mut i = 0;
+        while i < offsets.len() {
+            let file = (square % 8) as i8 + offsets[i].0;
+            let rank = (square / 8) as i8 + offsets[i].1;
+
+            if file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                table[square] |= 1 << (rank * 8 + file);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+
+    table
+}
+
+/// Walks the rays one square at a time, only used to fill the tables
+fn slide(square: usize, directions: &[(i8, i8)], occupancy: u64) -> u64 {
+    let mut out = 0;
+
+    for (dx, dy) in directions {
+        let (mut file, mut rank) = ((square % 8) as i8, (square / 8) as i8);
+
+        loop {
+            file += dx;
+            rank += dy;
+            if !(0..8).contains(&file) || !(0..8).contains(&rank) {
+                break;
+            }
+
+            let bit = 1 << (rank * 8 + file);
+            out |= bit;
+            if occupancy & bit != 0 {
+                break;
+            }
+        }
+    }
+
+    out
+}
+
+/// The rays without their last square
+fn relevant_occupancy(square: usize, directions: &[(i8, i8)]) -> u64 {
+    let mut out = 0;
+
+    for (dx, dy) in directions {
+        let (mut file, mut rank) = ((square % 8) as i8 + dx, (square / 8) as i8 + dy);
+
+        while (0..8).contains(&(file + dx)) && (0..8).contains(&(rank + dy)) {
+            out |= 1 << (rank * 8 + file);
+            file += dx;
+            rank += dy;
+        }
+    }
+
+    out
+}
+
+/// Every subset of the mask, using the Carry-Rippler trick
+fn subsets(mask: u64) -> impl Iterator<Item = u64> {
+    let mut next = Some(0u64);
+
+    std::iter::from_fn(move || {
+        let current = next?;
+        let following = current.wrapping_sub(mask) & mask;
+        next = (following != 0).then_some(following);
+        Some(current)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{BitBoard, Color, Position};
+
+    fn bb(squares: &[&str]) -> BitBoard {
+        let mut out = BitBoard::default();
+        for square in squares {
+            out.set(Position::from_algebraic(square).unwrap());
+        }
+        out
+    }
+
+    fn pos(square: &str) -> Position {
+        Position::from_algebraic(square).unwrap()
+    }
+
+    #[test]
+    fn leapers() {
+        println!("{}", BitBoard::knight_attacks(pos("b1")));
+        assert_eq!(BitBoard::knight_attacks(pos("b1")), bb(&["a3", "c3", "d2"]));
+        assert_eq!(BitBoard::knight_attacks(pos("e4")).count(), 8);
+
+        assert_eq!(BitBoard::king_attacks(pos("a1")), bb(&["a2", "b1", "b2"]));
+        assert_eq!(BitBoard::king_attacks(pos("e4")).count(), 8);
+
+        assert_eq!(BitBoard::pawn_attacks(pos("a2"), Color::White), bb(&["b3"]));
+        assert_eq!(
+            BitBoard::pawn_attacks(pos("e7"), Color::Black),
+            bb(&["d6", "f6"])
+        );
+    }
+
+    #[test]
+    fn sliders() {
+        let occupancy = bb(&["e6", "c4", "e2", "g2"]);
+
+        println!("{}", BitBoard::rook_attacks(pos("e4"), occupancy));
+        assert_eq!(
+            BitBoard::rook_attacks(pos("e4"), occupancy),
+            bb(&["e5", "e6", "e3", "e2", "d4", "c4", "f4", "g4", "h4"])
+        );
+
+        println!("{}", BitBoard::bishop_attacks(pos("e4"), occupancy));
+        assert_eq!(
+            BitBoard::bishop_attacks(pos("e4"), occupancy),
+            bb(&["d5", "c6", "b7", "a8", "f5", "g6", "h7", "d3", "c2", "b1", "f3", "g2"])
+        );
+
+        assert_eq!(
+            BitBoard::queen_attacks(pos("a1"), BitBoard::default()).count(),
+            21
+        );
+    }
+
+    #[test]
+    fn between() {
+        assert_eq!(BitBoard::between(pos("a1"), pos("a4")), bb(&["a2", "a3"]));
+        assert_eq!(BitBoard::between(pos("h8"), pos("e5")), bb(&["g7", "f6"]));
+        assert_eq!(BitBoard::between(pos("e1"), pos("f1")), BitBoard::default());
+        assert_eq!(BitBoard::between(pos("a1"), pos("b3")), BitBoard::default());
+    }
+
+    // Checks every entry of the tables against the slow ray walk
+    #[test]
+    fn magics() {
+        for (table, directions) in [
+            (&*super::ROOK_TABLE, super::ROOK_DIRECTIONS),
+            (&*super::BISHOP_TABLE, super::BISHOP_DIRECTIONS),
+        ] {
+            for (square, magic) in table.magics.iter().enumerate() {
+                for occupancy in super::subsets(magic.mask) {
+                    // The squares outside of the mask must not change anything
+                    let noise = !magic.mask & 0x8100_0000_0000_0081;
+                    assert_eq!(
+                        table.attacks(square, occupancy | noise),
+                        super::slide(square, &directions, occupancy),
+                        "square {square}, occupancy {occupancy:#x}"
+                    );
+                }
+            }
+        }
+    }
+
+    // Prints new magics, run with `cargo test --release find_magics -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn find_magics() {
+        // xorshift64*, the magics are the same every run
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        let mut random = move || {
+            state ^= state >> 12;
+            state ^= state << 25;
+            state ^= state >> 27;
+            state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+        };
+
+        for (name, directions) in [
+            ("ROOK_MAGICS", super::ROOK_DIRECTIONS),
+            ("BISHOP_MAGICS", super::BISHOP_DIRECTIONS),
+        ] {
+            println!("const {name}: [u64; 64] = [");
+
+            for square in 0..64 {
+                let mask = super::relevant_occupancy(square, &directions);
+                let shift = 64 - mask.count_ones();
+                let entries = super::subsets(mask)
+                    .map(|occupancy| (occupancy, super::slide(square, &directions, occupancy)))
+                    .collect::<Vec<(u64, u64)>>();
+
+                let magic = loop {
+                    // Few bits set make better magics
+                    let magic = random() & random() & random();
+                    if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+                        continue;
+                    }
+
+                    let mut table = vec![None; 1 << (64 - shift)];
+                    let valid = entries.iter().all(|(occupancy, attacks)| {
+                        let slot = &mut table[(occupancy.wrapping_mul(magic) >> shift) as usize];
+                        match slot {
+                            Some(other) => other == attacks,
+                            None => {
+                                *slot = Some(*attacks);
+                                true
+                            }
+                        }
+                    });
+
+                    if valid {
+                        break magic;
+                    }
+                };
+
+                println!("    {magic:#018x},");
+            }
+
+            println!("];");
+        }
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Copy, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Copy, Clone, PartialEq, Default, Hash, serde::Serialize, serde::Deserialize)]
 pub struct BitBoard(u64);
 
 // format!(":b", self.0) displays the raw bytes of the stored value
@@ -43,6 +43,23 @@ impl BitBoard {
     pub fn count(&self) -> u32 {
         self.0.count_ones()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterates over every position that is set in this [`BitBoard`], from A1 to H8
+    pub fn positions(&self) -> impl Iterator<Item = super::Position> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let index = bits.trailing_zeros() as u8;
+            bits &= bits - 1;
+            super::Position::from_index(index % 8, index / 8)
+        })
+    }
 }
 
 impl std::fmt::Debug for BitBoard {
@@ -81,6 +98,18 @@ impl From<u64> for BitBoard {
     }
 }
 
+impl From<BitBoard> for u64 {
+    fn from(bb: BitBoard) -> Self {
+        bb.0
+    }
+}
+
+impl From<super::Position> for BitBoard {
+    fn from(position: super::Position) -> Self {
+        BitBoard(1 << position.to_index())
+    }
+}
+
 // impl std::ops::Deref for BitBoard {
 //     type Target = u64;
 
@@ -1,82 +1,457 @@
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Clone)]
+#[serde(into = "BoardRepr", try_from = "BoardRepr")]
 pub struct Board {
     active_player: super::Color,
-    pub white_bb: super::BitBoard,
-    pub black_bb: super::BitBoard,
+    castle_rights: super::CastleRights,
+    en_passant: Option<super::Position>,
+    // Half moves since the last capture or pawn move
+    halfmove_clock: u32,
+    // Starts at 1 and goes up after each black move
+    fullmove_number: u32,
+    // Keys of every position before the current one, `unmake_move` takes them back off
+    // Only the last `halfmove_clock` ones can come back, they're the only ones sent over the network
+    history: Vec<u64>,
+    // Indexed by `Piece::to_index` and `Color::to_index`
+    pieces: [super::BitBoard; 6],
+    colors: [super::BitBoard; 2],
+    // What stands on each square, indexed by `Position::to_index`
+    mailbox: [Option<(super::Color, super::Piece)>; 64],
+    // Zobrist key of the position, kept up to date by `set`, `unset` and `play`
+    hash: u64,
+    variant: super::Variant,
+    // Checks given by each player, only counted in three-check
+    checks: [u8; 2],
+    // Pieces each player can drop in crazyhouse, indexed by `Color::to_index` and `Piece::to_index`
+    pockets: [[u8; 5]; 2],
+    // Pieces that were pawns, they go back to the pocket as pawns when they get taken
+    promoted: super::BitBoard,
+}
+
+/// What a move changed that can't be found back from the move itself, given by `make_move` to undo it with
+/// `unmake_move`
+#[derive(Debug, Clone, Copy)]
+pub struct Undo {
+    mv: super::ChessMove,
+    captured: Option<super::Piece>,
+    // The king may have landed on the rook's square in Chess960, the move alone can't tell anymore
+    castle: Option<super::CastleSide>,
+    castle_rights: super::CastleRights,
+    en_passant: Option<super::Position>,
+    halfmove_clock: u32,
+    checks: [u8; 2],
+    pockets: [[u8; 5]; 2],
+    promoted: super::BitBoard,
+    hash: u64,
+}
 
+/// What goes on the wire, it keeps the layout the board had before the arrays so both sides don't need to be updated
+/// at the same time
+/// The fields added since then default to a standard game, and `version` tells which of them the sender knew about
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BoardRepr {
+    #[serde(default)]
+    version: u8,
+    active_player: super::Color,
+    castle_rights: super::CastleRights,
+    en_passant: Option<super::Position>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    history: Vec<u64>,
+    white_bb: super::BitBoard,
+    black_bb: super::BitBoard,
     piece_bb: std::collections::HashMap<super::Piece, super::BitBoard>,
+    // Since version 1
+    #[serde(default)]
+    variant: super::Variant,
+    #[serde(default)]
+    checks: [u8; 2],
+    #[serde(default)]
+    pockets: [[u8; 5]; 2],
+    #[serde(default)]
+    promoted: super::BitBoard,
 }
 
+/// The latest `BoardRepr` layout, 0 is the one from before the variants
+const WIRE_VERSION: u8 = 1;
+
+/// The standard starting position
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
 impl Board {
-    pub fn from_fen(fen: &str) -> Option<Self> {
+    pub fn from_fen(fen: &str) -> Result<Self, crate::error::chess::FenError> {
+        Self::from_variant_fen(fen, super::Variant::Standard)
+    }
+
+    /// The starting position of a variant
+    pub fn from_variant(variant: super::Variant) -> Self {
+        Self::from_variant_fen(variant.start_fen(), variant).unwrap()
+    }
+
+    /// A position played with the rules of a variant, three-check positions can give the checks each player still has
+    /// to give after the en passant square, like "3+3"
+    /// Crazyhouse positions can give the pockets after the pieces, like "[QNp]", and mark promoted pieces with a '~'
+    pub fn from_variant_fen(
+        fen: &str,
+        variant: super::Variant,
+    ) -> Result<Self, crate::error::chess::FenError> {
+        use crate::error::chess::FenError;
+
         let mut board = Self {
-            active_player: super::Color::default(), // White always starts (Unless the FEN string says otherwise)
-            white_bb: super::BitBoard::default(),
-            black_bb: super::BitBoard::default(),
-            piece_bb: std::collections::HashMap::default(),
+            variant,
+            ..Self::empty()
         };
 
-        // Initialize the pieces bitboards
-        for piece in super::piece::ALL_PIECES {
-            board.piece_bb.insert(piece, super::BitBoard::default());
+        let mut tokens = fen.split_whitespace().collect::<Vec<&str>>();
+
+        if variant == super::Variant::ThreeCheck
+            && tokens.get(4).is_some_and(|token| token.contains('+'))
+        {
+            let remaining = tokens.remove(4);
+            board.checks = remaining
+                .split_once('+')
+                .and_then(|(white, black)| {
+                    let given = |left: &str| {
+                        super::variant::CHECKS_TO_WIN.checked_sub(left.parse::<u8>().ok()?)
+                    };
+                    Some([given(white)?, given(black)?])
+                })
+                .ok_or_else(|| FenError::RemainingChecks(remaining.to_string()))?;
+        }
+
+        // The clocks are optional, some tools only send the first 4 fields
+        if !(4..=6).contains(&tokens.len()) {
+            return Err(FenError::FieldCount(tokens.len()));
         }
 
-        let tokens = fen
-            .split(' ')
-            .map(|split| split.to_string())
-            .collect::<Vec<String>>();
+        let mut pieces = tokens[0];
+        if variant.has_pockets() {
+            if let Some((placement, pocket)) = pieces
+                .strip_suffix(']')
+                .and_then(|pieces| pieces.split_once('['))
+            {
+                pieces = placement;
+                board.fill_pockets(pocket)?;
+            }
+        }
+        let active_player = tokens[1];
+        let castles = tokens[2];
+        let en_passant = tokens[3];
 
-        if tokens.len() < 4 {
-            error!("Invalid FEN string ({fen})");
-            return None;
+        // Set all the pieces to the right places, ranks are given from the 8th to the 1st
+        let ranks = pieces.split('/').collect::<Vec<&str>>();
+        if ranks.len() != 8 {
+            return Err(FenError::PiecePlacement(format!(
+                "expected 8 ranks, got {}",
+                ranks.len()
+            )));
         }
 
-        let pieces = tokens.first().unwrap();
-        let active_player = tokens.get(1).unwrap();
-        let _castles = tokens.get(2).unwrap();
-        let _idk = tokens.get(3).unwrap();
+        for (rank_index, rank) in (0..8u8).rev().zip(ranks) {
+            let mut file_index = 0u8;
+
+            for p in rank.chars() {
+                // The piece before was a pawn
+                if p == '~' && variant.has_pockets() {
+                    let promoted = file_index
+                        .checked_sub(1)
+                        .and_then(|file| super::Position::from_index(file, rank_index))
+                        .filter(|pos| {
+                            board.piece_at(*pos).is_some_and(|(_, piece)| {
+                                !matches!(piece, super::Piece::Pawn | super::Piece::King)
+                            })
+                        })
+                        .ok_or_else(|| {
+                            FenError::PiecePlacement(String::from("'~' has to follow a piece"))
+                        })?;
+                    board.set_promoted(promoted, true);
+                    continue;
+                }
+
+                let too_long = || {
+                    FenError::PiecePlacement(format!(
+                        "rank {} describes more than 8 squares",
+                        rank_index + 1
+                    ))
+                };
+
+                // check nbr
+                if let Some(nbr) = p.to_digit(10).filter(|nbr| (1..=8).contains(nbr)) {
+                    file_index += nbr as u8;
+                    if file_index > 8 {
+                        return Err(too_long());
+                    }
+                    continue;
+                }
+
+                // Else, match the piece or return an error if it's no understood
+                let Some(piece) = super::Piece::from_fen_char(p) else {
+                    return Err(FenError::PiecePlacement(format!(
+                        "could not convert '{p}' to a piece"
+                    )));
+                };
+
+                let Some(pos) = super::Position::from_index(file_index, rank_index) else {
+                    return Err(too_long());
+                };
 
-        // Set all the pieces to the right places
-        let mut pos = super::Position::from_index(0, 7).unwrap();
-        for p in pieces.chars() {
-            // Check line end
-            if p == '/' {
-                pos.set_file(super::File::A);
-                pos.move_down(1);
-                continue;
+                board.set(piece, super::Color::from_fen_char(p), pos);
+                file_index += 1;
             }
 
-            // check nbr
-            if let Some(nbr) = p.to_digit(10) {
-                pos.move_right(nbr as u8);
-                continue;
+            if file_index != 8 {
+                return Err(FenError::PiecePlacement(format!(
+                    "rank {} describes {file_index} squares instead of 8",
+                    rank_index + 1
+                )));
             }
+        }
+
+        // 'Deserialize' the active player
+        board.active_player = match active_player {
+            "w" => super::Color::White,
+            "b" => super::Color::Black,
+            _ => return Err(FenError::ActiveColor(active_player.to_string())),
+        };
+
+        board.castle_rights = super::CastleRights::from_fen(castles, &board)
+            .ok_or_else(|| FenError::CastlingRights(castles.to_string()))?;
 
-            // Else, match the piece or return an error if it's no understood
-            let Some(piece) = super::Piece::from_fen_char(p) else {
-                error!("Could not convert FEN '{p}' to piece");
-                return None;
+        if en_passant != "-" {
+            // The square has to be right behind a pawn that just jumped
+            let expected_rank = if board.active_player == super::Color::White {
+                super::Rank::Six
+            } else {
+                super::Rank::Three
             };
+            // Or behind a horde pawn that jumped from the first rank
+            let horde_rank =
+                variant == super::Variant::Horde && board.active_player == super::Color::Black;
 
-            board.set(piece, super::Color::from_fen_char(p), pos);
-            pos.move_right(1);
+            board.en_passant = Some(
+                super::Position::from_algebraic(en_passant)
+                    .filter(|pos| {
+                        pos.rank() == expected_rank
+                            || (horde_rank && pos.rank() == super::Rank::Two)
+                    })
+                    .ok_or_else(|| FenError::EnPassant(en_passant.to_string()))?,
+            );
         }
 
-        // 'Deserialize' the active player
-        if active_player.len() != 1 {
-            error!("Could not understand the active player of fen ({fen})");
-            return None;
-        }
-        match active_player.as_str() {
-            "w" => board.active_player = super::Color::White,
-            "b" => board.active_player = super::Color::Black,
-            _ => {
-                error!("Could not get the active player from fen string ({fen})");
-                return None;
+        if let Some(halfmove_clock) = tokens.get(4) {
+            board.halfmove_clock = halfmove_clock
+                .parse::<u32>()
+                .map_err(|_| FenError::HalfmoveClock(halfmove_clock.to_string()))?;
+        }
+
+        if let Some(fullmove_number) = tokens.get(5) {
+            board.fullmove_number = fullmove_number
+                .parse::<u32>()
+                .ok()
+                .filter(|nbr| *nbr > 0)
+                .ok_or_else(|| FenError::FullmoveNumber(fullmove_number.to_string()))?;
+        }
+
+        board.validate_setup()?;
+        board.hash = super::zobrist::full(&board);
+        debug_assert_eq!(board.validate(), Ok(()));
+
+        Ok(board)
+    }
+
+    fn empty() -> Self {
+        Self {
+            active_player: super::Color::default(), // White always starts (Unless the FEN string says otherwise)
+            castle_rights: super::CastleRights::default(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            history: Vec::new(),
+            pieces: [super::BitBoard::default(); 6],
+            colors: [super::BitBoard::default(); 2],
+            mailbox: [None; 64],
+            hash: 0,
+            variant: super::Variant::Standard,
+            checks: [0; 2],
+            pockets: [[0; 5]; 2],
+            promoted: super::BitBoard::default(),
+        }
+    }
+
+    /// Reads the inside of the brackets of a crazyhouse FEN, white pieces are uppercase
+    fn fill_pockets(&mut self, pocket: &str) -> Result<(), crate::error::chess::FenError> {
+        for c in pocket.chars() {
+            let piece = super::Piece::from_fen_char(c)
+                .filter(|piece| *piece != super::Piece::King)
+                .ok_or_else(|| crate::error::chess::FenError::Pocket(pocket.to_string()))?;
+            let color = super::Color::from_fen_char(c);
+            let count = self.pocket(color, piece);
+            self.set_pocket(color, piece, count.saturating_add(1));
+        }
+        Ok(())
+    }
+
+    /// Checks that the bitboards and the mailbox agree, the board calls it with `debug_assert` after every change
+    pub fn validate(&self) -> Result<(), crate::error::chess::BoardError> {
+        use crate::error::chess::BoardError;
+
+        let white = self.color_bb(super::Color::White);
+        let black = self.color_bb(super::Color::Black);
+
+        if let Some(pos) = (white & black).positions().next() {
+            return Err(BoardError::ColorOverlap(pos));
+        }
+
+        let mut all_pieces = super::BitBoard::default();
+        for piece in super::piece::ALL_PIECES {
+            let bb = self.get_bb(piece);
+            if let Some(pos) = (all_pieces & bb).positions().next() {
+                return Err(BoardError::PieceOverlap(pos));
+            }
+            all_pieces |= bb;
+        }
+
+        let occupancy = white | black;
+        if let Some(pos) = ((all_pieces | occupancy) & !(all_pieces & occupancy))
+            .positions()
+            .next()
+        {
+            return Err(BoardError::Incomplete(pos));
+        }
+
+        for index in 0..64 {
+            let pos = super::Position::from_index(index % 8, index / 8).unwrap();
+
+            let expected = super::piece::ALL_PIECES
+                .into_iter()
+                .find(|piece| self.get_bb(*piece).read(pos))
+                .map(|piece| {
+                    let color = if white.read(pos) {
+                        super::Color::White
+                    } else {
+                        super::Color::Black
+                    };
+                    (color, piece)
+                });
+
+            let found = self.mailbox[index as usize];
+            if found != expected {
+                return Err(BoardError::Mailbox {
+                    pos,
+                    expected,
+                    found,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rejects the positions that can't happen in a game
+    fn validate_setup(&self) -> Result<(), crate::error::chess::FenError> {
+        use crate::error::chess::FenError;
+
+        for color in [super::Color::White, super::Color::Black] {
+            let kings = (self.get_bb(super::Piece::King) & self.color_bb(color)).count();
+            if self
+                .variant
+                .king_count(color)
+                .is_some_and(|count| count != kings)
+            {
+                return Err(FenError::KingCount(color));
+            }
+        }
+
+        let back_ranks = [super::Rank::One, super::Rank::Eight];
+        if let Some(pawn) = self.get_bb(super::Piece::Pawn).positions().find(|pos| {
+            // The horde starts with pawns on its first rank
+            let horde = self.variant == super::Variant::Horde
+                && pos.rank() == super::Rank::One
+                && self.color_bb(super::Color::White).read(*pos);
+            back_ranks.contains(&pos.rank()) && !horde
+        }) {
+            return Err(FenError::PawnOnBackRank(pawn));
+        }
+
+        // The player that just played can't have left their king in check
+        let waiting = !self.active_player;
+        if self.variant.royal_king() && !super::movegen::checkers(self, waiting).is_empty() {
+            return Err(FenError::OpponentInCheck(waiting));
+        }
+
+        Ok(())
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut pieces = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+
+            for file in 0..8 {
+                let pos: super::Position = (file, rank).into();
+                let Some((color, piece)) = self.piece_at(pos) else {
+                    empty += 1;
+                    continue;
+                };
+
+                if empty != 0 {
+                    pieces.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                pieces.push(piece.to_fen_char(color));
+                if self.promoted.read(pos) {
+                    pieces.push('~');
+                }
+            }
+
+            if empty != 0 {
+                pieces.push_str(&empty.to_string());
             }
+            if rank != 0 {
+                pieces.push('/');
+            }
+        }
+
+        if self.variant.has_pockets() {
+            pieces.push('[');
+            for color in [super::Color::White, super::Color::Black] {
+                for piece in super::POCKET_PIECES {
+                    for _ in 0..self.pocket(color, piece) {
+                        pieces.push(piece.to_fen_char(color));
+                    }
+                }
+            }
+            pieces.push(']');
+        }
+
+        let active_player = match self.active_player {
+            super::Color::White => "w",
+            super::Color::Black => "b",
         };
 
-        Some(board)
+        let mut en_passant = self
+            .en_passant
+            .map(|pos| pos.to_algebraic())
+            .unwrap_or_else(|| String::from("-"));
+
+        // Three-check puts the checks each player still has to give right after it
+        if self.variant == super::Variant::ThreeCheck {
+            let left = |color: super::Color| {
+                super::variant::CHECKS_TO_WIN.saturating_sub(self.checks_given(color))
+            };
+            en_passant = format!(
+                "{en_passant} {}+{}",
+                left(super::Color::White),
+                left(super::Color::Black)
+            );
+        }
+
+        format!(
+            "{pieces} {active_player} {} {en_passant} {} {}",
+            self.castle_rights.to_fen(),
+            self.halfmove_clock,
+            self.fullmove_number
+        )
     }
 
     /// Returns the next to play of this [`Board`].
@@ -84,116 +459,575 @@ impl Board {
         self.active_player
     }
 
-    pub fn make_move(&mut self, mv: &super::movement::ChessMove) -> Result<(), ()> {
+    pub fn castle_rights(&self) -> super::CastleRights {
+        self.castle_rights
+    }
+
+    /// The square a pawn can move to to take en passant, if the last move was a pawn jumping two squares
+    pub fn en_passant(&self) -> Option<super::Position> {
+        self.en_passant
+    }
+
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// The rules the game is played with
+    pub fn variant(&self) -> super::Variant {
+        self.variant
+    }
+
+    /// How many times that player gave check, only counted in three-check
+    pub fn checks_given(&self, color: super::Color) -> u8 {
+        self.checks[color.to_index()]
+    }
+
+    /// How many pieces of that kind the player can drop, always 0 outside of crazyhouse
+    pub fn pocket(&self, color: super::Color, piece: super::Piece) -> u8 {
+        self.pockets[color.to_index()]
+            .get(piece.to_index())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Squares of the pieces that were pawns before, only tracked in crazyhouse
+    pub fn promoted_pieces(&self) -> super::BitBoard {
+        self.promoted
+    }
+
+    pub fn is_in_check(&self) -> bool {
+        self.variant.royal_king() && !super::movegen::checkers(self, self.active_player).is_empty()
+    }
+
+    // A variant win also leaves no move to play, that's not a mate or a stalemate
+    pub fn is_checkmate(&self) -> bool {
+        self.is_in_check() && self.legal_moves().is_empty() && self.variant.win(self).is_none()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_in_check() && self.legal_moves().is_empty() && self.variant.win(self).is_none()
+    }
+
+    /// Neither player can ever mate: lone kings, a single minor piece, or bishops all on the same square color
+    pub fn is_insufficient_material(&self) -> bool {
+        self.has_insufficient_material(super::Color::White)
+            && self.has_insufficient_material(super::Color::Black)
+    }
+
+    /// That player can't win, whatever the other one plays, which makes losing on time a draw
+    pub fn has_insufficient_material(&self, color: super::Color) -> bool {
+        use super::{Color, Piece, Variant};
+
+        let king = self.get_bb(Piece::King);
+        let mine = self.color_bb(color) & !king;
+        let theirs = self.color_bb(!color) & !king;
+
+        match self.variant {
+            Variant::Standard => (),
+            // Anything but a king can still give a check
+            Variant::ThreeCheck => return mine.is_empty(),
+            // Black can still take the whole horde, which mates like in standard chess
+            Variant::Horde if color == Color::Black => return false,
+            Variant::Horde => (),
+            // The kings can still walk to the center, and the pieces can still be lost or dropped
+            _ => return false,
+        }
+
+        if !(mine
+            & (self.get_bb(Piece::Pawn) | self.get_bb(Piece::Rook) | self.get_bb(Piece::Queen)))
+        .is_empty()
+        {
+            return false;
+        }
+
+        let knights = mine & self.get_bb(Piece::Knight);
+        let bishops = mine & self.get_bb(Piece::Bishop);
+
+        if (knights | bishops).is_empty() {
+            return true;
+        }
+
+        // A mate with a single knight needs a piece of the other player to block their king
+        if bishops.is_empty() && knights.count() == 1 {
+            return theirs.is_empty();
+        }
+
+        if !knights.is_empty() {
+            return false;
+        }
+
+        // Same with bishops of one square color, the blocking piece has to stand on the other color
+        let square_color =
+            |pos: super::Position| (pos.file().to_index() + pos.rank().to_index()) % 2;
+        let mut colors = bishops.positions().map(square_color);
+        let first = colors.next();
+        if !colors.all(|color| Some(color) == first) {
+            return false;
+        }
+
+        let their_bishops = theirs & self.get_bb(Piece::Bishop);
+        their_bishops == theirs
+            && their_bishops
+                .positions()
+                .all(|pos| Some(square_color(pos)) == first)
+    }
+
+    /// 50 moves from each player without any capture or pawn move
+    pub fn is_fifty_moves(&self) -> bool {
+        self.halfmove_clock >= 100
+    }
+
+    /// The current position already happened twice
+    pub fn is_threefold_repetition(&self) -> bool {
+        let key = self.hash;
+
+        // Positions from before the last capture or pawn move can't come back
+        self.history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .filter(|old| **old == key)
+            .count()
+            >= 2
+    }
+
+    /// The current position already happened once, a search can count that as a draw already
+    pub fn is_repetition(&self) -> bool {
+        self.history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .any(|old| *old == self.hash)
+    }
+
+    /// Zobrist key of the position: pieces, side to move, castle rights and the en passant square if it can be used
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Plays a legal move, the returned [`Undo`] can be given to `unmake_move` to go back to this position
+    pub fn make_move(&mut self, mv: &super::movement::ChessMove) -> Result<Undo, ()> {
         debug!("{:?}", mv.relative());
         if mv.color != self.active_player {
             return Err(());
         }
 
+        if mv.needs_promotion() && mv.promotion.is_none() {
+            error!("A pawn reaching the last rank has to be promoted");
+            return Err(());
+        }
+
         if !mv.is_legal(self) {
             error!("Illegal move");
 
             return Err(());
         }
 
-        let color_bb = match mv.color {
-            super::Color::Black => self.black_bb,
-            super::Color::White => self.white_bb,
+        if !mv.is_drop() && self.piece_at(mv.origin) != Some((mv.color, mv.piece)) {
+            // There is no given piece at that position
+            return Err(());
+        }
+
+        Ok(self.play(mv))
+    }
+
+    /// Applies a move without checking if it's legal
+    pub(crate) fn play(&mut self, mv: &super::movement::ChessMove) -> Undo {
+        // Has to be read before the board changes
+        let en_passant = mv.is_en_passant(self);
+        let castle = mv.castle_side(self);
+        let captured = if en_passant {
+            Some(super::Piece::Pawn)
+        } else if castle.is_some() {
+            // The rook on the target square is our own
+            None
+        } else {
+            self.piece_at(mv.target).map(|(_, piece)| piece)
         };
+        // A promoted piece goes back to being a pawn in the pocket
+        let pocketed = captured.map(|piece| {
+            if self.promoted.read(mv.target) {
+                super::Piece::Pawn
+            } else {
+                piece
+            }
+        });
 
-        let bb = self.piece_bb.get(&mv.piece).unwrap() & color_bb;
+        let undo = Undo {
+            mv: *mv,
+            captured,
+            castle,
+            castle_rights: self.castle_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            checks: self.checks,
+            pockets: self.pockets,
+            promoted: self.promoted,
+            hash: self.hash,
+        };
+        self.history.push(undo.hash);
 
-        if !bb.read(mv.origin) {
-            // There is no given piece at that position
-            return Err(());
+        // Captures and pawn moves can't be undone, older positions will never be seen again
+        if captured.is_some() || mv.piece == super::Piece::Pawn {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
         }
 
-        // Just overwrite the target pos for now
-        self.unset(mv.piece, mv.color, mv.origin);
+        // Those depend on the position, so they are taken out before it changes and put back at the end
+        self.hash ^= super::zobrist::castles(self.castle_rights) ^ super::zobrist::en_passant(self);
 
-        if let Some((color, piece)) = self.read(mv.target) {
-            self.unset(piece, color, mv.target); // Forgetting this causes a panic in the next read, aaand #46
+        if mv.is_drop() {
+            // `make_move` only lets through the pieces that are in the pocket
+            let count = self.pocket(mv.color, mv.piece);
+            debug_assert!(count > 0, "{:?} dropped from an empty pocket", mv.piece);
+            if let Some(count) = count.checked_sub(1) {
+                self.set_pocket(mv.color, mv.piece, count);
+            }
+            self.set(mv.piece, mv.color, mv.target);
+        } else if let Some(side) = castle {
+            // Both are taken off first, in Chess960 they can land on each other's square
+            let (rook, rook_target, king_target) = self.castle_squares(mv, side);
+            self.unset(mv.piece, mv.color, mv.origin);
+            self.unset(super::Piece::Rook, mv.color, rook);
+            self.set(super::Piece::Rook, mv.color, rook_target);
+            self.set(mv.piece, mv.color, king_target);
+        } else {
+            // Just overwrite the target pos for now
+            self.unset(mv.piece, mv.color, mv.origin);
+
+            if let Some((color, piece)) = self.piece_at(mv.target) {
+                self.unset(piece, color, mv.target); // Forgetting this causes a panic in the next read, aaand #46
+            }
+            self.set(mv.promotion.unwrap_or(mv.piece), mv.color, mv.target);
         }
-        self.set(mv.piece, mv.color, mv.target);
 
-        self.active_player = !self.active_player;
-        Ok(())
-    }
+        if en_passant {
+            // The eaten pawn is behind the target square
+            self.unset(
+                super::Piece::Pawn,
+                !mv.color,
+                super::Position::from_file_rank(mv.target.file(), mv.origin.rank()),
+            );
+        }
 
-    fn set(&mut self, piece: super::Piece, color: super::Color, pos: super::Position) {
-        let color_bb = match color {
-            super::Color::Black => &mut self.black_bb,
-            super::Color::White => &mut self.white_bb,
-        };
+        if self.variant.has_pockets() {
+            if let Some(piece) = pocketed {
+                let count = self.pocket(mv.color, piece);
+                self.set_pocket(mv.color, piece, count.saturating_add(1));
+            }
 
-        color_bb.set(pos);
+            let was_promoted = !mv.is_drop() && self.promoted.read(mv.origin);
+            self.set_promoted(mv.origin, false);
+            self.set_promoted(mv.target, was_promoted || mv.promotion.is_some());
+        }
 
-        let piece_bb = self.piece_bb.get_mut(&piece).unwrap();
-        piece_bb.set(pos);
-    }
+        self.update_castle_rights(mv);
 
-    fn unset(&mut self, piece: super::Piece, color: super::Color, pos: super::Position) {
-        let color_bb = match color {
-            super::Color::Black => &mut self.black_bb,
-            super::Color::White => &mut self.white_bb,
-        };
+        // A pawn that jumped two squares can be taken en passant on the square it skipped
+        self.en_passant = None;
+        if mv.piece == super::Piece::Pawn
+            && mv
+                .origin
+                .rank()
+                .to_index()
+                .abs_diff(mv.target.rank().to_index())
+                == 2
+        {
+            self.en_passant = super::Position::from_index(
+                mv.origin.file().to_index(),
+                (mv.origin.rank().to_index() + mv.target.rank().to_index()) / 2,
+            );
+        }
+
+        if self.variant == super::Variant::ThreeCheck
+            && !super::movegen::checkers(self, !mv.color).is_empty()
+        {
+            let given = &mut self.checks[mv.color.to_index()];
+            self.hash ^= super::zobrist::checks(mv.color, *given);
+            *given += 1;
+            self.hash ^= super::zobrist::checks(mv.color, *given);
+        }
 
-        color_bb.unset(pos);
+        if mv.color == super::Color::Black {
+            self.fullmove_number += 1;
+        }
+
+        self.active_player = !self.active_player;
 
-        let piece_bb = self.piece_bb.get_mut(&piece).unwrap();
-        piece_bb.unset(pos);
+        self.hash ^= super::zobrist::castles(self.castle_rights)
+            ^ super::zobrist::en_passant(self)
+            ^ super::zobrist::black_to_move();
+
+        debug_assert_eq!(self.validate(), Ok(()));
+        debug_assert_eq!(self.hash, super::zobrist::full(self));
+        undo
     }
 
-    pub fn read(&self, pos: super::Position) -> Option<(super::Color, super::Piece)> {
-        if !(self.white_bb | self.black_bb).read(pos) {
-            // Not in any board
-            return None;
+    /// Goes back to the position before the move, `undo` has to come from the last move played on this board
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let mv = undo.mv;
+
+        self.active_player = mv.color;
+        if mv.color == super::Color::Black {
+            self.fullmove_number -= 1;
         }
+        self.castle_rights = undo.castle_rights;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.checks = undo.checks;
+        self.pockets = undo.pockets;
+        self.promoted = undo.promoted;
 
-        let piece: Vec<&super::Piece> = self
-            .piece_bb
-            .iter()
-            .flat_map(|(p, bb)| if bb.read(pos) { Some(p) } else { None })
-            .collect();
+        let hash = self.history.pop();
+        debug_assert_eq!(hash, Some(undo.hash));
 
-        if piece.is_empty() {
-            panic!("Could not query the piece for position: {pos:?} with board: {self:?}")
+        if mv.is_drop() {
+            self.unset(mv.piece, mv.color, mv.target);
+        } else if let Some(side) = undo.castle {
+            let (rook, rook_target, king_target) = self.castle_squares(&mv, side);
+            self.unset(mv.piece, mv.color, king_target);
+            self.unset(super::Piece::Rook, mv.color, rook_target);
+            self.set(super::Piece::Rook, mv.color, rook);
+            self.set(mv.piece, mv.color, mv.origin);
+        } else {
+            self.unset(mv.promotion.unwrap_or(mv.piece), mv.color, mv.target);
+            self.set(mv.piece, mv.color, mv.origin);
         }
 
-        let mut color = None;
+        if let Some(captured) = undo.captured {
+            // The en passant square is back, so this can be asked again
+            let pos = if mv.is_en_passant(self) {
+                super::Position::from_file_rank(mv.target.file(), mv.origin.rank())
+            } else {
+                mv.target
+            };
+            self.set(captured, !mv.color, pos);
+        }
 
-        // We could assume that if it's not one it's the other, but i wanna make sure that i did not fuck up something in board sync
+        self.hash = undo.hash;
 
-        if self.white_bb.read(pos) {
-            color = Some(super::Color::White);
+        debug_assert_eq!(self.validate(), Ok(()));
+        debug_assert_eq!(self.hash, super::zobrist::full(self));
+    }
+
+    /// A castle is lost once the king or the rook has moved, or if the rook got eaten
+    fn update_castle_rights(&mut self, mv: &super::movement::ChessMove) {
+        if mv.piece == super::Piece::King {
+            self.castle_rights.remove_all(mv.color);
         }
 
-        if self.black_bb.read(pos) {
-            if color.is_some() {
-                panic!("The position {pos} for board {self:?} is black and white");
+        for color in [super::Color::White, super::Color::Black] {
+            for side in super::castle::ALL_CASTLE_SIDES {
+                let Some(file) = self.castle_rights.rook_file(color, side) else {
+                    continue;
+                };
+                let rook_home = super::Position::from_file_rank(file, color.back_rank());
+                if mv.origin == rook_home || mv.target == rook_home {
+                    self.castle_rights.remove(color, side);
+                }
             }
-            color = Some(super::Color::Black);
         }
+    }
+
+    /// Where the rook starts, and where the rook and the king land, the castle rights have to be the ones from before
+    /// the move
+    fn castle_squares(
+        &self,
+        mv: &super::movement::ChessMove,
+        side: super::CastleSide,
+    ) -> (super::Position, super::Position, super::Position) {
+        let rank = mv.color.back_rank();
+        let file = self
+            .castle_rights
+            .rook_file(mv.color, side)
+            .unwrap_or(side.rook_file());
 
-        Some((color.unwrap(), **piece.first().unwrap()))
+        (
+            super::Position::from_file_rank(file, rank),
+            super::Position::from_file_rank(side.rook_target_file(), rank),
+            super::Position::from_file_rank(side.king_target_file(), rank),
+        )
+    }
+
+    fn set(&mut self, piece: super::Piece, color: super::Color, pos: super::Position) {
+        self.colors[color.to_index()].set(pos);
+        self.pieces[piece.to_index()].set(pos);
+        self.mailbox[pos.to_index() as usize] = Some((color, piece));
+        self.hash ^= super::zobrist::piece(color, piece, pos);
+    }
+
+    fn unset(&mut self, piece: super::Piece, color: super::Color, pos: super::Position) {
+        self.colors[color.to_index()].unset(pos);
+        self.pieces[piece.to_index()].unset(pos);
+        self.mailbox[pos.to_index() as usize] = None;
+        self.hash ^= super::zobrist::piece(color, piece, pos);
+    }
+
+    fn set_pocket(&mut self, color: super::Color, piece: super::Piece, count: u8) {
+        // Kings never go in a pocket
+        let Some(slot) = self.pockets[color.to_index()].get_mut(piece.to_index()) else {
+            return;
+        };
+        self.hash ^= super::zobrist::pocket(color, piece, *slot)
+            ^ super::zobrist::pocket(color, piece, count);
+        *slot = count;
+    }
+
+    fn set_promoted(&mut self, pos: super::Position, promoted: bool) {
+        if self.promoted.read(pos) != promoted {
+            if promoted {
+                self.promoted.set(pos);
+            } else {
+                self.promoted.unset(pos);
+            }
+            self.hash ^= super::zobrist::promoted(pos);
+        }
+    }
+
+    /// What stands on that square
+    pub fn piece_at(&self, pos: super::Position) -> Option<(super::Color, super::Piece)> {
+        self.mailbox[pos.to_index() as usize]
     }
 
     pub fn flip(&mut self) {
-        self.white_bb.flip();
-        self.black_bb.flip();
-        for (_piece, bb) in self.piece_bb.iter_mut() {
+        for bb in self
+            .pieces
+            .iter_mut()
+            .chain(self.colors.iter_mut())
+            .chain([&mut self.promoted])
+        {
             bb.flip();
         }
+        // Same as flipping the bits, the square i goes to 63 - i
+        self.mailbox.reverse();
+        self.hash = super::zobrist::full(self);
     }
 
     pub fn get_bb(&self, piece: super::Piece) -> super::BitBoard {
-        *self.piece_bb.get(&piece).unwrap()
+        self.pieces[piece.to_index()]
+    }
+
+    pub fn color_bb(&self, color: super::Color) -> super::BitBoard {
+        self.colors[color.to_index()]
+    }
+
+    /// Every square that has a piece on it
+    pub fn occupancy(&self) -> super::BitBoard {
+        self.colors[0] | self.colors[1]
+    }
+
+    /// Every legal move for the player that has to play next
+    pub fn legal_moves(&self) -> Vec<super::ChessMove> {
+        super::movegen::legal_moves(self)
+    }
+}
+
+impl Undo {
+    /// The move that was played
+    pub fn chess_move(&self) -> super::ChessMove {
+        self.mv
+    }
+
+    /// The piece the move ate, if any
+    pub fn captured(&self) -> Option<super::Piece> {
+        self.captured
     }
 }
 
 impl Default for Board {
     fn default() -> Self {
-        Self::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap()
+        Self::from_fen(STARTING_FEN).unwrap()
+    }
+}
+
+impl From<Board> for BoardRepr {
+    fn from(board: Board) -> Self {
+        // The older keys can't repeat anymore, no need to send the whole game with every update
+        let recent = board
+            .history
+            .len()
+            .saturating_sub(board.halfmove_clock as usize);
+
+        Self {
+            version: WIRE_VERSION,
+            active_player: board.active_player,
+            castle_rights: board.castle_rights,
+            en_passant: board.en_passant,
+            halfmove_clock: board.halfmove_clock,
+            fullmove_number: board.fullmove_number,
+            white_bb: board.color_bb(super::Color::White),
+            black_bb: board.color_bb(super::Color::Black),
+            piece_bb: super::piece::ALL_PIECES
+                .into_iter()
+                .map(|piece| (piece, board.get_bb(piece)))
+                .collect(),
+            history: board.history[recent..].to_vec(),
+            variant: board.variant,
+            checks: board.checks,
+            pockets: board.pockets,
+            promoted: board.promoted,
+        }
+    }
+}
+
+impl TryFrom<BoardRepr> for Board {
+    type Error = crate::error::chess::BoardError;
+
+    fn try_from(repr: BoardRepr) -> Result<Self, Self::Error> {
+        use crate::error::chess::BoardError;
+
+        // Its fields may not mean the same anymore
+        if repr.version > WIRE_VERSION {
+            return Err(BoardError::WireVersion(repr.version));
+        }
+
+        let mut board = Self {
+            active_player: repr.active_player,
+            castle_rights: repr.castle_rights,
+            en_passant: repr.en_passant,
+            halfmove_clock: repr.halfmove_clock,
+            fullmove_number: repr.fullmove_number,
+            history: repr.history,
+            variant: repr.variant,
+            checks: repr.checks,
+            pockets: repr.pockets,
+            promoted: repr.promoted,
+            ..Self::empty()
+        };
+
+        for (piece, bb) in repr.piece_bb {
+            for pos in bb.positions() {
+                let color = match (repr.white_bb.read(pos), repr.black_bb.read(pos)) {
+                    (true, false) => super::Color::White,
+                    (false, true) => super::Color::Black,
+                    (true, true) => return Err(BoardError::ColorOverlap(pos)),
+                    (false, false) => return Err(BoardError::Incomplete(pos)),
+                };
+
+                if board.piece_at(pos).is_some() {
+                    return Err(BoardError::PieceOverlap(pos));
+                }
+                board.set(piece, color, pos);
+            }
+        }
+
+        // Colors without a piece
+        if let Some(pos) = ((repr.white_bb | repr.black_bb) & !board.occupancy())
+            .positions()
+            .next()
+        {
+            return Err(BoardError::Incomplete(pos));
+        }
+
+        board.validate()?;
+        board.hash = super::zobrist::full(&board);
+
+        Ok(board)
     }
 }
 
@@ -202,11 +1036,11 @@ mod tests {
     use super::*;
 
     fn display(b: &Board) {
-        println!("Whites: {}", b.white_bb);
-        println!("Blacks: {}", b.black_bb);
+        println!("Whites: {}", b.color_bb(super::super::Color::White));
+        println!("Blacks: {}", b.color_bb(super::super::Color::Black));
 
         for piece in super::super::piece::ALL_PIECES {
-            println!("{piece:?} {}", b.piece_bb.get(&piece).unwrap())
+            println!("{piece:?} {}", b.get_bb(piece))
         }
     }
 
@@ -239,7 +1073,6 @@ mod tests {
     fn play() {
         logger::init(Default::default(), None);
 
-
         use super::super::{
             position::{File, Position, Rank},
             ChessMove, Color, Piece,
@@ -251,7 +1084,7 @@ mod tests {
         b.make_move(&ChessMove::new(s, e, Piece::Pawn, Color::White, None))
             .unwrap();
 
-        println!("{}", b.white_bb | b.black_bb);
+        println!("{}", b.occupancy());
     }
 
     #[test]
@@ -267,4 +1100,542 @@ mod tests {
         println!("{}", white);
         println!("{}", black);
     }
+
+    #[test]
+    fn castle() {
+        use super::super::{CastleSide, ChessMove, Color, Piece, Position};
+
+        let mut b = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        b.make_move(&ChessMove::new(
+            Position::from((4, 0)),
+            Position::from((6, 0)),
+            Piece::King,
+            Color::White,
+            None,
+        ))
+        .unwrap();
+
+        assert_eq!(b.piece_at((5, 0).into()), Some((Color::White, Piece::Rook)));
+        assert_eq!(b.piece_at((7, 0).into()), None);
+        assert!(!b.castle_rights().has(Color::White, CastleSide::KingSide));
+        assert!(!b.castle_rights().has(Color::White, CastleSide::QueenSide));
+
+        // Black's queen side rook moves
+        b.make_move(&ChessMove::new(
+            Position::from((0, 7)),
+            Position::from((0, 0)),
+            Piece::Rook,
+            Color::Black,
+            None,
+        ))
+        .unwrap();
+
+        assert!(!b.castle_rights().has(Color::Black, CastleSide::QueenSide));
+        assert!(b.castle_rights().has(Color::Black, CastleSide::KingSide));
+    }
+
+    #[test]
+    fn chess960_castle() {
+        use super::super::{ChessMove, Color, Piece, Position};
+
+        // The king and the rooks swap places on both sides
+        let fen = "1r1k4/8/8/8/8/8/8/2RK2R1 w GCb - 0 1";
+        let mut b = Board::from_fen(fen).unwrap();
+        let at = |b: &Board, square| b.piece_at(Position::from_algebraic(square).unwrap());
+
+        // The king takes its own rook, which lands in front of the black king
+        let castle = ChessMove::from_uci("d1c1", &b).unwrap();
+        assert_eq!(castle.to_san(&b), "O-O-O+");
+        let undo = b.make_move(&castle).unwrap();
+        assert_eq!(at(&b, "c1"), Some((Color::White, Piece::King)));
+        assert_eq!(at(&b, "d1"), Some((Color::White, Piece::Rook)));
+        assert_eq!(b.castle_rights().to_fen(), "b");
+
+        b.unmake_move(undo);
+        assert_eq!(b.to_fen(), fen);
+
+        let castle = ChessMove::from_san("O-O", &b).unwrap();
+        assert_eq!(castle.to_uci(), "d1g1");
+        b.make_move(&castle).unwrap();
+        assert_eq!(at(&b, "g1"), Some((Color::White, Piece::King)));
+        assert_eq!(at(&b, "f1"), Some((Color::White, Piece::Rook)));
+        assert_eq!(at(&b, "d1"), None);
+
+        // The king would land on c8, in front of the rook on c1
+        assert!(ChessMove::from_san("O-O-O", &b).is_err());
+        assert_eq!(b.castle_rights().to_fen(), "b");
+    }
+
+    #[test]
+    fn en_passant() {
+        use super::super::{ChessMove, Color, Piece, Position};
+
+        let mut b = Board::from_fen("4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1").unwrap();
+
+        b.make_move(&ChessMove::new(
+            Position::from((3, 6)),
+            Position::from((3, 4)),
+            Piece::Pawn,
+            Color::Black,
+            None,
+        ))
+        .unwrap();
+        assert_eq!(b.en_passant(), Some(Position::from((3, 5))));
+
+        b.make_move(&ChessMove::new(
+            Position::from((4, 4)),
+            Position::from((3, 5)),
+            Piece::Pawn,
+            Color::White,
+            None,
+        ))
+        .unwrap();
+
+        assert_eq!(b.piece_at((3, 4).into()), None);
+        assert_eq!(b.piece_at((3, 5).into()), Some((Color::White, Piece::Pawn)));
+        assert_eq!(b.en_passant(), None);
+        assert_eq!(b.get_bb(Piece::Pawn).count(), 1);
+    }
+
+    #[test]
+    fn promotion() {
+        use super::super::{ChessMove, Color, Piece, Position};
+
+        let mut b = Board::from_fen("8/P7/8/8/8/8/8/k6K w - - 0 1").unwrap();
+
+        let s = Position::from((0, 6));
+        let e = Position::from((0, 7));
+
+        assert!(b
+            .make_move(&ChessMove::new(s, e, Piece::Pawn, Color::White, None))
+            .is_err());
+        assert!(b
+            .make_move(&ChessMove::new(
+                s,
+                e,
+                Piece::Pawn,
+                Color::White,
+                Some(Piece::King)
+            ))
+            .is_err());
+
+        b.make_move(&ChessMove::new(
+            s,
+            e,
+            Piece::Pawn,
+            Color::White,
+            Some(Piece::Knight),
+        ))
+        .unwrap();
+
+        assert_eq!(b.piece_at(e), Some((Color::White, Piece::Knight)));
+        assert!(b.get_bb(Piece::Pawn).is_empty());
+    }
+
+    #[test]
+    fn checkmate() {
+        // Fool's mate
+        let b = Board::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3")
+            .unwrap();
+
+        assert!(b.is_in_check());
+        assert!(b.is_checkmate());
+        assert!(!b.is_stalemate());
+    }
+
+    #[test]
+    fn stalemate() {
+        let b = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+
+        assert!(!b.is_in_check());
+        assert!(b.is_stalemate());
+        assert!(!b.is_checkmate());
+    }
+
+    #[test]
+    fn insufficient_material() {
+        use super::super::{Color, Variant};
+
+        let insufficient = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/4K3 w - - 0 1",
+            // Both bishops on dark squares
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ];
+        let sufficient = [
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3RK3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3NKN2 w - - 0 1",
+            // Bishops on different square colors
+            "4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ];
+
+        for fen in insufficient {
+            assert!(
+                Board::from_fen(fen).unwrap().is_insufficient_material(),
+                "{fen}"
+            );
+        }
+        for fen in sufficient {
+            assert!(
+                !Board::from_fen(fen).unwrap().is_insufficient_material(),
+                "{fen}"
+            );
+        }
+
+        // Only one player can still win with a knight against a pawn
+        let b = Board::from_fen("4k3/8/8/8/8/8/4P3/4K1n1 w - - 0 1").unwrap();
+        assert!(!b.has_insufficient_material(Color::White));
+        assert!(!b.has_insufficient_material(Color::Black));
+        let b = Board::from_fen("4k3/8/8/8/8/8/8/4KBn1 w - - 0 1").unwrap();
+        assert!(!b.has_insufficient_material(Color::White));
+        let b = Board::from_fen("4k3/8/8/8/8/8/8/4K1n1 w - - 0 1").unwrap();
+        assert!(b.has_insufficient_material(Color::White));
+        assert!(b.has_insufficient_material(Color::Black));
+
+        // Black can always take the horde
+        let b = Board::from_variant(Variant::Horde);
+        assert!(!b.has_insufficient_material(Color::White));
+        assert!(!b.has_insufficient_material(Color::Black));
+    }
+
+    #[test]
+    fn fifty_moves() {
+        use super::super::{ChessMove, Color, Piece, Position};
+
+        let mut b = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        assert!(!b.is_fifty_moves());
+
+        b.make_move(&ChessMove::new(
+            Position::from((0, 0)),
+            Position::from((0, 1)),
+            Piece::Rook,
+            Color::White,
+            None,
+        ))
+        .unwrap();
+        assert!(b.is_fifty_moves());
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        use super::super::{ChessMove, Color, Piece, Position};
+
+        let mut b = Board::default();
+
+        let shuffle = [
+            ((6, 0), (5, 2), Color::White),
+            ((6, 7), (5, 5), Color::Black),
+            ((5, 2), (6, 0), Color::White),
+            ((5, 5), (6, 7), Color::Black),
+        ];
+
+        for _ in 0..2 {
+            assert!(!b.is_threefold_repetition());
+            for (origin, target, color) in shuffle {
+                b.make_move(&ChessMove::new(
+                    Position::from(origin),
+                    Position::from(target),
+                    Piece::Knight,
+                    color,
+                    None,
+                ))
+                .unwrap();
+            }
+        }
+
+        assert!(b.is_threefold_repetition());
+        assert_eq!(b.halfmove_clock(), 8);
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        let fens = [
+            STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 12 45",
+            "4k3/8/8/8/8/8/8/4K2R b K - 3 60",
+        ];
+
+        for fen in fens {
+            assert_eq!(Board::from_fen(fen).unwrap().to_fen(), fen);
+        }
+
+        // The clocks get their default values when missing
+        assert_eq!(
+            Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - -")
+                .unwrap()
+                .to_fen(),
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+        );
+    }
+
+    #[test]
+    fn fen_counters() {
+        use super::super::{ChessMove, Color, Piece, Position};
+
+        let mut b = Board::default();
+
+        for (origin, target, piece, color) in [
+            ((6, 0), (5, 2), Piece::Knight, Color::White),
+            ((4, 6), (4, 4), Piece::Pawn, Color::Black),
+            ((5, 2), (6, 0), Piece::Knight, Color::White),
+        ] {
+            b.make_move(&ChessMove::new(
+                Position::from(origin),
+                Position::from(target),
+                piece,
+                color,
+                None,
+            ))
+            .unwrap();
+        }
+
+        assert_eq!(
+            b.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR b KQkq - 1 2"
+        );
+    }
+
+    #[test]
+    fn fen_errors() {
+        use crate::error::chess::FenError;
+
+        let cases = [
+            ("4k3/8/8/8/8/8/8/4K3 w", FenError::FieldCount(2).to_string()),
+            ("", FenError::FieldCount(0).to_string()),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 1 extra",
+                FenError::FieldCount(7).to_string(),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+                FenError::ActiveColor("x".to_string()).to_string(),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w KX - 0 1",
+                FenError::CastlingRights("KX".to_string()).to_string(),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - e3 0 1",
+                FenError::EnPassant("e3".to_string()).to_string(),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - -1 1",
+                FenError::HalfmoveClock("-1".to_string()).to_string(),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+                FenError::FullmoveNumber("0".to_string()).to_string(),
+            ),
+        ];
+
+        for (fen, error) in cases {
+            assert_eq!(
+                Board::from_fen(fen).unwrap_err().to_string(),
+                error,
+                "{fen}"
+            );
+        }
+
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3/8 w - - 0 1",
+            "4k3/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K4 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4X3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K02 w - - 0 1",
+            // Pieces after a full rank
+            "4k3/8/8/8/8/8/8/4K3p w - - 0 1",
+            "4k3/8/8/8/8/8/8p/4K3 w - - 0 1",
+            // Long enough to wrap around a u8
+            "4k3/8/8/8/8/8/8/4K3888888888888888888888888888888888888 w - - 0 1",
+        ] {
+            assert!(
+                matches!(Board::from_fen(fen), Err(FenError::PiecePlacement(_))),
+                "{fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn fen_illegal_setups() {
+        use super::super::{Color, Position};
+        use crate::error::chess::FenError;
+
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1"),
+            Err(FenError::KingCount(Color::Black))
+        );
+        assert_eq!(
+            Board::from_fen("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"),
+            Err(FenError::KingCount(Color::White))
+        );
+        assert_eq!(
+            Board::from_fen("4k2P/8/8/8/8/8/8/4K3 w - - 0 1"),
+            Err(FenError::PawnOnBackRank(Position::from((7, 7))))
+        );
+        assert_eq!(
+            Board::from_fen("4k3/8/8/8/8/8/8/4K2r w - - 0 1").map(|b| b.next_to_play()),
+            Ok(Color::White)
+        );
+        assert_eq!(
+            Board::from_fen("4k3/8/8/8/8/8/8/4K2r b - - 0 1"),
+            Err(FenError::OpponentInCheck(Color::White))
+        );
+    }
+
+    #[test]
+    fn piece_at() {
+        let b = Board::default();
+
+        assert_eq!(
+            b.piece_at((4, 0).into()),
+            Some((super::super::Color::White, super::super::Piece::King))
+        );
+        assert_eq!(
+            b.piece_at((3, 7).into()),
+            Some((super::super::Color::Black, super::super::Piece::Queen))
+        );
+        assert_eq!(b.piece_at((4, 3).into()), None);
+    }
+
+    #[test]
+    fn validate() {
+        use crate::error::chess::BoardError;
+
+        let e1 = super::super::Position::from((4, 0));
+        assert_eq!(Board::default().validate(), Ok(()));
+
+        // Break the boards on purpose
+        let mut b = Board::default();
+        b.mailbox[e1.to_index() as usize] = None;
+        assert!(matches!(b.validate(), Err(BoardError::Mailbox { .. })));
+
+        let mut b = Board::default();
+        b.colors[super::super::Color::Black.to_index()].set(e1);
+        assert_eq!(b.validate(), Err(BoardError::ColorOverlap(e1)));
+
+        let mut b = Board::default();
+        b.pieces[super::super::Piece::Queen.to_index()].set(e1);
+        assert_eq!(b.validate(), Err(BoardError::PieceOverlap(e1)));
+
+        let mut b = Board::default();
+        b.colors[super::super::Color::White.to_index()].unset(e1);
+        assert_eq!(b.validate(), Err(BoardError::Incomplete(e1)));
+    }
+
+    #[test]
+    fn wire_format() {
+        let b =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+
+        // Same fields as before the arrays
+        let text = ron::to_string(&b).unwrap();
+        println!("{text}");
+        for field in ["white_bb", "black_bb", "piece_bb", "history"] {
+            assert!(text.contains(field));
+        }
+        assert_eq!(ron::from_str::<Board>(&text).unwrap(), b);
+
+        // Only the keys that can still repeat are sent
+        let mut b = Board::default();
+        for uci in ["e2e4", "e7e5", "g1f3", "b8c6", "f3g1", "c6b8", "g1f3"] {
+            let mv = super::super::ChessMove::from_uci(uci, &b).unwrap();
+            b.make_move(&mv).unwrap();
+        }
+        let sent = ron::from_str::<Board>(&ron::to_string(&b).unwrap()).unwrap();
+        assert_eq!(BoardRepr::from(b.clone()).history.len(), 5);
+        assert_eq!(sent.hash(), b.hash());
+        assert!(sent.is_repetition());
+
+        // A board from before the variants is a standard one, and a newer one can't be read
+        let old = text
+            .replace(&format!("version:{WIRE_VERSION},"), "")
+            .replace(
+                ",variant:Standard,checks:(0,0),pockets:((0,0,0,0,0),(0,0,0,0,0)),promoted:(0)",
+                "",
+            );
+        println!("{old}");
+        for field in ["version", "variant", "checks", "pockets", "promoted"] {
+            assert!(!old.contains(field));
+        }
+        assert_eq!(
+            ron::from_str::<Board>(&old).unwrap(),
+            ron::from_str::<Board>(&text).unwrap()
+        );
+        let newer = text.replace(
+            &format!("version:{WIRE_VERSION},"),
+            &format!("version:{},", WIRE_VERSION + 1),
+        );
+        assert!(ron::from_str::<Board>(&newer).is_err());
+
+        // The pieces and the colors have to agree
+        let mut repr = BoardRepr::from(b);
+        repr.white_bb.set(super::super::Position::from((0, 3)));
+        let text = ron::to_string(&repr).unwrap();
+        assert!(ron::from_str::<Board>(&text).is_err());
+    }
+
+    #[test]
+    fn unmake_move() {
+        // Castles, en passant, promotions with and without captures, checks and pins
+        let fens = [
+            STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ];
+
+        for fen in fens {
+            let mut b = Board::from_fen(fen).unwrap();
+            let start = b.clone();
+
+            for mv in start.legal_moves() {
+                let undo = b.make_move(&mv).unwrap();
+                let after = b.clone();
+
+                // One move deeper, so the undone moves also get to see the ones that were played before them
+                for reply in after.legal_moves() {
+                    let undo = b.make_move(&reply).unwrap();
+                    b.unmake_move(undo);
+                    assert_eq!(b, after, "{fen}: {} {}", mv.to_uci(), reply.to_uci());
+                }
+
+                b.unmake_move(undo);
+                assert_eq!(b, start, "{fen}: {}", mv.to_uci());
+                assert_eq!(b.to_fen(), fen);
+            }
+        }
+    }
+
+    #[test]
+    fn undo_record() {
+        use super::super::Piece;
+
+        let mut b =
+            Board::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .unwrap();
+
+        let mv = super::super::ChessMove::from_uci("e5f6", &b).unwrap();
+        let undo = b.make_move(&mv).unwrap();
+        println!("{undo:?}");
+
+        assert_eq!(undo.chess_move(), mv);
+        assert_eq!(undo.captured(), Some(Piece::Pawn));
+        assert_eq!(b.piece_at((5, 4).into()), None);
+
+        b.unmake_move(undo);
+        assert_eq!(
+            b.piece_at((5, 4).into()),
+            Some((super::super::Color::Black, Piece::Pawn))
+        );
+    }
 }
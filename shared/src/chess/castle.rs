@@ -0,0 +1,280 @@
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum CastleSide {
+    KingSide,
+    QueenSide,
+}
+
+pub const ALL_CASTLE_SIDES: [CastleSide; 2] = [CastleSide::KingSide, CastleSide::QueenSide];
+
+/// Which castles are still available for both players, with the file of the rook for each of them
+#[derive(
+    Default, Clone, Copy, Debug, Hash, PartialEq, Eq, serde::Deserialize, serde::Serialize,
+)]
+pub struct CastleRights {
+    white_king_side: Option<super::File>,
+    white_queen_side: Option<super::File>,
+    black_king_side: Option<super::File>,
+    black_queen_side: Option<super::File>,
+}
+
+impl CastleSide {
+    /// File of the rook that castles on that side in standard chess
+    pub fn rook_file(&self) -> super::File {
+        match self {
+            CastleSide::KingSide => super::File::H,
+            CastleSide::QueenSide => super::File::A,
+        }
+    }
+
+    /// File the king lands on
+    pub fn king_target_file(&self) -> super::File {
+        match self {
+            CastleSide::KingSide => super::File::G,
+            CastleSide::QueenSide => super::File::C,
+        }
+    }
+
+    /// File the rook lands on
+    pub fn rook_target_file(&self) -> super::File {
+        match self {
+            CastleSide::KingSide => super::File::F,
+            CastleSide::QueenSide => super::File::D,
+        }
+    }
+}
+
+impl CastleRights {
+    pub fn all() -> Self {
+        Self {
+            white_king_side: Some(super::File::H),
+            white_queen_side: Some(super::File::A),
+            black_king_side: Some(super::File::H),
+            black_queen_side: Some(super::File::A),
+        }
+    }
+
+    /// Reads the castling field of a FEN string ("KQkq", "Kq", "-", ..), `board` has to hold the pieces already
+    /// For Chess960, the rooks can also be given by their file ("HFhf" in Shredder-FEN), "K" and "Q" are then the
+    /// outermost rooks like in X-FEN
+    pub fn from_fen(field: &str, board: &super::Board) -> Option<Self> {
+        let mut rights = Self::default();
+
+        if field == "-" {
+            return Some(rights);
+        }
+
+        for c in field.chars() {
+            let color = if c.is_ascii_uppercase() {
+                super::Color::White
+            } else {
+                super::Color::Black
+            };
+            let rank = color.back_rank();
+            let king = super::movegen::king_position(board, color).filter(|pos| pos.rank() == rank);
+
+            let (side, file) = match c.to_ascii_lowercase() {
+                'k' => (
+                    CastleSide::KingSide,
+                    outermost_rook(board, color, king, CastleSide::KingSide),
+                ),
+                'q' => (
+                    CastleSide::QueenSide,
+                    outermost_rook(board, color, king, CastleSide::QueenSide),
+                ),
+                file @ 'a'..='h' => {
+                    let file = super::File::from_index(file as u8 - b'a')?;
+                    let king = king?.file().to_index();
+                    let side = match file.to_index() {
+                        index if index > king => CastleSide::KingSide,
+                        index if index < king => CastleSide::QueenSide,
+                        _ => return None,
+                    };
+                    (side, file)
+                }
+                _ => return None,
+            };
+            *rights.get_mut(color, side) = Some(file);
+        }
+
+        Some(rights)
+    }
+
+    /// The rooks on the standard files are written "KQkq", the other ones with their file
+    pub fn to_fen(&self) -> String {
+        let fen = [
+            (super::Color::White, CastleSide::KingSide),
+            (super::Color::White, CastleSide::QueenSide),
+            (super::Color::Black, CastleSide::KingSide),
+            (super::Color::Black, CastleSide::QueenSide),
+        ]
+        .into_iter()
+        .filter_map(|(color, side)| {
+            let file = self.rook_file(color, side)?;
+            let c = match side {
+                _ if file != side.rook_file() => (b'a' + file.to_index()) as char,
+                CastleSide::KingSide => 'k',
+                CastleSide::QueenSide => 'q',
+            };
+
+            Some(match color {
+                super::Color::White => c.to_ascii_uppercase(),
+                super::Color::Black => c,
+            })
+        })
+        .collect::<String>();
+
+        if fen.is_empty() {
+            String::from("-")
+        } else {
+            fen
+        }
+    }
+
+    pub fn has(&self, color: super::Color, side: CastleSide) -> bool {
+        self.get(color, side).is_some()
+    }
+
+    /// File the rook that castles on that side started from, if the castle is still available
+    pub fn rook_file(&self, color: super::Color, side: CastleSide) -> Option<super::File> {
+        *self.get(color, side)
+    }
+
+    pub fn remove(&mut self, color: super::Color, side: CastleSide) {
+        *self.get_mut(color, side) = None
+    }
+
+    pub fn remove_all(&mut self, color: super::Color) {
+        for side in ALL_CASTLE_SIDES {
+            self.remove(color, side)
+        }
+    }
+
+    fn get(&self, color: super::Color, side: CastleSide) -> &Option<super::File> {
+        match (color, side) {
+            (super::Color::White, CastleSide::KingSide) => &self.white_king_side,
+            (super::Color::White, CastleSide::QueenSide) => &self.white_queen_side,
+            (super::Color::Black, CastleSide::KingSide) => &self.black_king_side,
+            (super::Color::Black, CastleSide::QueenSide) => &self.black_queen_side,
+        }
+    }
+
+    fn get_mut(&mut self, color: super::Color, side: CastleSide) -> &mut Option<super::File> {
+        match (color, side) {
+            (super::Color::White, CastleSide::KingSide) => &mut self.white_king_side,
+            (super::Color::White, CastleSide::QueenSide) => &mut self.white_queen_side,
+            (super::Color::Black, CastleSide::KingSide) => &mut self.black_king_side,
+            (super::Color::Black, CastleSide::QueenSide) => &mut self.black_queen_side,
+        }
+    }
+}
+
+/// The rook furthest from the king on that side, the standard file if there is none so "KQkq" still reads on any board
+fn outermost_rook(
+    board: &super::Board,
+    color: super::Color,
+    king: Option<super::Position>,
+    side: CastleSide,
+) -> super::File {
+    let rank = color.back_rank();
+    let files: Vec<u8> = match (side, king) {
+        (CastleSide::KingSide, Some(king)) => (king.file().to_index() + 1..8).rev().collect(),
+        (CastleSide::QueenSide, Some(king)) => (0..king.file().to_index()).collect(),
+        (_, None) => Vec::new(),
+    };
+
+    files
+        .into_iter()
+        .flat_map(super::File::from_index)
+        .find(|file| {
+            board.piece_at(super::Position::from_file_rank(*file, rank))
+                == Some((color, super::Piece::Rook))
+        })
+        .unwrap_or(side.rook_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::{Color, File};
+
+    #[test]
+    fn fen() {
+        let board = crate::chess::Board::default();
+
+        assert_eq!(
+            CastleRights::from_fen("KQkq", &board),
+            Some(CastleRights::all())
+        );
+        assert_eq!(
+            CastleRights::from_fen("-", &board),
+            Some(CastleRights::default())
+        );
+        assert_eq!(CastleRights::from_fen("KQx", &board), None);
+
+        assert_eq!(CastleRights::all().to_fen(), "KQkq");
+        assert_eq!(CastleRights::default().to_fen(), "-");
+
+        let rights = CastleRights::from_fen("Kq", &board).unwrap();
+        assert!(rights.has(Color::White, CastleSide::KingSide));
+        assert!(!rights.has(Color::White, CastleSide::QueenSide));
+        assert!(!rights.has(Color::Black, CastleSide::KingSide));
+        assert!(rights.has(Color::Black, CastleSide::QueenSide));
+
+        // Shredder-FEN gives the same rights on a standard board
+        assert_eq!(
+            CastleRights::from_fen("HAha", &board),
+            Some(CastleRights::all())
+        );
+    }
+
+    #[test]
+    fn chess960_fen() {
+        // Rooks on b and g, another rook on h for black that can't castle
+        let board =
+            crate::chess::Board::from_fen("1r3krr/pppppppp/8/8/8/8/PPPPPPPP/1R3KR1 w - - 0 1")
+                .unwrap();
+
+        let rights = CastleRights::from_fen("GBgb", &board).unwrap();
+        assert_eq!(
+            rights.rook_file(Color::White, CastleSide::KingSide),
+            Some(File::G)
+        );
+        assert_eq!(
+            rights.rook_file(Color::White, CastleSide::QueenSide),
+            Some(File::B)
+        );
+        assert_eq!(
+            rights.rook_file(Color::Black, CastleSide::KingSide),
+            Some(File::G)
+        );
+        assert_eq!(rights.to_fen(), "GBgb");
+
+        // X-FEN, the letters are the outermost rooks
+        let rights = CastleRights::from_fen("KQkq", &board).unwrap();
+        assert_eq!(
+            rights.rook_file(Color::White, CastleSide::KingSide),
+            Some(File::G)
+        );
+        assert_eq!(
+            rights.rook_file(Color::Black, CastleSide::KingSide),
+            Some(File::H)
+        );
+        assert_eq!(rights.to_fen(), "GBkb");
+
+        // The king's own file is not a rook
+        assert_eq!(CastleRights::from_fen("F", &board), None);
+    }
+
+    #[test]
+    fn remove() {
+        let mut rights = CastleRights::all();
+
+        rights.remove(Color::Black, CastleSide::KingSide);
+        assert!(!rights.has(Color::Black, CastleSide::KingSide));
+        assert!(rights.has(Color::Black, CastleSide::QueenSide));
+
+        rights.remove_all(Color::White);
+        assert!(!rights.has(Color::White, CastleSide::KingSide));
+        assert!(!rights.has(Color::White, CastleSide::QueenSide));
+    }
+}
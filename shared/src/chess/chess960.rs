@@ -0,0 +1,143 @@
+// Fischer random chess: the back rank is shuffled, with the bishops on both colors and the king between the rooks
+// The setups are numbered from 0 to 959 like everywhere else, 518 is the standard one
+// https://en.wikipedia.org/wiki/Fischer_random_chess_numbering_scheme
+
+/// Number of different setups
+pub const CHESS960_SETUPS: u16 = 960;
+
+// Where the two knights go among the five squares left once the bishops and the queen are placed
+const KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// The back rank of a setup, from the a file to the h file
+pub fn chess960_back_rank(number: u16) -> Option<[super::Piece; 8]> {
+    if number >= CHESS960_SETUPS {
+        return None;
+    }
+
+    let mut rank = [None; 8];
+    let number = number as usize;
+
+    // The light squared bishop is on b, d, f or h, the dark squared one on a, c, e or g
+    rank[number % 4 * 2 + 1] = Some(super::Piece::Bishop);
+    rank[number / 4 % 4 * 2] = Some(super::Piece::Bishop);
+
+    let number = number / 16;
+    place(&mut rank, number % 6, super::Piece::Queen);
+
+    // Taken from the right first, so the first index stays right
+    let (first, second) = KNIGHTS[number / 6];
+    place(&mut rank, second, super::Piece::Knight);
+    place(&mut rank, first, super::Piece::Knight);
+
+    // The king always ends up between the rooks
+    for piece in [super::Piece::Rook, super::Piece::King, super::Piece::Rook] {
+        place(&mut rank, 0, piece);
+    }
+
+    Some(rank.map(|piece| piece.unwrap()))
+}
+
+/// Puts the piece on the nth empty square
+fn place(rank: &mut [Option<super::Piece>; 8], nth: usize, piece: super::Piece) {
+    if let Some(square) = rank.iter_mut().filter(|square| square.is_none()).nth(nth) {
+        *square = Some(piece);
+    }
+}
+
+impl super::Board {
+    /// The starting position of a Chess960 setup, with all the castles available
+    pub fn chess960(number: u16) -> Option<Self> {
+        let back_rank = chess960_back_rank(number)?;
+
+        let white = back_rank
+            .iter()
+            .map(|piece| piece.to_fen_char(super::Color::White))
+            .collect::<String>();
+        let black = white.to_ascii_lowercase();
+
+        // Shredder-FEN, the rooks are given by their files
+        let castles = back_rank
+            .iter()
+            .enumerate()
+            .filter(|(_, piece)| **piece == super::Piece::Rook)
+            .rev()
+            .map(|(file, _)| (b'A' + file as u8) as char)
+            .collect::<String>();
+
+        let fen = format!(
+            "{black}/pppppppp/8/8/8/8/PPPPPPPP/{white} w {castles}{} - 0 1",
+            castles.to_ascii_lowercase()
+        );
+
+        Self::from_fen(&fen).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Board, CastleSide, Color, File, Piece};
+    use super::{chess960_back_rank, CHESS960_SETUPS};
+
+    #[test]
+    fn numbers() {
+        assert_eq!(
+            Board::chess960(518).unwrap().to_fen(),
+            super::super::STARTING_FEN
+        );
+        assert_eq!(
+            Board::chess960(0).unwrap().to_fen(),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KFkf - 0 1"
+        );
+        assert_eq!(
+            Board::chess960(959).unwrap().to_fen(),
+            "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w CQcq - 0 1"
+        );
+        assert!(Board::chess960(CHESS960_SETUPS).is_none());
+    }
+
+    #[test]
+    fn all_setups() {
+        let mut seen = std::collections::HashSet::new();
+
+        for number in 0..CHESS960_SETUPS {
+            let rank = chess960_back_rank(number).unwrap();
+            assert!(seen.insert(rank), "{number} is there twice");
+
+            let files = |piece| {
+                rank.iter()
+                    .enumerate()
+                    .filter(|(_, p)| **p == piece)
+                    .map(|(file, _)| file)
+                    .collect::<Vec<usize>>()
+            };
+            let bishops = files(Piece::Bishop);
+            let rooks = files(Piece::Rook);
+            let king = files(Piece::King)[0];
+
+            assert_eq!(bishops.len(), 2);
+            assert_ne!(bishops[0] % 2, bishops[1] % 2);
+            assert!(rooks[0] < king && king < rooks[1]);
+
+            // Every setup has the four castles, and its FEN reads back to the same rooks
+            let board = Board::chess960(number).unwrap();
+            assert_eq!(
+                board
+                    .castle_rights()
+                    .rook_file(Color::Black, CastleSide::QueenSide),
+                File::from_index(rooks[0] as u8)
+            );
+            assert_eq!(Board::from_fen(&board.to_fen()), Ok(board));
+        }
+    }
+}
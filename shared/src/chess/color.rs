@@ -27,6 +27,22 @@ impl Color {
             Color::Black
         }
     }
+
+    /// To index arrays, white is 0
+    pub fn to_index(&self) -> usize {
+        match self {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+
+    /// The rank where the pieces of this color start
+    pub fn back_rank(&self) -> super::Rank {
+        match self {
+            Color::Black => super::Rank::Eight,
+            Color::White => super::Rank::One,
+        }
+    }
 }
 
 impl std::fmt::Display for Color {
@@ -1,16 +1,27 @@
+mod analysis;
+mod attacks;
 mod bitboard;
 mod board;
+mod castle;
+mod chess960;
 mod color;
 pub mod movegen;
 mod movement;
+mod notation;
+mod perft;
 mod piece;
 mod position;
 mod square;
+mod variant;
+mod zobrist;
 
 pub use bitboard::BitBoard;
-pub use board::Board;
+pub use board::{Board, Undo, STARTING_FEN};
+pub use castle::{CastleRights, CastleSide};
+pub use chess960::{chess960_back_rank, CHESS960_SETUPS};
 pub use color::Color;
 pub use movement::{ChessMove, RelativeChessMove};
-pub use piece::Piece;
+pub use piece::{Piece, ALL_PIECES, PROMOTION_PIECES};
 pub use position::{File, Position, Rank};
 pub use square::Square;
+pub use variant::{Variant, VariantWin, ALL_VARIANTS, CHECKS_TO_WIN, POCKET_PIECES};
@@ -3,10 +3,8 @@ pub fn all_legals(
     piece_pos: super::Position,
     board: &super::Board,
 ) -> Option<Vec<super::ChessMove>> {
-    let mut out = Vec::new();
-
     let piece_color = {
-        let (read_color, read_piece) = board.read(piece_pos)?;
+        let (read_color, read_piece) = board.piece_at(piece_pos)?;
 
         if read_piece != piece {
             return None;
@@ -14,88 +12,225 @@ pub fn all_legals(
         read_color
     };
 
-    out.append(&mut basic_moves(piece, piece_pos, piece_color));
+    let safety = KingSafety::new(board, piece_color);
 
+    let mut out = piece_legals(piece, piece_pos, piece_color, board, &safety);
 
-    // Specific moves
-    match piece {
-        super::Piece::King => {}
-        super::Piece::Rook => {}
-        super::Piece::Pawn => {
-            out.append(&mut pawn_first_move(piece, piece_pos, piece_color));
+    // The variant can forbid a move because of the other pieces, like the forced captures of antichess
+    if board.variant() != super::Variant::Standard {
+        let all = legal_moves(board);
+        out.retain(|mv| all.contains(mv));
+    }
+
+    debug!("All legal moves for {piece_color} {piece}s: {out:#?}");
+    Some(out)
+}
+
+/// Every legal move of the player that has to play next
+pub fn legal_moves(board: &super::Board) -> Vec<super::ChessMove> {
+    let color = board.next_to_play();
+    let safety = KingSafety::new(board, color);
+
+    let mut out = Vec::new();
 
-            out.append(&mut pawn_eat(piece, piece_pos, piece_color, board));
+    for piece in super::piece::ALL_PIECES {
+        for pos in (board.get_bb(piece) & board.color_bb(color)).positions() {
+            out.append(&mut piece_legals(piece, pos, color, board, &safety));
         }
-        _ => {}
     }
 
-    // Clean up the list
-    let out = out
-        .iter()
-        .filter(|mv| {
-            assert_eq!(mv.piece, piece); // if this panics, you fucked up
+    if board.variant().has_pockets() {
+        out.append(&mut drops(board, color, &safety));
+    }
 
-            // Who's turn is it ?
-            if board.next_to_play() != mv.color {
-                debug!("Wait your turn");
-                return false;
-            }
+    board.variant().filter_moves(board, &mut out);
+    out
+}
 
-            // Are the infos right ?
-            if board.read(mv.origin) != Some((mv.color, mv.piece)) {
-                debug!("The given origin doesn't contains the given piece");
-                return false;
-            }
+/// Pieces from the pocket can go on any empty square, except pawns on the first and last ranks, and can block a check
+fn drops(board: &super::Board, color: super::Color, safety: &KingSafety) -> Vec<super::ChessMove> {
+    const BACK_RANKS: u64 = 0xff00_0000_0000_00ff;
 
-            // Does the target square is taken by a teammate
-            if board.read(mv.target).map(|(color, _)| color == mv.color) == Some(true) {
-                debug!("Cannot eat teammate");
-                return false;
-            }
+    let squares = !board.occupancy() & safety.check_mask;
 
-            // Is the piece going over another piece ?
-            if !los_filter(**mv, board) {
-                return false;
-            }
+    super::POCKET_PIECES
+        .into_iter()
+        .filter(|piece| board.pocket(color, *piece) > 0)
+        .flat_map(|piece| {
+            let squares = if piece == super::Piece::Pawn {
+                squares & !super::BitBoard::from(BACK_RANKS)
+            } else {
+                squares
+            };
+            squares
+                .positions()
+                .map(move |pos| super::ChessMove::drop(piece, color, pos))
+        })
+        .collect()
+}
 
-            #[allow(clippy::single_match)] // will be multiple later (maybe)
-            match mv.piece {
-                crate::chess::Piece::Pawn => {
-                    // Pawns cannot eat in front of them
-                    if board.read(mv.target).is_some() && mv.origin.file() == mv.target.file() {
-                        debug!("Pawns cannot eat in front of them");
-                        return false;
-                    }
-                }
-                _ => (),
-            }
+fn piece_legals(
+    piece: super::Piece,
+    piece_pos: super::Position,
+    piece_color: super::Color,
+    board: &super::Board,
+    safety: &KingSafety,
+) -> Vec<super::ChessMove> {
+    // Who's turn is it ?
+    if board.next_to_play() != piece_color {
+        return Vec::new();
+    }
 
-            true
-        })
-        .cloned()
+    let occupancy = board.occupancy();
+    let enemies = board.color_bb(!piece_color);
+
+    let targets = match piece {
+        super::Piece::Pawn => {
+            // Pawns cannot eat in front of them, and can only eat air for en passant
+            let en_passant = board
+                .en_passant()
+                .map(super::BitBoard::from)
+                .unwrap_or_default();
+
+            pawn_pushes(piece_pos, piece_color, occupancy, board.variant())
+                | (super::BitBoard::pawn_attacks(piece_pos, piece_color) & (enemies | en_passant))
+        }
+        _ => attacks_from(piece, piece_color, piece_pos, occupancy) & !board.color_bb(piece_color),
+    };
+
+    let mut out = targets
+        .positions()
+        .map(|target| super::ChessMove::new(piece_pos, target, piece, piece_color, None))
         .collect::<Vec<super::ChessMove>>();
 
-    debug!("All legal moves for {piece_color} {piece}s: {out:#?}");
-    Some(out)
+    // Does it leave our king in check ?
+    out.retain(|mv| {
+        if mv.is_en_passant(board) && board.variant().royal_king() {
+            // Two pieces leave the king's rank at once, which the pins can't see, so just try it
+            let mut after = board.clone();
+            after.play(mv);
+            return checkers(&after, mv.color).is_empty();
+        }
+
+        safety.allows(mv)
+    });
+
+    if piece == super::Piece::Pawn {
+        out = out
+            .into_iter()
+            .flat_map(|mv| promotions(mv, board.variant()))
+            .collect();
+    }
+
+    // Castles check the squares of the king themselves
+    if piece == super::Piece::King {
+        out.append(&mut castles(piece, piece_pos, piece_color, board, safety));
+    }
+
+    out
+}
+
+/// A pawn reaching the last rank has to choose what it becomes, one move per choice
+fn promotions(mv: super::ChessMove, variant: super::Variant) -> Vec<super::ChessMove> {
+    if !mv.needs_promotion() {
+        return vec![mv];
+    }
+
+    variant
+        .promotion_pieces()
+        .iter()
+        .map(|promotion| {
+            super::ChessMove::new(mv.origin, mv.target, mv.piece, mv.color, Some(*promotion))
+        })
+        .collect()
+}
+
+/// One square forward, or two from the starting rank, as long as nothing is in the way
+fn pawn_pushes(
+    piece_pos: super::Position,
+    piece_color: super::Color,
+    occupancy: super::BitBoard,
+    variant: super::Variant,
+) -> super::BitBoard {
+    let mut out = super::BitBoard::default();
+
+    let dy = match piece_color {
+        super::Color::White => 1,
+        super::Color::Black => -1,
+    };
+
+    let Some(one) = offset(piece_pos, 0, dy).filter(|pos| !occupancy.read(*pos)) else {
+        return out;
+    };
+    out.set(one);
+
+    if variant.pawn_jumps_from(piece_color, piece_pos.rank()) {
+        if let Some(two) = offset(one, 0, dy).filter(|pos| !occupancy.read(*pos)) {
+            out.set(two);
+        }
+    }
+
+    out
 }
 
-fn basic_moves(
+fn castles(
     piece: super::Piece,
     piece_pos: super::Position,
     piece_color: super::Color,
+    board: &super::Board,
+    safety: &KingSafety,
 ) -> Vec<super::ChessMove> {
-    piece
-        .pseudo_legal_relative_moves()
+    let rank = piece_color.back_rank();
+
+    // The castle rights are lost once the king moves, and you can't castle out of a check
+    // Without a royal king there is nothing to castle
+    if !board.variant().royal_king() || piece_pos.rank() != rank || safety.danger.read(piece_pos) {
+        return Vec::new();
+    }
+
+    super::castle::ALL_CASTLE_SIDES
         .iter()
-        .flat_map(|rmv| {
-            let mut rmv = *rmv;
-            if piece_color == super::Color::Black {
-                rmv.y *= -1; // Flip it as black pieces move downwards
+        .flat_map(|side| {
+            let rook_file = board.castle_rights().rook_file(piece_color, *side)?;
+            let rook_pos = super::Position::from_file_rank(rook_file, rank);
+            if board.piece_at(rook_pos) != Some((piece_color, super::Piece::Rook)) {
+                return None;
             }
-            let target = super::Position::from_index(
-                (piece_pos.file().to_index() as i8 + rmv.x) as u8,
-                (piece_pos.rank().to_index() as i8 + rmv.y) as u8,
-            )?;
+
+            let king_target = super::Position::from_file_rank(side.king_target_file(), rank);
+            let rook_target = super::Position::from_file_rank(side.rook_target_file(), rank);
+
+            // In Chess960 the king and the rook can start anywhere, everything they go through has to be empty apart
+            // from themselves
+            let mut occupancy = board.occupancy();
+            occupancy.unset(piece_pos);
+            occupancy.unset(rook_pos);
+
+            let king_path = super::BitBoard::between(piece_pos, king_target)
+                | super::BitBoard::from(king_target);
+            let rook_path = super::BitBoard::between(rook_pos, rook_target)
+                | super::BitBoard::from(rook_target);
+            if !((king_path | rook_path) & occupancy).is_empty() {
+                return None;
+            }
+
+            // The king cannot go through or land on an attacked square, with the rook gone as it could have been
+            // hiding a slider
+            if king_path
+                .positions()
+                .any(|pos| !attackers(board, pos, !piece_color, occupancy).is_empty())
+            {
+                return None;
+            }
+
+            // The usual castles are still the king moving two squares, the others take the rook so they can't be
+            // mistaken for a king move
+            let target = if piece_pos.file() == super::File::E && rook_file == side.rook_file() {
+                king_target
+            } else {
+                rook_pos
+            };
+
             Some(super::ChessMove::new(
                 piece_pos,
                 target,
@@ -107,130 +242,353 @@ fn basic_moves(
         .collect::<Vec<super::ChessMove>>()
 }
 
-fn pawn_first_move(
-    piece: super::Piece,
-    piece_pos: super::Position,
-    piece_color: super::Color,
-) -> Vec<super::ChessMove> {
-    let delta: i8 = if piece_pos.rank() == super::Rank::Two && piece_color == super::Color::White {
-        2
-    } else if piece_pos.rank() == super::Rank::Seven && piece_color == super::Color::Black {
-        -2
-    } else {
-        warn!("Pawn first move check else");
-        return Vec::new();
-    };
+/// Everything needed to know if a move leaves its own king in check, computed once per position
+struct KingSafety {
+    // Squares the king cannot step on
+    danger: super::BitBoard,
+    // Squares the other pieces have to move to, every square when not in check
+    check_mask: super::BitBoard,
+    pins: Vec<(super::Position, super::BitBoard)>,
+}
 
-    let Some(target) = super::Position::from_index(
-        piece_pos.file().to_index(),
-        (piece_pos.rank().to_index() as i8 + delta) as u8,
-    ) else {
-        return Vec::new();
-    };
+impl KingSafety {
+    fn new(board: &super::Board, color: super::Color) -> Self {
+        let king = king_position(board, color).filter(|_| board.variant().royal_king());
+        let Some(king) = king else {
+            // Custom setups and some variants have no king to protect
+            return Self {
+                danger: super::BitBoard::default(),
+                check_mask: !super::BitBoard::default(),
+                pins: Vec::new(),
+            };
+        };
 
-    vec![super::ChessMove::new(
-        piece_pos,
-        target,
-        piece,
-        piece_color,
-        None,
-    )]
-}
+        // Remove the king from the occupancy so it can't hide behind itself while fleeing a slider
+        let mut occupancy = board.occupancy();
+        occupancy.unset(king);
+        let danger = attack_map(board, !color, occupancy);
 
-fn pawn_eat(
-    piece: super::Piece,
-    piece_pos: super::Position,
-    piece_color: super::Color,
-    board: &super::Board,
-) -> Vec<super::ChessMove> {
-    let flip: i8 = if piece_color == super::Color::White {
-        1
-    } else {
-        -1
-    };
+        let checkers = checkers(board, color);
 
-    [
-        super::Position::from_index(
-            (piece_pos.file().to_index() as i8 + -1) as u8,
-            (piece_pos.rank().to_index() as i8 + flip) as u8,
-        ),
-        super::Position::from_index(
-            (piece_pos.file().to_index() as i8 + 1) as u8,
-            (piece_pos.rank().to_index() as i8 + flip) as u8,
-        ),
-    ]
-    .iter()
-    .flatten() // Don't panic if the position is out of the board
-    .flat_map(|target| {
-        // Can't eat air
-        let Some((read_color, _read_piece)) = board.read(*target) else {
-            return None;
+        let check_mask = match checkers.count() {
+            0 => !super::BitBoard::default(),
+            1 => {
+                let checker = checkers.positions().next().unwrap();
+                checkers | super::BitBoard::between(king, checker)
+            }
+            // Double check, only the king can move
+            _ => super::BitBoard::default(),
         };
 
-        // Can't eat teammate
-        if read_color == piece_color {
-            return None;
+        Self {
+            danger,
+            check_mask,
+            pins: pins(board, color),
         }
+    }
 
-        Some(super::ChessMove::new(
-            piece_pos,
-            *target,
-            piece,
-            piece_color,
-            None,
-        ))
-    })
-    .collect::<Vec<super::ChessMove>>()
-}
-
-// Make sure that a piece won't go through another one
-fn los_filter(mv: super::ChessMove, board: &super::Board) -> bool {
-    // Bypass for knight as the line of sight check is useless and the target check done above
-    if mv.piece == super::Piece::Knight {
-        return true;
-    }
-
-    let target = (mv.target.file().to_index(), mv.target.rank().to_index());
-    let origin = (mv.origin.file().to_index(), mv.origin.rank().to_index());
-
-    let mut pos = origin;
-
-    let delta = (
-        match origin.0.cmp(&target.0) {
-            std::cmp::Ordering::Less => 1,
-            std::cmp::Ordering::Equal => 0,
-            std::cmp::Ordering::Greater => -1,
-        },
-        match origin.1.cmp(&target.1) {
-            std::cmp::Ordering::Less => 1,
-            std::cmp::Ordering::Equal => 0,
-            std::cmp::Ordering::Greater => -1,
-        },
-    );
-
-    loop {
-        // TODO: Can this trigger an integer overflow ?
-        pos.0 = (pos.0 as i8 + delta.0) as u8;
-        pos.1 = (pos.1 as i8 + delta.1) as u8;
-
-        #[allow(clippy::absurd_extreme_comparisons)]
-        #[allow(unused_comparisons)]
-        if pos.0 < 0 || pos.0 > 7 || pos.1 < 0 || pos.1 > 7 {
-            return false;
+    fn allows(&self, mv: &super::ChessMove) -> bool {
+        if mv.piece == super::Piece::King {
+            return !self.danger.read(mv.target);
         }
 
-        if let Some((read_color, _)) = board.read(pos.into()) {
-            // You can eat, but you can't go further
-            if read_color != mv.color && pos == target {
-                return true;
-            }
-            // You can't eat your allies
-            debug!("You can't eat your allies");
+        if !self.check_mask.read(mv.target) {
             return false;
         }
 
-        if pos == target {
-            return true;
+        self.pins
+            .iter()
+            .find(|(pinned, _)| *pinned == mv.origin)
+            .map(|(_, ray)| ray.read(mv.target))
+            .unwrap_or(true)
+    }
+}
+
+pub fn king_position(board: &super::Board, color: super::Color) -> Option<super::Position> {
+    (board.get_bb(super::Piece::King) & board.color_bb(color))
+        .positions()
+        .next()
+}
+
+/// Squares attacked by a piece standing on `pos`, sliders stop on the first piece of `occupancy`
+pub fn attacks_from(
+    piece: super::Piece,
+    color: super::Color,
+    pos: super::Position,
+    occupancy: super::BitBoard,
+) -> super::BitBoard {
+    match piece {
+        super::Piece::Pawn => super::BitBoard::pawn_attacks(pos, color),
+        super::Piece::Knight => super::BitBoard::knight_attacks(pos),
+        super::Piece::King => super::BitBoard::king_attacks(pos),
+        super::Piece::Bishop => super::BitBoard::bishop_attacks(pos, occupancy),
+        super::Piece::Rook => super::BitBoard::rook_attacks(pos, occupancy),
+        super::Piece::Queen => super::BitBoard::queen_attacks(pos, occupancy),
+    }
+}
+
+/// Every square attacked by the pieces of the given color
+pub fn attacked_squares(board: &super::Board, by: super::Color) -> super::BitBoard {
+    attack_map(board, by, board.occupancy())
+}
+
+fn attack_map(
+    board: &super::Board,
+    by: super::Color,
+    occupancy: super::BitBoard,
+) -> super::BitBoard {
+    let mut out = super::BitBoard::default();
+
+    for piece in super::piece::ALL_PIECES {
+        for pos in (board.get_bb(piece) & board.color_bb(by)).positions() {
+            out |= attacks_from(piece, by, pos, occupancy);
         }
     }
+
+    out
+}
+
+/// Pieces of the given color attacking `square`, sliders stop on the first piece of `occupancy`
+pub fn attackers(
+    board: &super::Board,
+    square: super::Position,
+    by: super::Color,
+    occupancy: super::BitBoard,
+) -> super::BitBoard {
+    let mut out = super::BitBoard::default();
+
+    // A piece attacks the square if a piece of the other color, moving like it from the square, would attack it
+    for piece in super::piece::ALL_PIECES {
+        out |= attacks_from(piece, !by, square, occupancy) & board.get_bb(piece);
+    }
+
+    out & board.color_bb(by)
+}
+
+/// The enemy pieces currently giving check to the king of the given color
+pub fn checkers(board: &super::Board, color: super::Color) -> super::BitBoard {
+    let Some(king) = king_position(board, color) else {
+        return super::BitBoard::default();
+    };
+
+    attackers(board, king, !color, board.occupancy())
+}
+
+/// Pieces of the given color that are pinned to their king, with the squares they can still move to
+pub fn pins(board: &super::Board, color: super::Color) -> Vec<(super::Position, super::BitBoard)> {
+    let mut out = Vec::new();
+
+    let Some(king) = king_position(board, color) else {
+        return out;
+    };
+
+    let occupancy = board.occupancy();
+    let friends = board.color_bb(color);
+    let enemies = board.color_bb(!color);
+    let queens = board.get_bb(super::Piece::Queen);
+
+    // Enemy sliders that would see the king if our own pieces were not there
+    let snipers = ((super::BitBoard::rook_attacks(king, enemies)
+        & (board.get_bb(super::Piece::Rook) | queens))
+        | (super::BitBoard::bishop_attacks(king, enemies)
+            & (board.get_bb(super::Piece::Bishop) | queens)))
+        & enemies;
+
+    for sniper in snipers.positions() {
+        let ray = super::BitBoard::between(king, sniper);
+        let blockers = ray & occupancy;
+
+        // With two pieces in the way, none of them is pinned
+        if blockers.count() == 1 && !(blockers & friends).is_empty() {
+            out.push((
+                blockers.positions().next().unwrap(),
+                ray | super::BitBoard::from(sniper),
+            ));
+        }
+    }
+
+    out
+}
+
+fn offset(pos: super::Position, dx: i8, dy: i8) -> Option<super::Position> {
+    let x = pos.file().to_index() as i8 + dx;
+    let y = pos.rank().to_index() as i8 + dy;
+
+    if !(0..8).contains(&x) || !(0..8).contains(&y) {
+        return None;
+    }
+
+    super::Position::from_index(x as u8, y as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Board, Piece, Position};
+
+    #[test]
+    fn start_position() {
+        assert_eq!(Board::default().legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn pinned_piece() {
+        // The bishop on e2 is pinned by the rook on e7
+        let b = Board::from_fen("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1").unwrap();
+
+        let bishop = Position::from((4, 1));
+        assert_eq!(
+            super::all_legals(Piece::Bishop, bishop, &b).unwrap(),
+            Vec::new()
+        );
+        assert_eq!(super::pins(&b, b.next_to_play()).len(), 1);
+    }
+
+    #[test]
+    fn pinned_piece_along_the_ray() {
+        // The rook on e2 can still slide towards the rook that pins it
+        let b = Board::from_fen("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1").unwrap();
+
+        let rook = Position::from((4, 1));
+        let moves = super::all_legals(Piece::Rook, rook, &b).unwrap();
+        assert_eq!(moves.len(), 5);
+        assert!(moves.iter().all(|mv| mv.target.file() == rook.file()));
+    }
+
+    #[test]
+    fn check_evasion() {
+        // Only the king can get out of this check
+        let b = Board::from_fen("4k3/4r3/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+
+        let moves = b.legal_moves();
+        assert_eq!(moves.len(), 4);
+        assert!(moves.iter().all(|mv| mv.piece == Piece::King));
+    }
+
+    #[test]
+    fn check_block_and_capture() {
+        // The knight on b3 can either take the checking rook or block on c1
+        let b = Board::from_fen("6k1/8/8/8/8/1N6/8/r5K1 w - - 0 1").unwrap();
+
+        let mut knight_targets = b
+            .legal_moves()
+            .iter()
+            .filter(|mv| mv.piece == Piece::Knight)
+            .map(|mv| mv.target.to_index())
+            .collect::<Vec<u8>>();
+        knight_targets.sort();
+
+        assert_eq!(knight_targets, vec![0, 2]);
+    }
+
+    #[test]
+    fn double_check() {
+        let b = Board::from_fen("4k3/8/8/8/1b6/8/8/R3K2r w - - 0 1").unwrap();
+
+        assert_eq!(super::checkers(&b, b.next_to_play()).count(), 2);
+        assert!(b.legal_moves().iter().all(|mv| mv.piece == Piece::King));
+    }
+
+    #[test]
+    fn king_cannot_walk_into_attack() {
+        // The checking pawn is defended, d1 and f1 are covered by the rook, f2 by the other pawn
+        let b = Board::from_fen("4k3/8/8/8/8/4p3/3p4/r3K3 w - - 0 1").unwrap();
+
+        let moves = b.legal_moves();
+
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].target, Position::from((4, 1)));
+    }
+
+    #[test]
+    fn castles() {
+        use super::super::CastleSide;
+
+        let b = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let castles = b
+            .legal_moves()
+            .iter()
+            .flat_map(|mv| mv.castle_side(&b))
+            .collect::<Vec<CastleSide>>();
+        assert_eq!(castles.len(), 2);
+
+        // No rights left
+        let b = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w kq - 0 1").unwrap();
+        assert!(b
+            .legal_moves()
+            .iter()
+            .all(|mv| mv.castle_side(&b).is_none()));
+
+        // b1 is taken, only the king side is possible
+        let b = Board::from_fen("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1").unwrap();
+        let castles = b
+            .legal_moves()
+            .iter()
+            .flat_map(|mv| mv.castle_side(&b))
+            .collect::<Vec<CastleSide>>();
+        assert_eq!(castles, vec![CastleSide::KingSide]);
+    }
+
+    #[test]
+    fn castles_through_check() {
+        // Out of check
+        let b = Board::from_fen("4k3/4r3/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        assert!(b
+            .legal_moves()
+            .iter()
+            .all(|mv| mv.castle_side(&b).is_none()));
+
+        // Through check (f1 and d1)
+        let b = Board::from_fen("4k3/3r1r2/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        assert!(b
+            .legal_moves()
+            .iter()
+            .all(|mv| mv.castle_side(&b).is_none()));
+
+        // Into check (g1), the queen side is still fine as b1 can be attacked
+        let b = Board::from_fen("4k3/1r4r1/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        let castles = b
+            .legal_moves()
+            .iter()
+            .flat_map(|mv| mv.castle_side(&b))
+            .collect::<Vec<super::super::CastleSide>>();
+        assert_eq!(castles, vec![super::super::CastleSide::QueenSide]);
+    }
+
+    #[test]
+    fn en_passant() {
+        let b = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+
+        let pawn = Position::from((4, 4));
+        let moves = super::all_legals(Piece::Pawn, pawn, &b).unwrap();
+        assert_eq!(moves.len(), 2);
+        assert!(moves.iter().any(|mv| mv.is_en_passant(&b)));
+    }
+
+    #[test]
+    fn en_passant_out_of_check() {
+        // The pawn that just jumped gives check, taking it en passant is legal
+        let b = Board::from_fen("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1").unwrap();
+
+        assert!(b.legal_moves().iter().any(|mv| mv.is_en_passant(&b)));
+    }
+
+    #[test]
+    fn en_passant_horizontal_pin() {
+        // Both pawns leave the fifth rank, the rook would see the king
+        let b = Board::from_fen("8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1").unwrap();
+
+        assert!(b.legal_moves().iter().all(|mv| !mv.is_en_passant(&b)));
+    }
+
+    #[test]
+    fn promotion() {
+        let b = Board::from_fen("1n6/P7/8/8/8/8/8/k6K w - - 0 1").unwrap();
+
+        let pawn = Position::from((0, 6));
+        let moves = super::all_legals(Piece::Pawn, pawn, &b).unwrap();
+
+        // 4 pieces, either by pushing or by taking the knight
+        assert_eq!(moves.len(), 8);
+        assert!(moves.iter().all(|mv| mv.promotion.is_some()));
+    }
 }
@@ -1,11 +1,3 @@
-lazy_static::lazy_static! {
-    pub static ref RELATIVE_MOVES: std::collections::HashMap<super::Piece, Vec<RelativeChessMove>> = {
-        let path = crate::file::Path::new(crate::file::FileSystem::Internal, "config/pieces_relative_moves.ron".to_string());
-        let bytes = crate::file::bytes(path);
-        ron::de::from_bytes::<std::collections::HashMap<super::Piece, Vec<RelativeChessMove>>>(&bytes).unwrap()
-    };
-}
-
 #[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize, Hash, PartialEq, Eq)]
 pub struct ChessMove {
     pub origin: super::Position,
@@ -13,8 +5,6 @@ pub struct ChessMove {
     pub piece: super::Piece,
     pub color: super::Color,
     pub promotion: Option<super::Piece>,
-    // castle: bool,
-    // eat: Option<super::Position>, // could be bool but the eaten piece is not at the target pos if en-passant, right ?
 }
 
 #[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize, Hash, PartialEq, Eq)]
@@ -41,10 +31,52 @@ impl ChessMove {
         }
     }
 
+    /// Crazyhouse puts a piece from the pocket on an empty square, the move starts and ends on that square
+    pub fn drop(piece: super::Piece, color: super::Color, target: super::Position) -> Self {
+        Self::new(target, target, piece, color, None)
+    }
+
+    /// A piece coming out of the pocket, no other move stays on its square
+    pub fn is_drop(&self) -> bool {
+        self.origin == self.target
+    }
+
     pub fn is_legal(&self, board: &super::Board) -> bool {
-        super::movegen::all_legals(self.piece, self.origin, board)
-            .unwrap_or_default()
-            .contains(self)
+        board.legal_moves().contains(self)
+    }
+
+    /// Castles are sent as the king moving two squares towards the rook, or as the king taking its own rook when that
+    /// can't work, like in most Chess960 setups
+    pub fn castle_side(&self, board: &super::Board) -> Option<super::CastleSide> {
+        if self.piece != super::Piece::King || self.origin.rank() != self.target.rank() {
+            return None;
+        }
+
+        let towards = self.target.file().to_index() as i8 - self.origin.file().to_index() as i8;
+        let onto_rook = board.piece_at(self.target) == Some((self.color, super::Piece::Rook));
+        if towards.abs() != 2 && !onto_rook {
+            return None;
+        }
+
+        match towards.signum() {
+            1 => Some(super::CastleSide::KingSide),
+            -1 => Some(super::CastleSide::QueenSide),
+            _ => None,
+        }
+    }
+
+    /// The only capture where the eaten piece isn't on the target square, it's right behind it
+    pub fn is_en_passant(&self, board: &super::Board) -> bool {
+        self.piece == super::Piece::Pawn
+            && self.origin.file() != self.target.file()
+            && board.en_passant() == Some(self.target)
+    }
+
+    /// A pawn reaching the last rank has to be promoted
+    pub fn needs_promotion(&self) -> bool {
+        self.piece == super::Piece::Pawn
+            && !self.is_drop()
+            && self.target.rank() == (!self.color).back_rank()
     }
 
     pub fn relative(&self) -> RelativeChessMove {
@@ -0,0 +1,472 @@
+// Text representations of a move: SAN ("Nbd7", "exd6", "e8=Q+", "O-O") and UCI's long algebraic ("e7e8q")
+
+use crate::error::chess::NotationError;
+
+impl super::ChessMove {
+    /// Standard Algebraic Notation, `board` is the position before the move is played
+    /// Safe to call with any move, the illegal ones just don't get their check suffix
+    pub fn to_san(&self, board: &super::Board) -> String {
+        let mut san = match self.castle_side(board) {
+            Some(super::CastleSide::KingSide) => String::from("O-O"),
+            Some(super::CastleSide::QueenSide) => String::from("O-O-O"),
+            None => self.san_body(board),
+        };
+
+        // Check and mate suffixes, a move that isn't legal is never played to find them
+        if self.is_legal(board) {
+            let mut after = board.clone();
+            after.play(self);
+            if after.is_checkmate() {
+                san.push('#');
+            } else if after.is_in_check() {
+                san.push('+');
+            }
+        }
+
+        san
+    }
+
+    fn san_body(&self, board: &super::Board) -> String {
+        let mut san = String::new();
+
+        // Crazyhouse drops, like "N@f3", pawns are written too
+        if self.is_drop() {
+            san.push(self.piece.to_fen_char(super::Color::White));
+            san.push('@');
+            san.push_str(&self.target.to_algebraic());
+            return san;
+        }
+
+        let capture = board.piece_at(self.target).is_some() || self.is_en_passant(board);
+
+        if self.piece == super::Piece::Pawn {
+            // Pawns only show where they come from when they eat
+            if capture {
+                san.push_str(&self.origin.file().to_string().to_lowercase());
+            }
+        } else {
+            san.push(self.piece.to_fen_char(super::Color::White));
+
+            // Other pieces of the same kind that could go to the same square
+            let others = board
+                .legal_moves()
+                .into_iter()
+                .filter(|mv| {
+                    mv.piece == self.piece && mv.target == self.target && mv.origin != self.origin
+                })
+                .collect::<Vec<super::ChessMove>>();
+
+            if !others.is_empty() {
+                let file = self.origin.file().to_string().to_lowercase();
+                let rank = self.origin.rank().to_string();
+
+                if others
+                    .iter()
+                    .all(|mv| mv.origin.file() != self.origin.file())
+                {
+                    san.push_str(&file);
+                } else if others
+                    .iter()
+                    .all(|mv| mv.origin.rank() != self.origin.rank())
+                {
+                    san.push_str(&rank);
+                } else {
+                    san.push_str(&file);
+                    san.push_str(&rank);
+                }
+            }
+        }
+
+        if capture {
+            san.push('x');
+        }
+
+        san.push_str(&self.target.to_algebraic());
+
+        if let Some(promotion) = self.promotion {
+            san.push('=');
+            san.push(promotion.to_fen_char(super::Color::White));
+        }
+
+        san
+    }
+
+    /// Reads a SAN move, a bit more forgiving than what `to_san` writes: 'x', '+', '#', '=' and annotations are optional,
+    /// and extra disambiguation is fine
+    pub fn from_san(san: &str, board: &super::Board) -> Result<Self, NotationError> {
+        let invalid = || NotationError::Invalid(san.to_string());
+
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+
+        let legal_moves = board.legal_moves();
+
+        // Castles, some people write them with zeros
+        let castle_side = match text {
+            "O-O" | "0-0" => Some(super::CastleSide::KingSide),
+            "O-O-O" | "0-0-0" => Some(super::CastleSide::QueenSide),
+            _ => None,
+        };
+        if let Some(side) = castle_side {
+            return legal_moves
+                .into_iter()
+                .find(|mv| mv.castle_side(board) == Some(side))
+                .ok_or_else(|| NotationError::Illegal(san.to_string()));
+        }
+
+        if let Some((piece, target)) = text.split_once('@') {
+            let piece = match piece {
+                "" => Some(super::Piece::Pawn),
+                piece => piece
+                    .chars()
+                    .next()
+                    .filter(|_| piece.len() == 1)
+                    .filter(char::is_ascii_uppercase)
+                    .and_then(super::Piece::from_fen_char),
+            }
+            .ok_or_else(invalid)?;
+            let target = super::Position::from_algebraic(target).ok_or_else(invalid)?;
+
+            let mv = super::ChessMove::drop(piece, board.next_to_play(), target);
+            if !legal_moves.contains(&mv) {
+                return Err(NotationError::Illegal(san.to_string()));
+            }
+            return Ok(mv);
+        }
+
+        let mut chars = text
+            .chars()
+            .filter(|c| *c != 'x' && *c != '=')
+            .collect::<Vec<char>>();
+
+        // Promotions are always uppercase, a lowercase 'b' is a file
+        let promotion = match chars.last() {
+            Some(c @ ('Q' | 'R' | 'B' | 'N')) => {
+                let promotion = super::Piece::from_fen_char(*c);
+                chars.pop();
+                promotion
+            }
+            _ => None,
+        };
+
+        if chars.len() < 2 {
+            return Err(invalid());
+        }
+        let target = chars.split_off(chars.len() - 2).iter().collect::<String>();
+        let target = super::Position::from_algebraic(&target).ok_or_else(invalid)?;
+
+        let mut hints = chars.as_slice();
+
+        let piece = match hints.first() {
+            Some(c @ ('K' | 'Q' | 'R' | 'B' | 'N')) => {
+                hints = &hints[1..];
+                super::Piece::from_fen_char(*c).unwrap()
+            }
+            _ => super::Piece::Pawn,
+        };
+
+        // What's left is the disambiguation
+        let mut file_hint = None;
+        let mut rank_hint = None;
+        for c in hints {
+            match c {
+                'a'..='h' if file_hint.is_none() => file_hint = Some(*c as u8 - b'a'),
+                '1'..='8' if rank_hint.is_none() => rank_hint = Some(*c as u8 - b'1'),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let mut candidates = legal_moves.into_iter().filter(|mv| {
+            mv.piece == piece
+                && mv.target == target
+                && mv.promotion == promotion
+                && mv.castle_side(board).is_none()
+                && file_hint
+                    .map(|file| mv.origin.file().to_index() == file)
+                    .unwrap_or(true)
+                && rank_hint
+                    .map(|rank| mv.origin.rank().to_index() == rank)
+                    .unwrap_or(true)
+        });
+
+        let Some(mv) = candidates.next() else {
+            return Err(NotationError::Illegal(san.to_string()));
+        };
+
+        if candidates.next().is_some() {
+            return Err(NotationError::Ambiguous(san.to_string()));
+        }
+
+        Ok(mv)
+    }
+
+    /// Long algebraic notation used by the UCI protocol, castles are written as the king's move ("e1g1", or "e1h1"
+    /// when the king goes to its rook)
+    pub fn to_uci(&self) -> String {
+        // Drops are written like in SAN
+        if self.is_drop() {
+            return format!(
+                "{}@{}",
+                self.piece.to_fen_char(super::Color::White),
+                self.target.to_algebraic()
+            );
+        }
+
+        let mut uci = format!(
+            "{}{}",
+            self.origin.to_algebraic(),
+            self.target.to_algebraic()
+        );
+
+        if let Some(promotion) = self.promotion {
+            uci.push(promotion.to_fen_char(super::Color::Black));
+        }
+
+        uci
+    }
+
+    pub fn from_uci(uci: &str, board: &super::Board) -> Result<Self, NotationError> {
+        let invalid = || NotationError::Invalid(uci.to_string());
+
+        let uci = uci.trim();
+        if !(4..=5).contains(&uci.len()) || !uci.is_ascii() {
+            return Err(invalid());
+        }
+
+        if uci.as_bytes()[1] == b'@' {
+            let piece = uci
+                .chars()
+                .next()
+                .filter(char::is_ascii_uppercase)
+                .and_then(super::Piece::from_fen_char)
+                .ok_or_else(invalid)?;
+            let target = super::Position::from_algebraic(&uci[2..]).ok_or_else(invalid)?;
+
+            let mv = super::ChessMove::drop(piece, board.next_to_play(), target);
+            if !mv.is_legal(board) {
+                return Err(NotationError::Illegal(uci.to_string()));
+            }
+            return Ok(mv);
+        }
+
+        let origin = super::Position::from_algebraic(&uci[0..2]).ok_or_else(invalid)?;
+        let target = super::Position::from_algebraic(&uci[2..4]).ok_or_else(invalid)?;
+
+        let promotion = match uci[4..].chars().next() {
+            Some(c @ ('q' | 'r' | 'b' | 'n')) => super::Piece::from_fen_char(c),
+            Some(_) => return Err(invalid()),
+            None => None,
+        };
+
+        let Some((color, piece)) = board.piece_at(origin) else {
+            return Err(NotationError::Illegal(uci.to_string()));
+        };
+
+        let mv = super::ChessMove::new(origin, target, piece, color, promotion);
+
+        if !mv.is_legal(board) {
+            return Err(NotationError::Illegal(uci.to_string()));
+        }
+
+        Ok(mv)
+    }
+}
+
+impl std::fmt::Display for super::ChessMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_uci())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Board, ChessMove, Variant};
+    use crate::error::chess::NotationError;
+
+    fn san_round_trip(fen: &str, sans: &[&str]) {
+        let board = Board::from_fen(fen).unwrap();
+
+        for san in sans {
+            let mv = ChessMove::from_san(san, &board).unwrap();
+            assert_eq!(&mv.to_san(&board), san);
+        }
+    }
+
+    #[test]
+    fn san() {
+        san_round_trip(super::super::STARTING_FEN, &["e4", "e3", "Nf3", "Na3"]);
+
+        // Captures, en passant, promotion with check
+        san_round_trip(
+            "r3k3/1P6/8/3pP3/8/8/8/4K3 w q d6 0 1",
+            &["exd6", "e6", "b8=Q+", "bxa8=N", "Kd2"],
+        );
+
+        // Castles, and a queen giving mate
+        san_round_trip(
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            &["O-O", "O-O-O", "Rxa8+"],
+        );
+        san_round_trip(
+            "rnbqkbnr/pppp1ppp/8/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 0 1",
+            &["Qxf7#"],
+        );
+    }
+
+    #[test]
+    fn san_disambiguation() {
+        // Two rooks on the same rank, then on the same file
+        san_round_trip("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", &["Rhf1", "Rab1"]);
+        san_round_trip("4k3/R7/8/8/8/8/8/R3K3 w - - 0 1", &["R1a3", "R7a5"]);
+
+        // Three queens, the file and the rank are needed
+        san_round_trip(
+            "8/k7/8/8/4Q2Q/8/8/1K5Q w - - 0 1",
+            &["Qh4e1", "Qee1", "Q1e1", "Q1h2", "Q4h2"],
+        );
+    }
+
+    #[test]
+    fn san_forgiving() {
+        let board = Board::default();
+
+        assert_eq!(
+            ChessMove::from_san("Ngf3", &board).unwrap(),
+            ChessMove::from_san("Nf3", &board).unwrap()
+        );
+        assert_eq!(
+            ChessMove::from_san("e4!?", &board).unwrap(),
+            ChessMove::from_san("e4", &board).unwrap()
+        );
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        assert_eq!(
+            ChessMove::from_san("0-0", &board).unwrap(),
+            ChessMove::from_san("O-O", &board).unwrap()
+        );
+    }
+
+    #[test]
+    fn san_errors() {
+        let board = Board::default();
+
+        assert_eq!(
+            ChessMove::from_san("e5", &board),
+            Err(NotationError::Illegal(String::from("e5")))
+        );
+        assert_eq!(
+            ChessMove::from_san("Zf3", &board),
+            Err(NotationError::Invalid(String::from("Zf3")))
+        );
+        assert_eq!(
+            ChessMove::from_san("O-O", &board),
+            Err(NotationError::Illegal(String::from("O-O")))
+        );
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+        assert_eq!(
+            ChessMove::from_san("Rf1", &board),
+            Err(NotationError::Ambiguous(String::from("Rf1")))
+        );
+    }
+
+    #[test]
+    fn san_unchecked() {
+        use super::super::{Color, File, Piece, Position, Rank};
+
+        // Moves straight from a client, writing them must not play them
+        let board = Board::default();
+        let pos = |file, rank| Position::from_file_rank(file, rank);
+
+        // Looks like a castle, but the bishop is in the way
+        let castle = ChessMove::new(
+            pos(File::E, Rank::One),
+            pos(File::G, Rank::One),
+            Piece::King,
+            Color::White,
+            None,
+        );
+        assert_eq!(castle.to_san(&board), "O-O");
+
+        // Nothing on e4
+        let empty = ChessMove::new(
+            pos(File::E, Rank::Four),
+            pos(File::E, Rank::Five),
+            Piece::Pawn,
+            Color::White,
+            None,
+        );
+        assert_eq!(empty.to_san(&board), "e5");
+    }
+
+    #[test]
+    fn uci() {
+        let board = Board::from_fen("r3k3/1P6/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+
+        for uci in ["b7b8q", "b7a8n", "e1g1", "h1h8"] {
+            assert_eq!(ChessMove::from_uci(uci, &board).unwrap().to_uci(), uci);
+        }
+
+        assert_eq!(
+            ChessMove::from_uci("b7b8", &board),
+            Err(NotationError::Illegal(String::from("b7b8")))
+        );
+        assert_eq!(
+            ChessMove::from_uci("b7b8k", &board),
+            Err(NotationError::Invalid(String::from("b7b8k")))
+        );
+        assert_eq!(
+            ChessMove::from_uci("e2e4", &board),
+            Err(NotationError::Illegal(String::from("e2e4")))
+        );
+        assert_eq!(
+            ChessMove::from_uci("z1e4", &board),
+            Err(NotationError::Invalid(String::from("z1e4")))
+        );
+    }
+
+    #[test]
+    fn drops() {
+        let board =
+            Board::from_variant_fen("4k3/8/8/8/8/8/r3K3/8[NP] w - - 0 1", Variant::Crazyhouse)
+                .unwrap();
+
+        for (san, uci) in [("N@d2", "N@d2"), ("P@b2", "P@b2")] {
+            let mv = ChessMove::from_san(san, &board).unwrap();
+            assert!(mv.is_drop());
+            assert_eq!(mv.to_san(&board), san);
+            assert_eq!(mv.to_uci(), uci);
+            assert_eq!(ChessMove::from_uci(uci, &board), Ok(mv));
+        }
+        assert_eq!(
+            ChessMove::from_san("@c2", &board).unwrap().to_san(&board),
+            "P@c2"
+        );
+
+        // Not blocking the check, or nothing to drop
+        assert_eq!(
+            ChessMove::from_san("N@d4", &board),
+            Err(NotationError::Illegal(String::from("N@d4")))
+        );
+        assert_eq!(
+            ChessMove::from_uci("Q@d2", &board),
+            Err(NotationError::Illegal(String::from("Q@d2")))
+        );
+        assert_eq!(
+            ChessMove::from_uci("x@d2", &board),
+            Err(NotationError::Invalid(String::from("x@d2")))
+        );
+
+        // Built by hand like a client could, kings and empty pockets are refused without being played
+        for board in [board.clone(), Board::default()] {
+            for piece in [super::super::Piece::King, super::super::Piece::Queen] {
+                let mv = ChessMove::drop(
+                    piece,
+                    board.next_to_play(),
+                    super::super::Position::from_algebraic("d3").unwrap(),
+                );
+                println!("{}", mv.to_san(&board));
+                assert!(board.clone().make_move(&mv).is_err());
+            }
+        }
+    }
+}
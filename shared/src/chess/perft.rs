@@ -0,0 +1,279 @@
+// Move path enumeration, counts the leaves of the legal move tree to check the move generation against known numbers
+// https://www.chessprogramming.org/Perft_Results
+
+impl super::Board {
+    /// Number of positions reachable in exactly `depth` moves
+    pub fn perft(&self, depth: u32) -> u64 {
+        // The same board is used for the whole tree, moves are undone on the way back
+        self.clone().perft_inner(depth)
+    }
+
+    /// Perft of every legal move, to find which one is wrong when the total doesn't match a reference engine
+    /// `depth` counts the move itself, so nothing is returned for 0
+    pub fn divide(&self, depth: u32) -> Vec<(super::ChessMove, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        let mut board = self.clone();
+
+        self.legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let undo = board.play(&mv);
+                let nodes = board.perft_inner(depth - 1);
+                board.unmake_move(undo);
+                (mv, nodes)
+            })
+            .collect()
+    }
+
+    fn perft_inner(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.legal_moves();
+
+        // No need to play the last moves
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+        for mv in &moves {
+            let undo = self.play(mv);
+            nodes += self.perft_inner(depth - 1);
+            self.unmake_move(undo);
+        }
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Board, Variant};
+
+    fn check(fen: &str, expected: &[u64]) {
+        check_variant(fen, Variant::Standard, expected);
+    }
+
+    fn check_variant(fen: &str, variant: Variant, expected: &[u64]) {
+        let board = Board::from_variant_fen(fen, variant).unwrap();
+
+        for (depth, nodes) in expected.iter().enumerate() {
+            let depth = depth as u32 + 1;
+            println!("{variant} {fen} depth {depth}: {nodes}");
+            assert_eq!(board.perft(depth), *nodes, "{fen} at depth {depth}");
+        }
+    }
+
+    #[test]
+    fn start_position() {
+        check(super::super::STARTING_FEN, &[20, 400, 8_902, 197_281]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        check(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2_039, 97_862],
+        );
+    }
+
+    #[test]
+    fn en_passant_pins() {
+        // Position 3, lots of en passant captures that would leave the king in check
+        check(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2_812, 43_238],
+        );
+    }
+
+    #[test]
+    fn promotions() {
+        // Position 4, and its mirror
+        check(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9_467],
+        );
+        check(
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            &[6, 264, 9_467],
+        );
+
+        // Position 5
+        check(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1_486, 62_379],
+        );
+    }
+
+    #[test]
+    fn middlegame() {
+        // Position 6
+        check(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2_079, 89_890],
+        );
+    }
+
+    #[test]
+    fn chess960() {
+        // Castles with the king or the rook already on their target square, or going through each other
+        check(
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            &[21, 528, 12_189],
+        );
+        check(
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+            &[20, 479, 10_471],
+        );
+        check(
+            "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+            &[22, 593, 13_440],
+        );
+
+        // The standard setup doesn't change anything
+        let board = Board::chess960(518).unwrap();
+        assert_eq!(board.perft(3), 8_902);
+    }
+
+    #[test]
+    fn king_of_the_hill() {
+        check_variant(
+            super::super::STARTING_FEN,
+            Variant::KingOfTheHill,
+            &[20, 400, 8_902, 197_281],
+        );
+
+        // Stepping on d4 or e4 ends the game, nothing can be played after
+        check_variant(
+            "k7/8/8/8/8/3K4/8/8 w - - 0 1",
+            Variant::KingOfTheHill,
+            &[8, 18],
+        );
+    }
+
+    #[test]
+    fn three_check() {
+        check_variant(
+            Variant::ThreeCheck.start_fen(),
+            Variant::ThreeCheck,
+            &[20, 400, 8_902, 197_281],
+        );
+
+        // Ra8+ is the third check, the 3 answers of standard chess are gone
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 1+3 0 1";
+        let standard = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let three_check = Board::from_variant_fen(fen, Variant::ThreeCheck).unwrap();
+        assert_eq!(three_check.perft(1), standard.perft(1));
+        assert_eq!(three_check.perft(2), standard.perft(2) - 3);
+    }
+
+    #[test]
+    fn antichess() {
+        check_variant(
+            Variant::Antichess.start_fen(),
+            Variant::Antichess,
+            &[20, 400, 8_067, 153_299],
+        );
+    }
+
+    #[test]
+    fn horde() {
+        check_variant(
+            Variant::Horde.start_fen(),
+            Variant::Horde,
+            &[8, 128, 1_274, 23_310],
+        );
+    }
+
+    #[test]
+    fn crazyhouse() {
+        check_variant(
+            Variant::Crazyhouse.start_fen(),
+            Variant::Crazyhouse,
+            &[20, 400, 8_902, 197_281],
+        );
+
+        // Drops go on every empty square, pawns not on the back ranks, and only between the king and the checker
+        check_variant(
+            "4k3/8/8/8/8/8/8/4K3[Nn] w - - 0 1",
+            Variant::Crazyhouse,
+            &[67],
+        );
+        check_variant(
+            "4k3/8/8/8/8/8/8/4K3[P] w - - 0 1",
+            Variant::Crazyhouse,
+            &[53],
+        );
+        check_variant(
+            "4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1",
+            Variant::Crazyhouse,
+            &[6],
+        );
+    }
+
+    fn check_depth(fen: &str, depth: u32, nodes: u64) {
+        let board = Board::from_fen(fen).unwrap();
+        println!("{fen} depth {depth}: {nodes}");
+        assert_eq!(board.perft(depth), nodes, "{fen} at depth {depth}");
+    }
+
+    #[test]
+    fn edge_cases() {
+        // Self stalemate
+        check_depth("K1k5/8/P7/8/8/8/8/8 w - - 0 1", 6, 2_217);
+        // Underpromotion to give check
+        check_depth("8/P1k5/K7/8/8/8/8/8 w - - 0 1", 6, 92_683);
+        // Double check
+        check_depth("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", 4, 23_527);
+    }
+
+    // Too slow for debug builds, run them with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn edge_cases_deep() {
+        // Illegal en passant, the pawn is pinned or the capture uncovers a check
+        check_depth("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 6, 1_134_888);
+        check_depth("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", 6, 1_015_133);
+        // En passant capture that checks the opponent
+        check_depth("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 6, 1_440_467);
+        // Castles that give check
+        check_depth("5k2/8/8/8/8/8/8/4K2R w K - 0 1", 6, 661_072);
+        check_depth("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", 6, 803_711);
+        // Castle rights lost when the rooks are taken, castles prevented by attacks
+        check_depth("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 4, 1_274_206);
+        check_depth("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 4, 1_720_476);
+        // Promotions out of check and giving check
+        check_depth("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 6, 3_821_001);
+        check_depth("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", 6, 217_342);
+        // Discovered check
+        check_depth("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", 5, 1_004_658);
+        // Stalemate and checkmate
+        check_depth("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", 7, 567_584);
+    }
+
+    #[test]
+    #[ignore]
+    fn crazyhouse_deep() {
+        // The first captured pieces get dropped back
+        let board = Board::from_variant(Variant::Crazyhouse);
+        assert_eq!(board.perft(5), 4_888_832);
+    }
+
+    #[test]
+    fn divide() {
+        let board = Board::default();
+        let divide = board.divide(3);
+
+        for (mv, nodes) in &divide {
+            println!("{}: {nodes}", mv.to_uci());
+        }
+
+        assert_eq!(divide.len(), 20);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 8_902);
+        assert!(board.divide(0).is_empty());
+    }
+}
@@ -7,6 +7,9 @@ pub const ALL_PIECES: [Piece; 6] = [
     Piece::King,
 ];
 
+/// What a pawn can become once it reaches the last rank
+pub const PROMOTION_PIECES: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
 #[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize, Hash, PartialEq, Eq)]
 pub enum Piece {
     King,
@@ -18,10 +21,16 @@ pub enum Piece {
 }
 
 impl Piece {
-    pub fn pseudo_legal_relative_moves(&self) -> &Vec<super::movement::RelativeChessMove> {
-        let movelist = super::movement::RELATIVE_MOVES.get(self);
-
-        movelist.unwrap()
+    /// Position of the piece in [`ALL_PIECES`], to index arrays
+    pub fn to_index(&self) -> usize {
+        match self {
+            Piece::Pawn => 0,
+            Piece::Knight => 1,
+            Piece::Bishop => 2,
+            Piece::Rook => 3,
+            Piece::Queen => 4,
+            Piece::King => 5,
+        }
     }
 
     pub fn from_fen_char(c: char) -> Option<Self> {
@@ -35,6 +44,23 @@ impl Piece {
             _ => None,
         }
     }
+
+    /// Uppercase for white, lowercase for black
+    pub fn to_fen_char(&self, color: super::Color) -> char {
+        let c = match self {
+            Piece::King => 'k',
+            Piece::Queen => 'q',
+            Piece::Rook => 'r',
+            Piece::Bishop => 'b',
+            Piece::Knight => 'n',
+            Piece::Pawn => 'p',
+        };
+
+        match color {
+            super::Color::White => c.to_ascii_uppercase(),
+            super::Color::Black => c,
+        }
+    }
 }
 
 impl std::fmt::Display for Piece {
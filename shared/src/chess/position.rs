@@ -74,6 +74,27 @@ impl Position {
         // self.rank.to_index() << 3 ^ self.file.to_index() // crates.rs/chess method
     }
 
+    /// Reads a square in the lowercase algebraic format used by FEN, like "e3"
+    pub fn from_algebraic(s: &str) -> Option<Self> {
+        let mut chars = s.chars();
+        let file = chars.next()?;
+        let rank = chars.next()?;
+
+        if chars.next().is_some() || !('a'..='h').contains(&file) {
+            return None;
+        }
+
+        Self::from_index(file as u8 - b'a', rank.to_digit(10)?.checked_sub(1)? as u8)
+    }
+
+    pub fn to_algebraic(&self) -> String {
+        format!(
+            "{}{}",
+            self.file.to_string().to_lowercase(),
+            self.rank.to_string()
+        )
+    }
+
     pub fn file(&self) -> File {
         self.file
     }
@@ -343,6 +364,19 @@ mod tests {
         assert_eq!(Position::from_index(8, 8), None)
     }
 
+    #[test]
+    fn algebraic() {
+        let pos = Position::from_algebraic("e3").unwrap();
+        assert_eq!(pos, Position::from_file_rank(File::E, Rank::Three));
+        assert_eq!(pos.to_algebraic(), "e3");
+
+        assert_eq!(Position::from_algebraic("h8").unwrap().to_index(), 63);
+        assert_eq!(Position::from_algebraic("i1"), None);
+        assert_eq!(Position::from_algebraic("a0"), None);
+        assert_eq!(Position::from_algebraic("a9"), None);
+        assert_eq!(Position::from_algebraic("a10"), None);
+    }
+
     #[test]
     fn r#move() {
         let mut pos = Position::from_index(0, 7).unwrap();
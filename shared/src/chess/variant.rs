@@ -0,0 +1,494 @@
+// Rules that change the game, the board carries its variant and asks it whenever the rules differ from standard chess
+// Each hook is a match on the variant, a new variant has to go through all of them
+// https://lichess.org/variant
+
+#[derive(
+    Default, Debug, Clone, Copy, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// Bringing the king to one of the four center squares wins
+    KingOfTheHill,
+    /// Giving check three times wins
+    ThreeCheck,
+    /// Captures are forced, the king is a piece like the others, and the player that can't move anymore wins
+    Antichess,
+    /// White has 36 pawns and no king, black wins by taking them all
+    Horde,
+    /// The pieces taken go in the pocket of the player that took them, who can drop them back on the board instead
+    /// of moving
+    Crazyhouse,
+}
+
+pub const ALL_VARIANTS: [Variant; 6] = [
+    Variant::Standard,
+    Variant::KingOfTheHill,
+    Variant::ThreeCheck,
+    Variant::Antichess,
+    Variant::Horde,
+    Variant::Crazyhouse,
+];
+
+/// How a game ends in a variant, on top of the usual checkmates and draws
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    enum_variant_name::VariantName,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum VariantWin {
+    KingInTheCenter,
+    ThirdCheck,
+    NoMovesLeft,
+    HordeDestroyed,
+}
+
+/// Number of checks that wins a three-check game
+pub const CHECKS_TO_WIN: u8 = 3;
+
+const CENTER: u64 = 0x0000_0018_1800_0000;
+
+const HORDE_FEN: &str =
+    "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1";
+const ANTICHESS_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1";
+const CRAZYHOUSE_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1";
+
+/// What can be in a pocket, in the order the FEN writes them
+pub const POCKET_PIECES: [super::Piece; 5] = [
+    super::Piece::Queen,
+    super::Piece::Rook,
+    super::Piece::Bishop,
+    super::Piece::Knight,
+    super::Piece::Pawn,
+];
+
+// Antichess pawns can become kings too
+const ANTICHESS_PROMOTIONS: [super::Piece; 5] = [
+    super::Piece::Queen,
+    super::Piece::Rook,
+    super::Piece::Bishop,
+    super::Piece::Knight,
+    super::Piece::King,
+];
+
+impl Variant {
+    pub fn start_fen(&self) -> &'static str {
+        match self {
+            Variant::Antichess => ANTICHESS_FEN,
+            Variant::Horde => HORDE_FEN,
+            Variant::Crazyhouse => CRAZYHOUSE_FEN,
+            _ => super::STARTING_FEN,
+        }
+    }
+
+    /// The variants with the standard pieces can also start from a Chess960 setup
+    pub fn has_own_setup(&self) -> bool {
+        matches!(self, Variant::Antichess | Variant::Horde)
+    }
+
+    /// Taken pieces are kept to be dropped later
+    pub fn has_pockets(&self) -> bool {
+        *self == Variant::Crazyhouse
+    }
+
+    /// A king that can't be left in check, and that can castle
+    pub fn royal_king(&self) -> bool {
+        *self != Variant::Antichess
+    }
+
+    /// How many kings a player must have, none if any number is fine
+    pub fn king_count(&self, color: super::Color) -> Option<u32> {
+        match (self, color) {
+            (Variant::Antichess, _) => None,
+            (Variant::Horde, super::Color::White) => Some(0),
+            _ => Some(1),
+        }
+    }
+
+    /// Horde pawns start on the first rank, and can jump two squares from there
+    pub fn pawn_jumps_from(&self, color: super::Color, rank: super::Rank) -> bool {
+        match color {
+            super::Color::White => {
+                rank == super::Rank::Two || (*self == Variant::Horde && rank == super::Rank::One)
+            }
+            super::Color::Black => rank == super::Rank::Seven,
+        }
+    }
+
+    pub fn promotion_pieces(&self) -> &'static [super::Piece] {
+        match self {
+            Variant::Antichess => &ANTICHESS_PROMOTIONS,
+            _ => &super::piece::PROMOTION_PIECES,
+        }
+    }
+
+    /// Removes the moves that the variant forbids from the moves of the position
+    pub fn filter_moves(&self, board: &super::Board, moves: &mut Vec<super::ChessMove>) {
+        match self {
+            // The game is over, there is nothing left to play
+            _ if self.finished(board).is_some() => moves.clear(),
+            Variant::Antichess => {
+                let is_capture = |mv: &super::ChessMove| {
+                    board.piece_at(mv.target).is_some() || mv.is_en_passant(board)
+                };
+                if moves.iter().any(is_capture) {
+                    moves.retain(is_capture);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// The player that won by the rules of the variant, if the game is over that way
+    pub fn win(&self, board: &super::Board) -> Option<(VariantWin, super::Color)> {
+        if let Some(win) = self.finished(board) {
+            return Some(win);
+        }
+
+        match self {
+            Variant::Antichess if board.legal_moves().is_empty() => {
+                Some((VariantWin::NoMovesLeft, board.next_to_play()))
+            }
+            _ => None,
+        }
+    }
+
+    /// The wins that can be seen without looking at the moves
+    fn finished(&self, board: &super::Board) -> Option<(VariantWin, super::Color)> {
+        match self {
+            Variant::KingOfTheHill => [super::Color::White, super::Color::Black]
+                .into_iter()
+                .find(|color| {
+                    !(board.get_bb(super::Piece::King)
+                        & board.color_bb(*color)
+                        & super::BitBoard::from(CENTER))
+                    .is_empty()
+                })
+                .map(|color| (VariantWin::KingInTheCenter, color)),
+            Variant::ThreeCheck => [super::Color::White, super::Color::Black]
+                .into_iter()
+                .find(|color| board.checks_given(*color) >= CHECKS_TO_WIN)
+                .map(|color| (VariantWin::ThirdCheck, color)),
+            Variant::Horde if board.color_bb(super::Color::White).is_empty() => {
+                Some((VariantWin::HordeDestroyed, super::Color::Black))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The names used in the PGN Variant tag
+        let name = match self {
+            Variant::Standard => "Standard",
+            Variant::KingOfTheHill => "King of the Hill",
+            Variant::ThreeCheck => "Three-check",
+            Variant::Antichess => "Antichess",
+            Variant::Horde => "Horde",
+            Variant::Crazyhouse => "Crazyhouse",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Some tools write the names differently, only the letters are compared
+        let simplify = |name: &str| {
+            name.chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect::<String>()
+                .to_ascii_lowercase()
+        };
+
+        ALL_VARIANTS
+            .into_iter()
+            .find(|variant| simplify(&variant.to_string()) == simplify(s))
+            .ok_or_else(|| format!("Unknown variant '{s}'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Board, ChessMove, Color, Piece, Position, Rank};
+    use super::{Variant, VariantWin, ALL_VARIANTS};
+
+    fn board(fen: &str, variant: Variant) -> Board {
+        Board::from_variant_fen(fen, variant).unwrap()
+    }
+
+    fn play(board: &mut Board, uci: &str) {
+        let mv = ChessMove::from_uci(uci, board).unwrap();
+        board.make_move(&mv).unwrap();
+    }
+
+    #[test]
+    fn names() {
+        for variant in ALL_VARIANTS {
+            assert_eq!(variant.to_string().parse::<Variant>(), Ok(variant));
+        }
+        assert_eq!(
+            "kingOfTheHill".parse::<Variant>(),
+            Ok(Variant::KingOfTheHill)
+        );
+        assert_eq!(
+            "3check".parse::<Variant>(),
+            Err(String::from("Unknown variant '3check'"))
+        );
+    }
+
+    #[test]
+    fn start_positions() {
+        for variant in ALL_VARIANTS {
+            let board = Board::from_variant(variant);
+            println!("{variant}: {}", board.to_fen());
+            assert_eq!(board.variant(), variant);
+            assert!(variant.win(&board).is_none());
+        }
+
+        let horde = Board::from_variant(Variant::Horde);
+        assert_eq!(horde.color_bb(Color::White).count(), 36);
+        assert!(horde.get_bb(Piece::King).count() == 1);
+
+        // Not valid outside of their variant
+        assert!(Board::from_fen(Variant::Horde.start_fen()).is_err());
+        assert!(Board::from_variant_fen("8/8/8/8/8/8/8/k7 w - - 0 1", Variant::Antichess).is_ok());
+        assert!(Board::from_variant_fen("8/8/8/8/8/8/8/k7 w - - 0 1", Variant::Standard).is_err());
+    }
+
+    #[test]
+    fn king_of_the_hill() {
+        let mut b = board("k7/8/8/8/8/3K4/8/8 w - - 0 1", Variant::KingOfTheHill);
+        play(&mut b, "d3e4");
+
+        assert_eq!(
+            Variant::KingOfTheHill.win(&b),
+            Some((VariantWin::KingInTheCenter, Color::White))
+        );
+        assert!(b.legal_moves().is_empty());
+        assert!(!b.is_stalemate());
+        assert!(!b.is_insufficient_material());
+
+        // Just a king move in standard chess, and two lone kings are a draw
+        let mut b = board("k7/8/8/8/8/3K4/8/8 w - - 0 1", Variant::Standard);
+        play(&mut b, "d3e4");
+        assert!(Variant::Standard.win(&b).is_none());
+        assert!(b.is_insufficient_material());
+    }
+
+    #[test]
+    fn three_check() {
+        let mut b = board("4k3/8/8/8/8/8/8/R3K3 w - - 2+3 0 1", Variant::ThreeCheck);
+        assert_eq!(b.checks_given(Color::White), 1);
+        assert_eq!(b.checks_given(Color::Black), 0);
+
+        play(&mut b, "a1a7");
+        assert_eq!(b.checks_given(Color::White), 1);
+        play(&mut b, "e8d8");
+        play(&mut b, "a7a8");
+
+        assert_eq!(b.checks_given(Color::White), 2);
+        assert_eq!(b.to_fen(), "R2k4/8/8/8/8/8/8/4K3 b - - 1+3 3 2");
+        assert!(Variant::ThreeCheck.win(&b).is_none());
+
+        play(&mut b, "d8c7");
+        play(&mut b, "a8a7");
+        assert_eq!(
+            Variant::ThreeCheck.win(&b),
+            Some((VariantWin::ThirdCheck, Color::White))
+        );
+        assert!(b.legal_moves().is_empty());
+
+        // Taking the check back gives it back
+        let mut b = board("4k3/8/8/8/8/8/8/R3K3 w - - 1+3 0 1", Variant::ThreeCheck);
+        let mv = ChessMove::from_uci("a1a8", &b).unwrap();
+        let hash = b.hash();
+        let undo = b.make_move(&mv).unwrap();
+        assert!(Variant::ThreeCheck.win(&b).is_some());
+        b.unmake_move(undo);
+        assert_eq!(b.checks_given(Color::White), 2);
+        assert_eq!(b.hash(), hash);
+
+        // The same pieces with other checks are another position
+        let other = board("4k3/8/8/8/8/8/8/R3K3 w - - 3+3 0 1", Variant::ThreeCheck);
+        assert_ne!(other.hash(), hash);
+    }
+
+    #[test]
+    fn antichess() {
+        // The rook has to take, the king doesn't care about the bishop
+        let b = board("8/8/8/8/8/1b6/8/1R2K3 w - - 0 1", Variant::Antichess);
+        let moves = b.legal_moves();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].to_uci(), "b1b3");
+        assert!(!b.is_in_check());
+
+        let b = board("8/8/8/8/8/8/8/4K2r w - - 0 1", Variant::Antichess);
+        assert!(!b.is_in_check());
+        assert!(b
+            .legal_moves()
+            .iter()
+            .all(|mv| mv.castle_side(&b).is_none()));
+
+        // Promoting to a king
+        let b = board("8/P7/8/8/8/8/8/7k w - - 0 1", Variant::Antichess);
+        assert!(b
+            .legal_moves()
+            .iter()
+            .any(|mv| mv.promotion == Some(Piece::King)));
+
+        // White lost everything, that's a win
+        let mut b = board("8/8/8/8/8/8/1p6/2R5 b - - 0 1", Variant::Antichess);
+        play(&mut b, "b2c1q");
+        assert_eq!(
+            Variant::Antichess.win(&b),
+            Some((VariantWin::NoMovesLeft, Color::White))
+        );
+        assert_eq!(
+            crate::game::EndReason::from_board(&b),
+            Some((
+                crate::game::EndReason::Variant(VariantWin::NoMovesLeft),
+                Some(Color::White)
+            ))
+        );
+
+        // So is being stuck
+        let b = board("8/8/8/8/8/p7/P7/8 w - - 0 1", Variant::Antichess);
+        assert_eq!(
+            Variant::Antichess.win(&b),
+            Some((VariantWin::NoMovesLeft, Color::White))
+        );
+        assert!(!b.is_stalemate());
+    }
+
+    #[test]
+    fn horde() {
+        let b = Board::from_variant(Variant::Horde);
+
+        // The pawns of the first rank can jump
+        let jump = ChessMove::new(
+            Position::from_algebraic("a1").unwrap(),
+            Position::from_algebraic("a3").unwrap(),
+            Piece::Pawn,
+            Color::White,
+            None,
+        );
+        assert!(!jump.is_legal(&b));
+        let b = board("4k3/8/8/8/8/8/8/P7 w - - 0 1", Variant::Horde);
+        assert!(jump.is_legal(&b));
+
+        let mut b = board("4k3/8/8/8/8/8/1p6/P7 w - - 0 1", Variant::Horde);
+        play(&mut b, "a1a3");
+        assert_eq!(b.en_passant(), Position::from_algebraic("a2"));
+
+        // Taking the last pawn
+        let mut b = board("4k3/8/8/8/8/8/1p6/2P5 b - - 0 1", Variant::Horde);
+        assert!(Variant::Horde.win(&b).is_none());
+        play(&mut b, "b2c1q");
+        assert_eq!(
+            Variant::Horde.win(&b),
+            Some((VariantWin::HordeDestroyed, Color::Black))
+        );
+
+        // The pawns can still mate, that's not a variant win
+        let b = board("7k/6PP/5PP1/8/8/8/8/8 b - - 0 1", Variant::Horde);
+        assert!(b.is_checkmate());
+        assert!(Variant::Horde.win(&b).is_none());
+
+        // White has no king
+        assert!(Board::from_variant_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1", Variant::Horde).is_err());
+    }
+
+    #[test]
+    fn crazyhouse() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R[QPnn] w KQkq - 2 3";
+        assert_eq!(board(fen, Variant::Crazyhouse).to_fen(), fen);
+        assert_eq!(
+            board(fen, Variant::Crazyhouse).pocket(Color::Black, Piece::Knight),
+            2
+        );
+        assert!(Board::from_fen(fen).is_err());
+        assert!(
+            Board::from_variant_fen("4k3/8/8/8/8/8/8/4K3[K] w - - 0 1", Variant::Crazyhouse)
+                .is_err()
+        );
+
+        // Taken pieces go to the pocket of the one that took them
+        let mut b = board("4k3/8/8/8/8/8/3n4/4K3[] w - - 0 1", Variant::Crazyhouse);
+        play(&mut b, "e1d2");
+        assert_eq!(b.pocket(Color::White, Piece::Knight), 1);
+        assert_eq!(b.to_fen(), "4k3/8/8/8/8/8/3K4/8[N] b - - 0 1");
+
+        // Promoted pieces are marked, and go back as pawns
+        let mut b = board("4k3/P7/8/8/8/8/8/4K3[] w - - 0 1", Variant::Crazyhouse);
+        play(&mut b, "a7a8q");
+        assert!(b.to_fen().starts_with("Q~3k3/"));
+        assert_eq!(b.promoted_pieces().count(), 1);
+
+        let mut b = board("3qk3/8/8/8/8/8/8/3Q~K3[] b - - 0 1", Variant::Crazyhouse);
+        play(&mut b, "d8d1");
+        assert_eq!(b.pocket(Color::Black, Piece::Pawn), 1);
+        assert_eq!(b.pocket(Color::Black, Piece::Queen), 0);
+        assert!(b.promoted_pieces().is_empty());
+
+        // No pawns on the first and last ranks
+        let b = board("4k3/8/8/8/8/8/8/4K3[P] w - - 0 1", Variant::Crazyhouse);
+        let drops = b
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.is_drop())
+            .collect::<Vec<ChessMove>>();
+        assert_eq!(drops.len(), 48);
+        assert!(drops
+            .iter()
+            .all(|mv| ![Rank::One, Rank::Eight].contains(&mv.target.rank())));
+        let queen = ChessMove::drop(
+            Piece::Queen,
+            Color::White,
+            Position::from_algebraic("d4").unwrap(),
+        );
+        assert!(!queen.is_legal(&b));
+
+        // Dropping can block a check, but not a double check
+        let b = board("4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1", Variant::Crazyhouse);
+        let drops = b
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.is_drop())
+            .collect::<Vec<ChessMove>>();
+        assert_eq!(drops.len(), 3);
+        assert!(drops.iter().all(|mv| mv.target.rank() == Rank::One));
+
+        let b = board("4k3/8/8/8/8/5n2/8/r3K3[N] w - - 0 1", Variant::Crazyhouse);
+        assert!(b.legal_moves().iter().all(|mv| !mv.is_drop()));
+
+        // Dropping and taking it back gives the same position
+        let mut b = board("4k3/8/8/8/8/8/3K4/8[N] b - - 0 1", Variant::Crazyhouse);
+        play(&mut b, "e8e7");
+        let fen = b.to_fen();
+        let hash = b.hash();
+        let mv = ChessMove::from_uci("N@f5", &b).unwrap();
+        let undo = b.make_move(&mv).unwrap();
+        assert_eq!(b.pocket(Color::White, Piece::Knight), 0);
+        assert_eq!(b.piece_at(mv.target), Some((Color::White, Piece::Knight)));
+        assert!(b.is_in_check());
+        b.unmake_move(undo);
+        assert_eq!(b.to_fen(), fen);
+        assert_eq!(b.hash(), hash);
+
+        // The pocket is part of the position
+        let empty = board("4k3/8/8/8/8/8/3K4/8[] b - - 0 1", Variant::Crazyhouse);
+        assert_ne!(
+            empty.hash(),
+            board("4k3/8/8/8/8/8/3K4/8[N] b - - 0 1", Variant::Crazyhouse).hash()
+        );
+    }
+}
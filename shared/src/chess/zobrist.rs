@@ -0,0 +1,296 @@
+// Zobrist hashing: every piece on every square, castle right, en passant file and the side to move gets a random
+// number, the key of a position is the xor of the ones that are in it
+// Moving a piece is then only a few xors, the board keeps the key up to date as it plays
+// https://www.chessprogramming.org/Zobrist_Hashing
+
+struct Keys {
+    // Indexed by `Color::to_index`, `Piece::to_index` and `Position::to_index`
+    pieces: [[[u64; 64]; 6]; 2],
+    // White king side, white queen side, black king side, black queen side
+    castles: [u64; 4],
+    en_passant: [u64; 8],
+    black_to_move: u64,
+    // Indexed by `Color::to_index` and the checks given minus one, for three-check
+    checks: [[u64; 3]; 2],
+    // Indexed by `Color::to_index`, `Piece::to_index` and the count minus one, for crazyhouse
+    pockets: [[[u64; 16]; 5]; 2],
+    // Promoted pieces go back to the pocket as pawns
+    promoted: [u64; 64],
+}
+
+// Same numbers on every machine and every run, so the keys can be stored
+const SEED: u64 = 0x5eed_c4e5_5b0a_4d00;
+
+const KEYS: Keys = generate();
+
+const fn generate() -> Keys {
+    let mut state = SEED;
+    let mut keys = Keys {
+        pieces: [[[0; 64]; 6]; 2],
+        castles: [0; 4],
+        en_passant: [0; 8],
+        black_to_move: 0,
+        checks: [[0; 3]; 2],
+        pockets: [[[0; 16]; 5]; 2],
+        promoted: [0; 64],
+    };
+
+    let mut color = 0;
+    while color < 2 {
+        let mut piece = 0;
+        while piece < 6 {
+            let mut square = 0;
+            while square < 64 {
+                keys.pieces[color][piece][square] = splitmix64(&mut state);
+                square += 1;
+            }
+            piece += 1;
+        }
+        color += 1;
+    }
+
+    let mut i = 0;
+    while i < 4 {
+        keys.castles[i] = splitmix64(&mut state);
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < 8 {
+        keys.en_passant[i] = splitmix64(&mut state);
+        i += 1;
+    }
+
+    keys.black_to_move = splitmix64(&mut state);
+
+    // Added after the others so the older keys, and the books using them, stay the same
+    let mut color = 0;
+    while color < 2 {
+        let mut i = 0;
+        while i < 3 {
+            keys.checks[color][i] = splitmix64(&mut state);
+            i += 1;
+        }
+        color += 1;
+    }
+
+    let mut color = 0;
+    while color < 2 {
+        let mut piece = 0;
+        while piece < 5 {
+            let mut i = 0;
+            while i < 16 {
+                keys.pockets[color][piece][i] = splitmix64(&mut state);
+                i += 1;
+            }
+            piece += 1;
+        }
+        color += 1;
+    }
+
+    let mut square = 0;
+    while square < 64 {
+        keys.promoted[square] = splitmix64(&mut state);
+        square += 1;
+    }
+
+    keys
+}
+
+// https://prng.di.unimi.it/splitmix64.c
+const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub(super) fn piece(color: super::Color, piece: super::Piece, pos: super::Position) -> u64 {
+    KEYS.pieces[color.to_index()][piece.to_index()][pos.to_index() as usize]
+}
+
+pub(super) fn castles(rights: super::CastleRights) -> u64 {
+    use super::{CastleSide, Color};
+
+    [
+        (Color::White, CastleSide::KingSide),
+        (Color::White, CastleSide::QueenSide),
+        (Color::Black, CastleSide::KingSide),
+        (Color::Black, CastleSide::QueenSide),
+    ]
+    .into_iter()
+    .zip(KEYS.castles)
+    .filter(|((color, side), _)| rights.has(*color, *side))
+    .fold(0, |key, (_, right)| key ^ right)
+}
+
+pub(super) fn black_to_move() -> u64 {
+    KEYS.black_to_move
+}
+
+/// Nothing for no check, so the positions of the other variants keep the same keys
+pub(super) fn checks(color: super::Color, given: u8) -> u64 {
+    match given {
+        0 => 0,
+        given => KEYS.checks[color.to_index()][given.min(3) as usize - 1],
+    }
+}
+
+/// Nothing for an empty pocket, past 16 pieces of a kind the count doesn't change the key anymore
+pub(super) fn pocket(color: super::Color, piece: super::Piece, count: u8) -> u64 {
+    match count {
+        0 => 0,
+        count => KEYS.pockets[color.to_index()][piece.to_index()][count.min(16) as usize - 1],
+    }
+}
+
+pub(super) fn promoted(pos: super::Position) -> u64 {
+    KEYS.promoted[pos.to_index() as usize]
+}
+
+/// The en passant square only counts if a pawn can actually take, otherwise the position is the same as without it
+pub(super) fn en_passant(board: &super::Board) -> u64 {
+    let Some(ep) = board.en_passant() else {
+        return 0;
+    };
+
+    let active = board.next_to_play();
+    let takers = super::BitBoard::pawn_attacks(ep, !active)
+        & board.get_bb(super::Piece::Pawn)
+        & board.color_bb(active);
+
+    if takers.is_empty() {
+        0
+    } else {
+        KEYS.en_passant[ep.file().to_index() as usize]
+    }
+}
+
+/// Computes the key from scratch, the board checks its incremental one against it in debug builds
+pub(super) fn full(board: &super::Board) -> u64 {
+    let mut key = castles(board.castle_rights()) ^ en_passant(board);
+
+    if board.next_to_play() == super::Color::Black {
+        key ^= black_to_move();
+    }
+
+    for color in [super::Color::White, super::Color::Black] {
+        key ^= checks(color, board.checks_given(color));
+
+        for kind in super::POCKET_PIECES {
+            key ^= pocket(color, kind, board.pocket(color, kind));
+        }
+    }
+
+    for pos in board.promoted_pieces().positions() {
+        key ^= promoted(pos);
+    }
+
+    for color in [super::Color::White, super::Color::Black] {
+        for kind in super::piece::ALL_PIECES {
+            for pos in (board.get_bb(kind) & board.color_bb(color)).positions() {
+                key ^= piece(color, kind, pos);
+            }
+        }
+    }
+
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Board, ChessMove};
+
+    #[test]
+    fn keys() {
+        let mut all = super::KEYS
+            .pieces
+            .iter()
+            .flatten()
+            .flatten()
+            .chain(&super::KEYS.castles)
+            .chain(&super::KEYS.en_passant)
+            .chain([&super::KEYS.black_to_move])
+            .chain(super::KEYS.checks.iter().flatten())
+            .chain(super::KEYS.pockets.iter().flatten().flatten())
+            .chain(&super::KEYS.promoted)
+            .copied()
+            .collect::<Vec<u64>>();
+        println!("{:#018x} {:#018x}", all[0], all[1]);
+
+        let count = all.len();
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), count);
+        assert!(!all.contains(&0));
+    }
+
+    #[test]
+    fn transpositions() {
+        let play = |ucis: &[&str]| {
+            let mut board = Board::default();
+            for uci in ucis {
+                let mv = ChessMove::from_uci(uci, &board).unwrap();
+                board.make_move(&mv).unwrap();
+            }
+            board
+        };
+
+        // Same position from different move orders
+        let a = play(&["g1f3", "g8f6", "b1c3", "b8c6"]);
+        let b = play(&["b1c3", "b8c6", "g1f3", "g8f6"]);
+        assert_eq!(a.hash(), b.hash());
+        assert_ne!(a.hash(), Board::default().hash());
+
+        // The knights went back, but the side to move changed
+        let c = play(&["g1f3", "g8f6", "f3g1"]);
+        let d = play(&["g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert_ne!(c.hash(), Board::default().hash());
+        assert_eq!(d.hash(), Board::default().hash());
+
+        // Castle rights are part of the key
+        let e = play(&["e2e4", "e7e5", "e1e2", "e8e7", "e2e1", "e7e8"]);
+        let f = play(&["e2e4", "e7e5"]);
+        assert_ne!(e.hash(), f.hash());
+    }
+
+    #[test]
+    fn en_passant() {
+        // A black pawn can take on e3
+        let takeable = Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        let without = Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+        assert_ne!(takeable.hash(), without.hash());
+
+        // Nothing can take on e3, it's the same position
+        let useless = Board::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+        let without = Board::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(useless.hash(), without.hash());
+    }
+
+    #[test]
+    fn incremental() {
+        // Every kind of move, the board checks the key after each of them in debug builds
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        ];
+
+        for fen in fens {
+            let board = Board::from_fen(fen).unwrap();
+
+            for mv in board.legal_moves() {
+                let mut after = board.clone();
+                after.make_move(&mv).unwrap();
+                assert_eq!(after.hash(), super::full(&after), "{fen}: {}", mv.to_uci());
+
+                if let Some(reply) = after.legal_moves().first() {
+                    let undo = after.make_move(reply).unwrap();
+                    after.unmake_move(undo);
+                    assert_eq!(after.hash(), super::full(&after));
+                }
+            }
+        }
+    }
+}
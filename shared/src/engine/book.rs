@@ -0,0 +1,438 @@
+// Opening books in the Polyglot format, so the bots don't think about the moves everyone knows
+// http://hgm.nubati.net/book_format.html
+//
+// A book is a list of 16 bytes entries sorted by key: the position's key, the move, its weight and 4 bytes for the
+// learning data, everything in big endian
+// The keys come from the Polyglot Random64 table, see `polyglot`, so the books work with other programs too
+
+use crate::{
+    chess::{Board, ChessMove, Piece},
+    error::book::BookError,
+};
+
+const ENTRY_SIZE: usize = 16;
+
+/// One move of a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookEntry {
+    pub key: u64,
+    /// Polyglot encoding of the move, see `encode`
+    pub raw_move: u16,
+    /// How often it should be played compared to the other moves of the position
+    pub weight: u16,
+    pub learn: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+/// Makes a book out of games, the moves are weighted by how they scored
+#[derive(Debug, Clone)]
+pub struct BookBuilder {
+    max_plies: usize,
+    weights: std::collections::HashMap<(u64, u16), u64>,
+}
+
+impl Book {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        let chunks = bytes.chunks_exact(ENTRY_SIZE);
+        if !chunks.remainder().is_empty() {
+            return Err(BookError::Size(bytes.len()));
+        }
+
+        let mut entries = chunks
+            .map(|chunk| BookEntry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+                raw_move: u16::from_be_bytes(chunk[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(chunk[10..12].try_into().unwrap()),
+                learn: u32::from_be_bytes(chunk[12..16].try_into().unwrap()),
+            })
+            .collect::<Vec<BookEntry>>();
+
+        // The lookups need them sorted, don't trust the file for that
+        entries.sort_by_key(|entry| entry.key);
+
+        Ok(Self { entries })
+    }
+
+    /// Loads a book from the resources, `path` is relative to `resources/external`
+    pub fn load(path: &str) -> Result<Self, BookError> {
+        let bytes = crate::file::try_bytes(crate::file::Path::new(
+            crate::file::FileSystem::External,
+            path.to_string(),
+        ))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries
+            .iter()
+            .flat_map(|entry| {
+                let mut bytes = [0; ENTRY_SIZE];
+                bytes[0..8].copy_from_slice(&entry.key.to_be_bytes());
+                bytes[8..10].copy_from_slice(&entry.raw_move.to_be_bytes());
+                bytes[10..12].copy_from_slice(&entry.weight.to_be_bytes());
+                bytes[12..16].copy_from_slice(&entry.learn.to_be_bytes());
+                bytes
+            })
+            .collect()
+    }
+
+    /// The Polyglot key of a position, the entries are sorted by it
+    pub fn key(board: &Board) -> u64 {
+        super::polyglot::key(board)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[BookEntry] {
+        &self.entries
+    }
+
+    /// The book's moves for that position with their weights, the ones that can't be played are left out
+    pub fn moves(&self, board: &Board) -> Vec<(ChessMove, u16)> {
+        // The books are made from standard games, their moves mean nothing with other rules
+        if board.variant() != crate::chess::Variant::Standard {
+            return Vec::new();
+        }
+
+        let key = Self::key(board);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+
+        let legal_moves = board.legal_moves();
+
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| {
+                let mv = legal_moves
+                    .iter()
+                    .find(|mv| encode(mv, board) == entry.raw_move)?;
+                Some((*mv, entry.weight))
+            })
+            .collect()
+    }
+
+    /// A move of the book for that position, chosen by weight, `random` is any random number
+    pub fn pick(&self, board: &Board, random: u64) -> Option<ChessMove> {
+        let moves = self.moves(board);
+
+        let total = moves.iter().map(|(_, weight)| *weight as u64).sum::<u64>();
+        if total == 0 {
+            return None;
+        }
+
+        let mut target = random % total;
+        for (mv, weight) in moves {
+            if target < weight as u64 {
+                return Some(mv);
+            }
+            target -= weight as u64;
+        }
+
+        None
+    }
+}
+
+impl BookBuilder {
+    /// Only the first `max_plies` moves of each game go in the book
+    pub fn new(max_plies: usize) -> Self {
+        Self {
+            max_plies,
+            weights: std::collections::HashMap::new(),
+        }
+    }
+
+    /// A win counts twice as much as a draw, losses and unfinished games don't count
+    pub fn add_game(&mut self, pgn: &crate::pgn::Pgn) {
+        let mut board = pgn.start().clone();
+
+        for mv in pgn.moves().iter().take(self.max_plies) {
+            let points = match (pgn.outcome(), mv.color) {
+                (crate::pgn::Outcome::WhiteWins, crate::chess::Color::White)
+                | (crate::pgn::Outcome::BlackWins, crate::chess::Color::Black) => 2,
+                (crate::pgn::Outcome::Draw, _) => 1,
+                _ => 0,
+            };
+
+            *self
+                .weights
+                .entry((Book::key(&board), encode(mv, &board)))
+                .or_default() += points;
+
+            // The moves of a parsed game are legal
+            board.play(mv);
+        }
+    }
+
+    /// Adds every game of the `.pgn` files in that directory, returns how many were added
+    pub fn add_dir(&mut self, dir: &std::path::Path) -> Result<usize, BookError> {
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<std::path::PathBuf>, std::io::Error>>()?;
+        paths.retain(|path| {
+            path.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("pgn"))
+        });
+        // Same book whatever order the files are listed in
+        paths.sort();
+
+        let mut count = 0;
+        for path in paths {
+            let text = std::fs::read_to_string(&path)?;
+            let games = crate::pgn::Pgn::parse_all(&text)
+                .map_err(|error| BookError::Pgn { file: path, error })?;
+
+            for game in &games {
+                self.add_game(game);
+            }
+            count += games.len();
+        }
+
+        Ok(count)
+    }
+
+    pub fn build(self) -> Book {
+        // The weights have to fit in 16 bits
+        let max = self.weights.values().copied().max().unwrap_or_default();
+        let scale = max.div_ceil(u16::MAX as u64).max(1);
+
+        let mut entries = self
+            .weights
+            .into_iter()
+            .filter(|(_, weight)| *weight > 0)
+            .map(|((key, raw_move), weight)| BookEntry {
+                key,
+                raw_move,
+                weight: (weight / scale).max(1) as u16,
+                learn: 0,
+            })
+            .collect::<Vec<BookEntry>>();
+
+        // The best moves first in each position, like the other books
+        entries.sort_by(|a, b| {
+            a.key
+                .cmp(&b.key)
+                .then(b.weight.cmp(&a.weight))
+                .then(a.raw_move.cmp(&b.raw_move))
+        });
+
+        Book { entries }
+    }
+}
+
+/// The move in 16 bits: the target's file and rank, the origin's file and rank, 3 bits each, then the promotion
+/// Castles are written as the king taking its own rook, `board` is the position before the move
+fn encode(mv: &ChessMove, board: &Board) -> u16 {
+    let mut target = mv.target;
+    if let Some(file) = mv
+        .castle_side(board)
+        .and_then(|side| board.castle_rights().rook_file(mv.color, side))
+    {
+        target = crate::chess::Position::from_file_rank(file, mv.origin.rank());
+    }
+
+    let promotion = match mv.promotion {
+        Some(Piece::Knight) => 1,
+        Some(Piece::Bishop) => 2,
+        Some(Piece::Rook) => 3,
+        Some(Piece::Queen) => 4,
+        _ => 0,
+    };
+
+    target.file().to_index() as u16
+        | (target.rank().to_index() as u16) << 3
+        | (mv.origin.file().to_index() as u16) << 6
+        | (mv.origin.rank().to_index() as u16) << 9
+        | promotion << 12
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, Book, BookBuilder};
+    use crate::{
+        chess::{Board, ChessMove},
+        pgn::Pgn,
+    };
+
+    const GAMES: &str = r#"[Result "1-0"]
+
+1. e4 e5 2. Nf3 Nc6 1-0
+
+[Result "1/2-1/2"]
+
+1. e4 c5 1/2-1/2
+
+[Result "0-1"]
+
+1. d4 d5 0-1
+
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 Nf6 1-0
+"#;
+
+    fn book() -> Book {
+        let mut builder = BookBuilder::new(3);
+        for game in Pgn::parse_all(GAMES).unwrap() {
+            builder.add_game(&game);
+        }
+        builder.build()
+    }
+
+    fn uci(moves: &[(ChessMove, u16)]) -> Vec<(String, u16)> {
+        moves
+            .iter()
+            .map(|(mv, weight)| (mv.to_uci(), *weight))
+            .collect()
+    }
+
+    #[test]
+    fn encoding() {
+        let board = Board::default();
+        let e2e4 = ChessMove::from_uci("e2e4", &board).unwrap();
+        // From the format's description
+        assert_eq!(encode(&e2e4, &board), 0b000_001_100_011_100);
+
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let castle = ChessMove::from_uci("e1g1", &board).unwrap();
+        assert_eq!(encode(&castle, &board), 0b000_000_100_000_111);
+
+        // Every move has its own code
+        let codes = board
+            .legal_moves()
+            .iter()
+            .map(|mv| encode(mv, &board))
+            .collect::<std::collections::HashSet<u16>>();
+        assert_eq!(codes.len(), board.legal_moves().len());
+
+        let board = Board::from_fen("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let knight = ChessMove::from_uci("b7b8n", &board).unwrap();
+        let queen = ChessMove::from_uci("b7b8q", &board).unwrap();
+        assert_eq!(encode(&knight, &board) >> 12, 1);
+        assert_eq!(encode(&queen, &board) >> 12, 4);
+
+        // Chess960 castles already go to the rook
+        let board = Board::from_fen("1r3krr/pppppppp/8/8/8/8/PPPPPPPP/1R3KR1 w GB - 0 1").unwrap();
+        let castle = ChessMove::from_uci("f1g1", &board).unwrap();
+        assert_eq!(encode(&castle, &board), 0b000_000_101_000_110);
+    }
+
+    #[test]
+    fn builder() {
+        let book = book();
+        for entry in book.entries() {
+            println!("{entry:?}");
+        }
+
+        // Two wins and a draw for e4, d4 lost
+        let start = Board::default();
+        assert_eq!(uci(&book.moves(&start)), [(String::from("e2e4"), 5)]);
+
+        let mut board = start.clone();
+        board
+            .make_move(&ChessMove::from_uci("e2e4", &board).unwrap())
+            .unwrap();
+        assert_eq!(uci(&book.moves(&board)), [(String::from("c7c5"), 1)]);
+
+        // Only the first 3 plies
+        board
+            .make_move(&ChessMove::from_uci("e7e5", &board).unwrap())
+            .unwrap();
+        board
+            .make_move(&ChessMove::from_uci("g1f3", &board).unwrap())
+            .unwrap();
+        assert!(book.moves(&board).is_empty());
+    }
+
+    #[test]
+    fn pick() {
+        let mut builder = BookBuilder::new(1);
+        for game in Pgn::parse_all("1. e4 1-0\n\n1. e4 1-0\n\n1. d4 1-0\n\n1. c4 0-1").unwrap() {
+            builder.add_game(&game);
+        }
+        let book = builder.build();
+        let board = Board::default();
+
+        assert_eq!(
+            uci(&book.moves(&board)),
+            [(String::from("e2e4"), 4), (String::from("d2d4"), 2)]
+        );
+
+        let picks = (0..6)
+            .map(|random| book.pick(&board, random).unwrap().to_uci())
+            .collect::<Vec<String>>();
+        assert_eq!(picks, ["e2e4", "e2e4", "e2e4", "e2e4", "d2d4", "d2d4"]);
+
+        // Out of the book
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(book.pick(&board, 0), None);
+    }
+
+    #[test]
+    fn bytes() {
+        let book = book();
+        let bytes = book.to_bytes();
+        assert_eq!(bytes.len(), book.len() * 16);
+
+        let read = Book::from_bytes(&bytes).unwrap();
+        assert_eq!(read.entries(), book.entries());
+
+        assert!(matches!(
+            Book::from_bytes(&bytes[1..]),
+            Err(crate::error::book::BookError::Size(_))
+        ));
+        assert!(Book::from_bytes(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn polyglot() {
+        // An entry as another program would write it: 1. e4 from the start position, weight 10
+        let mut bytes = Vec::new();
+        bytes.extend(0x463b96181691fc9c_u64.to_be_bytes());
+        bytes.extend(0b000_001_100_011_100_u16.to_be_bytes());
+        bytes.extend(10_u16.to_be_bytes());
+        bytes.extend(0_u32.to_be_bytes());
+
+        let book = Book::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            uci(&book.moves(&Board::default())),
+            [(String::from("e2e4"), 10)]
+        );
+        assert_eq!(Book::key(&Board::default()), 0x463b96181691fc9c);
+    }
+
+    #[test]
+    fn directory() {
+        let dir = std::env::temp_dir().join(format!("shared_book_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.pgn"), GAMES).unwrap();
+        std::fs::write(dir.join("b.PGN"), "1. e4 e5 1-0").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a game").unwrap();
+
+        let mut builder = BookBuilder::new(10);
+        assert_eq!(builder.add_dir(&dir).unwrap(), 5);
+        let book = builder.build();
+        assert_eq!(
+            uci(&book.moves(&Board::default())),
+            [(String::from("e2e4"), 7)]
+        );
+
+        std::fs::write(dir.join("c.pgn"), "1. e4 e4 1-0").unwrap();
+        let error = BookBuilder::new(10).add_dir(&dir).unwrap_err();
+        println!("{error}");
+        assert!(error.to_string().contains("c.pgn"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,261 @@
+// Material and piece-square tables
+// The tables come from Tomasz Michniewski's simplified evaluation function, the king blends its middlegame and
+// endgame tables as the pieces come off the board
+// https://www.chessprogramming.org/Simplified_Evaluation_Function
+
+use crate::chess::{Board, Color, Piece};
+
+// The tables are written the way the board is seen by white: the 8th rank first, the a file on the left
+#[rustfmt::skip]
+const PAWN: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+// How much each piece counts towards the middlegame, all of them on the board make 24
+const PHASE: [i32; 6] = [0, 1, 1, 2, 4, 0];
+const MAX_PHASE: i32 = 24;
+
+/// Worth of a piece in centipawns, the king is priceless
+pub fn piece_value(piece: Piece) -> i32 {
+    match piece {
+        Piece::Pawn => 100,
+        Piece::Knight => 320,
+        Piece::Bishop => 330,
+        Piece::Rook => 500,
+        Piece::Queen => 900,
+        Piece::King => 0,
+    }
+}
+
+/// Static score of the position in centipawns, from the point of view of the player that has to play
+pub fn evaluate(board: &Board) -> i32 {
+    evaluate_with(board, &super::Style::default())
+}
+
+/// Same as `evaluate`, with a profile's style on top
+pub fn evaluate_with(board: &Board, style: &super::Style) -> i32 {
+    let phase = crate::chess::ALL_PIECES
+        .iter()
+        .map(|piece| board.get_bb(*piece).count() as i32 * PHASE[piece.to_index()])
+        .sum::<i32>()
+        .min(MAX_PHASE);
+
+    let mut score = 0;
+    let mut material = 0;
+
+    for color in [Color::White, Color::Black] {
+        let sign = if color == Color::White { 1 } else { -1 };
+        let enemy_king = (board.get_bb(Piece::King) & board.color_bb(!color))
+            .positions()
+            .next();
+
+        for piece in crate::chess::ALL_PIECES {
+            for pos in (board.get_bb(piece) & board.color_bb(color)).positions() {
+                // Flip the rank for white since the tables start from the 8th
+                let rank = match color {
+                    Color::White => 7 - pos.rank().to_index(),
+                    Color::Black => pos.rank().to_index(),
+                };
+                let square = (rank * 8 + pos.file().to_index()) as usize;
+
+                let position = match piece {
+                    Piece::Pawn => PAWN[square],
+                    Piece::Knight => KNIGHT[square],
+                    Piece::Bishop => BISHOP[square],
+                    Piece::Rook => ROOK[square],
+                    Piece::Queen => QUEEN[square],
+                    Piece::King => {
+                        (KING_MIDDLEGAME[square] * phase
+                            + KING_ENDGAME[square] * (MAX_PHASE - phase))
+                            / MAX_PHASE
+                    }
+                };
+
+                score += sign * (piece_value(piece) + position);
+                material += sign * piece_value(piece);
+
+                if let (Some(king), Piece::Knight | Piece::Bishop | Piece::Rook | Piece::Queen) =
+                    (enemy_king, piece)
+                {
+                    let distance = (pos.file().to_index() as i32 - king.file().to_index() as i32)
+                        .abs()
+                        .max((pos.rank().to_index() as i32 - king.rank().to_index() as i32).abs());
+                    score += sign * style.aggression * (7 - distance);
+                }
+            }
+        }
+
+        // Crazyhouse pieces in the pocket can come back anywhere
+        for piece in crate::chess::POCKET_PIECES {
+            let value = piece_value(piece) * board.pocket(color, piece) as i32;
+            score += sign * value;
+            material += sign * value;
+        }
+    }
+
+    // Fewer pieces left means fewer ways for the other player to come back
+    score += material.signum() * style.trades * (MAX_PHASE - phase);
+
+    match board.next_to_play() {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, evaluate_with};
+    use crate::{chess::Board, engine::Style};
+
+    #[test]
+    fn symmetry() {
+        assert_eq!(evaluate(&Board::default()), 0);
+
+        // Same position with the colors swapped, and the other player to move
+        let white = Board::from_fen("4k3/pp3ppp/8/3N4/8/8/PPP2PPP/4K3 w - - 0 1").unwrap();
+        let black = Board::from_fen("4k3/ppp2ppp/8/8/3n4/8/PP3PPP/4K3 b - - 0 1").unwrap();
+        println!("{}", evaluate(&white));
+        assert_eq!(evaluate(&white), evaluate(&black));
+    }
+
+    #[test]
+    fn material() {
+        // White is a queen up, it's good for white and bad for black
+        let white = Board::from_fen("3qk3/8/8/8/8/8/8/3QKQ2 w - - 0 1").unwrap();
+        let black = Board::from_fen("3qk3/8/8/8/8/8/8/3QKQ2 b - - 0 1").unwrap();
+        assert!(evaluate(&white) > 800);
+        assert!(evaluate(&black) < -800);
+    }
+
+    #[test]
+    fn king_activity() {
+        // Without pieces, the king wants to be in the center
+        let center = Board::from_fen("4k3/8/8/8/3K4/8/8/8 w - - 0 1").unwrap();
+        let corner = Board::from_fen("4k3/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        assert!(evaluate(&center) > evaluate(&corner));
+
+        // With all of them, it wants to hide
+        let castled =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1RK1 w kq - 0 1").unwrap();
+        let walking =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/4K3/PPPPPPPP/RNBQ1R2 w kq - 0 1").unwrap();
+        assert!(evaluate(&castled) > evaluate(&walking));
+    }
+
+    #[test]
+    fn style() {
+        let bonus = |fen: &str, style: Style| {
+            let board = Board::from_fen(fen).unwrap();
+            evaluate_with(&board, &style) - evaluate(&board)
+        };
+
+        // The queen next to the king or in its corner
+        let aggressive = Style {
+            aggression: 10,
+            trades: 0,
+        };
+        let near = bonus("6k1/8/5Q2/8/8/8/8/K7 w - - 0 1", aggressive);
+        let far = bonus("6k1/8/8/8/8/8/8/KQ6 w - - 0 1", aggressive);
+        println!("near {near} far {far}");
+        assert!(near > far);
+
+        // White is a rook up, with or without the other pieces
+        let trader = Style {
+            aggression: 0,
+            trades: 8,
+        };
+        let full = bonus(
+            "1nbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kk - 0 1",
+            trader,
+        );
+        let simplified = bonus("4k3/pppppppp/8/8/8/8/PPPPPPPP/R3K3 w - - 0 1", trader);
+        println!("full {full} simplified {simplified}");
+        assert!(simplified > full);
+        assert!(full >= 0);
+
+        // It's the same for black
+        assert_eq!(
+            bonus("4k3/pppppppp/8/8/8/8/PPPPPPPP/R3K3 b - - 0 1", trader),
+            -simplified
+        );
+    }
+}
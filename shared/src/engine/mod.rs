@@ -0,0 +1,549 @@
+// The built-in engine: iterative deepening alpha-beta with a quiescence search, a transposition table and a
+// material + piece-square tables evaluation
+// A profile can make it weaker or give it a style, see `profile`, and an opening book can answer before the search
+// It searches on its own copy of the board, so it can run on another thread and be stopped from the one that owns it
+
+mod book;
+mod eval;
+mod polyglot;
+mod profile;
+mod search;
+mod tt;
+
+pub use book::{Book, BookBuilder, BookEntry};
+pub use eval::{evaluate, evaluate_with, piece_value};
+pub use profile::{Level, Personality, Profile, Style, ALL_LEVELS, ALL_PERSONALITIES};
+pub use tt::TranspositionTable;
+
+use std::sync::{atomic::AtomicBool, Arc};
+
+/// When the search has to stop, whichever comes first
+/// Without any, it goes on until the stop flag is set
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<std::time::Duration>,
+}
+
+/// What a search thinks of the position, for the player that has to play
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    /// Number of moves until mate, negative when getting mated
+    Mate(i32),
+}
+
+/// Result of a finished iteration
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub score: Score,
+    pub nodes: u64,
+    pub time: std::time::Duration,
+    /// The moves both players are expected to play, starting with the best one
+    pub pv: Vec<crate::chess::ChessMove>,
+}
+
+pub struct Engine {
+    tt: TranspositionTable,
+    stop: Arc<AtomicBool>,
+    profile: Profile,
+    // Shared between the engines that use the same book
+    book: Option<Arc<Book>>,
+    // For the blunders and the book moves, restarted from the profile's seed on each new game
+    rng: profile::Rng,
+}
+
+impl Limits {
+    pub fn depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        Self {
+            nodes: Some(nodes),
+            ..Default::default()
+        }
+    }
+
+    pub fn time(time: std::time::Duration) -> Self {
+        Self {
+            time: Some(time),
+            ..Default::default()
+        }
+    }
+
+    /// Time for a move when playing on a clock, `moves_to_go` is the number of moves until the next time control
+    pub fn clock(
+        remaining: std::time::Duration,
+        increment: std::time::Duration,
+        moves_to_go: Option<u32>,
+    ) -> Self {
+        // Keep some time for the messages to come and go
+        let margin = std::time::Duration::from_millis(50).min(remaining / 10);
+        let usable = remaining - margin;
+
+        let time = (usable / moves_to_go.unwrap_or(30).max(1))
+            .saturating_add(increment.saturating_mul(3) / 4);
+        Self::time(time.min(usable))
+    }
+
+    /// For a player of a game, `think_time` at most and less when their clock is running out
+    /// Correspondence games don't need the days they give
+    pub fn game(
+        game: &crate::game::Game,
+        color: crate::chess::Color,
+        think_time: std::time::Duration,
+    ) -> Self {
+        let control = game.options().time_control;
+        let Some(remaining) = game
+            .clock()
+            .remaining(color)
+            .filter(|_| control.is_realtime())
+        else {
+            return Self::time(think_time);
+        };
+
+        let time = Self::clock(remaining, control.increment(), None)
+            .time
+            .map_or(think_time, |time| time.min(think_time));
+        Self::time(time)
+    }
+}
+
+impl Score {
+    fn from_internal(score: i32) -> Self {
+        if score >= search::MATE_BOUND {
+            Self::Mate((search::MATE - score + 1) / 2)
+        } else if score <= -search::MATE_BOUND {
+            Self::Mate(-(search::MATE + score) / 2)
+        } else {
+            Self::Centipawns(score)
+        }
+    }
+}
+
+impl std::fmt::Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Score::Centipawns(cp) => write!(f, "{:+.2}", *cp as f64 / 100.),
+            Score::Mate(moves) => write!(f, "#{moves}"),
+        }
+    }
+}
+
+impl Engine {
+    /// `hash_megabytes` is the size of the transposition table
+    pub fn new(hash_megabytes: usize) -> Self {
+        Self {
+            tt: TranspositionTable::new(hash_megabytes),
+            stop: Arc::new(AtomicBool::new(false)),
+            profile: Profile::default(),
+            book: None,
+            rng: profile::Rng::new(0),
+        }
+    }
+
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Also starts a new game, what was learned with the previous profile doesn't hold anymore
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+        self.new_game();
+    }
+
+    /// Setting it to true stops the running search, which then returns the best move it has
+    /// The engine sets it back to false when the search returns
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// Its moves are played without searching while the game is in it
+    pub fn set_book(&mut self, book: Option<Arc<Book>>) {
+        self.book = book;
+    }
+
+    /// Forgets what was learned from the previous searches
+    pub fn new_game(&mut self) {
+        self.tt.clear();
+        self.rng = profile::Rng::new(self.profile.seed);
+    }
+
+    /// How full the transposition table is, in permille
+    pub fn hash_usage(&self) -> u32 {
+        self.tt.usage()
+    }
+
+    /// Best move for the player that has to play, with what was found about it
+    /// With a profile that blunders, the move may be a random one, the info is still the search's
+    /// A move from the book comes with an empty search, of depth 0
+    /// Returns `None` when there is no legal move
+    pub fn search(
+        &mut self,
+        board: &crate::chess::Board,
+        limits: Limits,
+    ) -> Option<(crate::chess::ChessMove, SearchInfo)> {
+        self.search_with(board, limits, |_| ())
+    }
+
+    /// Same as `search`, `on_iteration` is called after each depth is done
+    pub fn search_with(
+        &mut self,
+        board: &crate::chess::Board,
+        limits: Limits,
+        mut on_iteration: impl FnMut(&SearchInfo),
+    ) -> Option<(crate::chess::ChessMove, SearchInfo)> {
+        let moves = board.legal_moves();
+        let first = *moves.first()?;
+
+        if let Some(book_move) = self
+            .book
+            .as_ref()
+            .and_then(|book| book.pick(board, self.rng.next()))
+        {
+            let info = SearchInfo {
+                depth: 0,
+                score: Score::Centipawns(0),
+                nodes: 0,
+                time: std::time::Duration::ZERO,
+                pv: vec![book_move],
+            };
+            return Some((book_move, info));
+        }
+
+        let limits = self.profile.cap(limits);
+        let mut searcher = search::Searcher::new(
+            board.clone(),
+            &mut self.tt,
+            &self.stop,
+            limits,
+            &self.profile,
+        );
+        let max_depth = limits
+            .depth
+            .unwrap_or(search::MAX_PLY as u32 - 1)
+            .clamp(1, search::MAX_PLY as u32 - 1);
+
+        let mut best: Option<SearchInfo> = None;
+
+        for depth in 1..=max_depth {
+            let score = searcher.negamax(depth, 0, -search::INFINITY, search::INFINITY);
+
+            // The unfinished iteration can't be trusted
+            if searcher.aborted {
+                break;
+            }
+            searcher.can_abort = true;
+
+            let info = SearchInfo {
+                depth,
+                score: Score::from_internal(score),
+                nodes: searcher.nodes,
+                time: searcher.start.elapsed(),
+                pv: searcher.pv[0].clone(),
+            };
+            on_iteration(&info);
+
+            let mate_found = matches!(info.score, Score::Mate(moves) if moves > 0 && moves as u32 * 2 <= depth + 1);
+            best = Some(info);
+
+            // The next iteration would take longer than what is left
+            let out_of_time = limits
+                .time
+                .is_some_and(|time| searcher.start.elapsed() * 2 >= time);
+
+            if mate_found || out_of_time {
+                break;
+            }
+        }
+
+        self.stop.store(false, std::sync::atomic::Ordering::Relaxed);
+
+        let info = best?;
+        let mut best_move = info.pv.first().copied().unwrap_or(first);
+
+        if self.rng.chance(self.profile.blunder) {
+            best_move = moves[self.rng.below(moves.len())];
+        }
+
+        Some((best_move, info))
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Engine, Level, Limits, Personality, Profile, Score};
+    use crate::chess::{Board, Variant};
+
+    fn best(fen: &str, limits: Limits) -> (String, super::SearchInfo) {
+        let board = Board::from_fen(fen).unwrap();
+        let (mv, info) = Engine::default()
+            .search_with(&board, limits, |info| {
+                println!(
+                    "depth {} score {} nodes {} time {:?} pv {:?}",
+                    info.depth,
+                    info.score,
+                    info.nodes,
+                    info.time,
+                    info.pv.iter().map(|mv| mv.to_uci()).collect::<Vec<_>>()
+                )
+            })
+            .unwrap();
+        (mv.to_uci(), info)
+    }
+
+    #[test]
+    fn mate_in_one() {
+        let (mv, info) = best("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1", Limits::depth(4));
+        assert_eq!(mv, "a1a8");
+        assert_eq!(info.score, Score::Mate(1));
+    }
+
+    #[test]
+    fn mate_in_two() {
+        // The rooks take turns
+        let (_, info) = best("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", Limits::depth(5));
+        assert_eq!(info.score, Score::Mate(2));
+    }
+
+    #[test]
+    fn getting_mated() {
+        // Black can only delay it
+        let (_, info) = best("7k/R7/1R6/8/8/8/8/6K1 b - - 0 1", Limits::depth(4));
+        assert_eq!(info.score, Score::Mate(-1));
+    }
+
+    #[test]
+    fn free_material() {
+        // The queen is hanging
+        let (mv, _) = best(
+            "rnb1kbnr/pppp1ppp/8/4p1q1/4P3/3P4/PPP2PPP/RNBQKBNR w KQkq - 1 3",
+            Limits::depth(3),
+        );
+        assert_eq!(mv, "c1g5");
+
+        // Taking the pawn loses the queen
+        let (mv, _) = best("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", Limits::depth(3));
+        assert_ne!(mv, "d1d5");
+    }
+
+    #[test]
+    fn variant_wins() {
+        // Nothing to mate with, but the king can walk to the center
+        let board = Board::from_variant_fen("k7/8/8/8/8/3K4/8/8 w - - 0 1", Variant::KingOfTheHill)
+            .unwrap();
+        let (mv, info) = Engine::default().search(&board, Limits::depth(3)).unwrap();
+        assert!(["d3d4", "d3e4"].contains(&mv.to_uci().as_str()));
+        assert_eq!(info.score, Score::Mate(1));
+
+        // The pawn has to take the rook on a1, then white has nothing left and wins
+        let board =
+            Board::from_variant_fen("8/8/8/8/8/8/1p6/2R5 w - - 0 1", Variant::Antichess).unwrap();
+        let (mv, info) = Engine::default().search(&board, Limits::depth(4)).unwrap();
+        assert_eq!(mv.to_uci(), "c1a1");
+        assert_eq!(info.score, Score::Mate(1));
+    }
+
+    #[test]
+    fn no_moves() {
+        let board = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(board.is_stalemate());
+        assert!(Engine::default().search(&board, Limits::depth(3)).is_none());
+    }
+
+    #[test]
+    fn limits() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+        let (_, info) = best(fen, Limits::depth(3));
+        assert_eq!(info.depth, 3);
+
+        // The first iteration always finishes
+        let (_, info) = best(fen, Limits::nodes(1));
+        assert_eq!(info.depth, 1);
+
+        let (_, info) = best(fen, Limits::nodes(20_000));
+        assert!(info.nodes <= 20_000);
+
+        let start = std::time::Instant::now();
+        best(fen, Limits::time(std::time::Duration::from_millis(200)));
+        assert!(start.elapsed() < std::time::Duration::from_millis(400));
+    }
+
+    #[test]
+    fn stop_flag() {
+        let mut engine = Engine::default();
+        let stop = engine.stop_flag();
+
+        let search = std::thread::spawn(move || {
+            let board = Board::default();
+            let result = engine.search(&board, Limits::default());
+            (engine, result)
+        });
+
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+
+        let (engine, result) = search.join().unwrap();
+        assert!(result.is_some());
+
+        // Ready for the next one
+        assert!(!engine
+            .stop_flag()
+            .load(std::sync::atomic::Ordering::Relaxed));
+    }
+
+    #[test]
+    fn clock() {
+        use std::time::Duration;
+
+        let limits = Limits::clock(Duration::from_secs(60), Duration::from_secs(1), None);
+        println!("{limits:?}");
+        assert!(limits.time.unwrap() > Duration::from_secs(1));
+        assert!(limits.time.unwrap() < Duration::from_secs(5));
+
+        // Never more than what is left
+        let limits = Limits::clock(Duration::from_millis(100), Duration::from_secs(5), Some(1));
+        assert!(limits.time.unwrap() < Duration::from_millis(100));
+
+        // Times too big to add up
+        let limits = Limits::clock(Duration::MAX, Duration::MAX, None);
+        assert!(limits.time.is_some());
+    }
+
+    #[test]
+    fn game_clock() {
+        use crate::game::{Clock, Game, Options, TimeControl};
+        use std::time::Duration;
+
+        let think_time = Duration::from_secs(1);
+        let time = |time_control| {
+            let game = Game::default()
+                .with_options(Options {
+                    time_control,
+                    ..Default::default()
+                })
+                .with_clock(Clock::new(time_control));
+            Limits::game(&game, crate::chess::Color::White, think_time).time
+        };
+
+        assert_eq!(time(TimeControl::Unlimited), Some(think_time));
+        assert_eq!(
+            time(TimeControl::Correspondence { days: 1 }),
+            Some(think_time)
+        );
+
+        // 10 seconds for the whole game
+        let blitz = time(TimeControl::Increment {
+            base: Duration::from_secs(10),
+            increment: Duration::ZERO,
+        })
+        .unwrap();
+        println!("{blitz:?}");
+        assert!(blitz < think_time);
+    }
+
+    // Both sides are played by the same engine
+    fn self_play(profile: Profile, plies: usize) -> Vec<String> {
+        let mut engine = Engine::new(1);
+        engine.set_profile(profile);
+
+        let mut board = Board::default();
+        let mut moves = Vec::new();
+        for _ in 0..plies {
+            let Some((mv, _)) = engine.search(&board, Limits::default()) else {
+                break;
+            };
+            moves.push(mv.to_uci());
+            board.make_move(&mv).unwrap();
+        }
+        moves
+    }
+
+    #[test]
+    fn profiles() {
+        let beginner = Profile::new(Level::Beginner, Personality::Balanced, 1);
+        let game = self_play(beginner, 16);
+        println!("{game:?}");
+
+        // Reproducible from the seed
+        assert_eq!(game, self_play(beginner, 16));
+        assert_ne!(
+            game,
+            self_play(
+                Profile {
+                    seed: 2,
+                    ..beginner
+                },
+                16
+            )
+        );
+
+        // The caps apply even without limits
+        let mut engine = Engine::new(1);
+        engine.set_profile(Level::Easy.profile());
+        let (_, info) = engine.search(&Board::default(), Limits::default()).unwrap();
+        assert_eq!(info.depth, 2);
+    }
+
+    #[test]
+    fn blunders() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        let mut engine = Engine::new(1);
+
+        let moves = (0..10)
+            .map(|seed| {
+                engine.set_profile(Profile {
+                    depth: Some(2),
+                    blunder: 100,
+                    seed,
+                    ..Default::default()
+                });
+                engine.search(&board, Limits::default()).unwrap().0.to_uci()
+            })
+            .collect::<Vec<String>>();
+        println!("{moves:?}");
+        assert!(moves.iter().any(|mv| mv != "a1a8"));
+
+        // Never misses it otherwise
+        for seed in 0..10 {
+            engine.set_profile(Profile::new(Level::Master, Personality::Aggressive, seed));
+            let (mv, _) = engine.search(&board, Limits::depth(2)).unwrap();
+            assert_eq!(mv.to_uci(), "a1a8");
+        }
+    }
+
+    #[test]
+    fn book() {
+        let mut builder = super::BookBuilder::new(2);
+        for game in crate::pgn::Pgn::parse_all("1. d4 d5 1-0\n\n1. c4 e5 1/2-1/2").unwrap() {
+            builder.add_game(&game);
+        }
+
+        let mut engine = Engine::new(1);
+        engine.set_book(Some(std::sync::Arc::new(builder.build())));
+
+        let board = Board::default();
+        let (mv, info) = engine.search(&board, Limits::depth(3)).unwrap();
+        assert!(["d2d4", "c2c4"].contains(&mv.to_uci().as_str()));
+        assert_eq!(info.depth, 0);
+
+        // Searches once out of it
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        let (mv, info) = engine.search(&board, Limits::depth(3)).unwrap();
+        assert_eq!(mv.to_uci(), "a1a8");
+        assert!(info.depth > 0);
+    }
+}
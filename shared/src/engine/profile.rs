@@ -0,0 +1,344 @@
+// How strong the engine plays and what it likes, so the bot can be beaten
+// Everything random comes from the seed, two engines with the same profile play the same moves in the same games
+
+/// Named strengths, from the weakest to full strength
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Level {
+    Beginner,
+    Easy,
+    Medium,
+    Hard,
+    #[default]
+    Master,
+}
+
+/// What the engine prefers when the moves are close, on top of winning
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Personality {
+    #[default]
+    Balanced,
+    // Goes for the enemy king and keeps the pieces on the board
+    Aggressive,
+    // Simplifies as soon as it's ahead
+    Trader,
+}
+
+/// Weights added to the evaluation, in centipawns, 0 doesn't change anything
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Style {
+    /// For each piece, per square closer to the enemy king
+    pub aggression: i32,
+    /// For the player that is ahead in material, per phase point that came off the board
+    pub trades: i32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Profile {
+    /// Caps on top of the limits of each search
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    /// Each evaluation is moved by up to that many centipawns
+    pub noise: i32,
+    /// Chances out of 100 to play a random move instead of the best one
+    pub blunder: u32,
+    pub style: Style,
+    pub seed: u64,
+}
+
+// The most a profile can ask for, the server gets them from the clients
+// Far from the mate scores, and from overflowing an evaluation
+const MAX_NOISE: i32 = 500;
+const MAX_STYLE: i32 = 50;
+
+pub const ALL_LEVELS: [Level; 5] = [
+    Level::Beginner,
+    Level::Easy,
+    Level::Medium,
+    Level::Hard,
+    Level::Master,
+];
+
+pub const ALL_PERSONALITIES: [Personality; 3] = [
+    Personality::Balanced,
+    Personality::Aggressive,
+    Personality::Trader,
+];
+
+impl Level {
+    pub fn profile(self) -> Profile {
+        let (depth, nodes, noise, blunder) = match self {
+            Level::Beginner => (Some(1), None, 200, 25),
+            Level::Easy => (Some(2), None, 100, 10),
+            Level::Medium => (Some(4), None, 40, 3),
+            Level::Hard => (None, Some(200_000), 10, 0),
+            Level::Master => (None, None, 0, 0),
+        };
+
+        Profile {
+            depth,
+            nodes,
+            noise,
+            blunder,
+            ..Default::default()
+        }
+    }
+}
+
+impl Personality {
+    pub fn style(self) -> Style {
+        match self {
+            Personality::Balanced => Style::default(),
+            Personality::Aggressive => Style {
+                aggression: 6,
+                trades: -5,
+            },
+            Personality::Trader => Style {
+                aggression: 0,
+                trades: 8,
+            },
+        }
+    }
+}
+
+impl Profile {
+    pub fn new(level: Level, personality: Personality, seed: u64) -> Self {
+        Self {
+            style: personality.style(),
+            seed,
+            ..level.profile()
+        }
+    }
+
+    /// The limits of a search, with this profile's caps
+    pub fn cap(&self, limits: super::Limits) -> super::Limits {
+        fn min<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+
+        super::Limits {
+            depth: min(limits.depth, self.depth),
+            nodes: min(limits.nodes, self.nodes),
+            time: limits.time,
+        }
+    }
+
+    /// The same profile, with its noise, blunders and style brought within what the server accepts
+    pub fn clamped(self) -> Self {
+        Self {
+            noise: self.noise.clamp(0, MAX_NOISE),
+            blunder: self.blunder.min(100),
+            style: Style {
+                aggression: self.style.aggression.clamp(-MAX_STYLE, MAX_STYLE),
+                trades: self.style.trades.clamp(-MAX_STYLE, MAX_STYLE),
+            },
+            ..self
+        }
+    }
+
+    /// Same for a given position, so the transposition table stays right
+    pub(super) fn noise(&self, key: u64) -> i32 {
+        if self.noise <= 0 {
+            return 0;
+        }
+
+        let range = self.noise as u64 * 2 + 1;
+        (splitmix64(key ^ self.seed) % range) as i32 - self.noise
+    }
+}
+
+/// Random numbers from the profile's seed
+#[derive(Debug, Clone)]
+pub(super) struct Rng(u64);
+
+impl Rng {
+    pub(super) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(super) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        splitmix64(self.0)
+    }
+
+    /// True `chance` times out of 100
+    pub(super) fn chance(&mut self, chance: u32) -> bool {
+        self.next() % 100 < chance as u64
+    }
+
+    pub(super) fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Level::Beginner => "beginner",
+            Level::Easy => "easy",
+            Level::Medium => "medium",
+            Level::Hard => "hard",
+            Level::Master => "master",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ALL_LEVELS
+            .into_iter()
+            .find(|level| level.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown level '{s}', expected one of {}",
+                    ALL_LEVELS.map(|level| level.to_string()).join(", ")
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for Personality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Personality::Balanced => "balanced",
+            Personality::Aggressive => "aggressive",
+            Personality::Trader => "trader",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for Personality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ALL_PERSONALITIES
+            .into_iter()
+            .find(|personality| personality.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown personality '{s}', expected one of {}",
+                    ALL_PERSONALITIES
+                        .map(|personality| personality.to_string())
+                        .join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Level, Personality, Profile, Rng, Style, ALL_LEVELS, ALL_PERSONALITIES};
+    use crate::engine::Limits;
+
+    #[test]
+    fn names() {
+        for level in ALL_LEVELS {
+            assert_eq!(level.to_string().parse::<Level>(), Ok(level));
+        }
+        for personality in ALL_PERSONALITIES {
+            assert_eq!(
+                personality.to_string().parse::<Personality>(),
+                Ok(personality)
+            );
+        }
+
+        assert_eq!("HARD".parse::<Level>(), Ok(Level::Hard));
+        let error = "grandmaster".parse::<Level>().unwrap_err();
+        println!("{error}");
+        assert!(error.contains("beginner"));
+    }
+
+    #[test]
+    fn cap() {
+        let profile = Level::Easy.profile();
+        assert_eq!(profile.cap(Limits::default()).depth, Some(2));
+        assert_eq!(profile.cap(Limits::depth(1)).depth, Some(1));
+        assert_eq!(profile.cap(Limits::depth(5)).depth, Some(2));
+
+        let time = Limits::time(std::time::Duration::from_secs(1));
+        assert_eq!(Level::Master.profile().cap(time), time);
+        assert_eq!(Level::Hard.profile().cap(time).nodes, Some(200_000));
+    }
+
+    #[test]
+    fn clamped() {
+        let profile = Profile {
+            noise: i32::MAX,
+            blunder: u32::MAX,
+            style: Style {
+                aggression: i32::MIN,
+                trades: i32::MAX,
+            },
+            ..Default::default()
+        }
+        .clamped();
+        println!("{profile:?}");
+        assert_eq!(profile.noise, 500);
+        assert_eq!(profile.blunder, 100);
+        assert_eq!(profile.style.aggression, -50);
+        assert_eq!(profile.style.trades, 50);
+        assert!(profile.noise(42).abs() <= 500);
+
+        // The named ones are already within the bounds
+        for level in ALL_LEVELS {
+            for personality in ALL_PERSONALITIES {
+                let profile = Profile::new(level, personality, 7);
+                assert_eq!(profile.clamped(), profile);
+            }
+        }
+    }
+
+    #[test]
+    fn noise() {
+        let profile = Profile::new(Level::Beginner, Personality::Balanced, 7);
+        let noises = (0..1000u64)
+            .map(|key| profile.noise(key))
+            .collect::<Vec<i32>>();
+
+        assert!(noises.iter().all(|noise| noise.abs() <= 200));
+        assert!(noises.iter().any(|noise| *noise > 100));
+        assert!(noises.iter().any(|noise| *noise < -100));
+
+        // Same position, same noise, unless the seed changes
+        assert_eq!(profile.noise(42), profile.noise(42));
+        let other = Profile { seed: 8, ..profile };
+        assert_ne!(
+            noises,
+            (0..1000u64)
+                .map(|key| other.noise(key))
+                .collect::<Vec<i32>>()
+        );
+
+        assert_eq!(Level::Master.profile().noise(42), 0);
+    }
+
+    #[test]
+    fn rng() {
+        let mut a = Rng::new(1);
+        let mut b = Rng::new(1);
+        let mut c = Rng::new(2);
+        let a = (0..10).map(|_| a.next()).collect::<Vec<u64>>();
+        assert_eq!(a, (0..10).map(|_| b.next()).collect::<Vec<u64>>());
+        assert_ne!(a, (0..10).map(|_| c.next()).collect::<Vec<u64>>());
+
+        let mut rng = Rng::new(3);
+        let hits = (0..10_000).filter(|_| rng.chance(25)).count();
+        println!("{hits}");
+        assert!((2000..3000).contains(&hits));
+        assert!(!(0..100).any(|_| rng.chance(0)));
+        assert!((0..100).all(|_| rng.chance(100)));
+    }
+}
@@ -0,0 +1,349 @@
+// Negamax alpha-beta with a quiescence search at the leaves
+// Moves are tried in this order: the transposition table's move, captures by MVV-LVA (most valuable victim, least
+// valuable attacker), promotions, the killers (quiet moves that cut at the same ply) and then by history (how often
+// a quiet move caused a cut anywhere)
+
+use super::tt::{Bound, Entry, TranspositionTable};
+use crate::chess::{Board, ChessMove, Piece};
+
+pub const MAX_PLY: usize = 128;
+pub const INFINITY: i32 = 32_000;
+pub const MATE: i32 = 31_000;
+// Anything above is a mate found at some ply
+pub const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+
+// The time and the stop flag are checked every that many nodes, it has to be a power of two
+const CHECK_INTERVAL: u64 = 1024;
+
+pub struct Searcher<'a> {
+    pub board: Board,
+    pub tt: &'a mut TranspositionTable,
+    pub stop: &'a std::sync::atomic::AtomicBool,
+    pub limits: super::Limits,
+    pub profile: &'a super::Profile,
+    pub start: std::time::Instant,
+    pub nodes: u64,
+    // The first iteration always finishes so there is a move to play
+    pub can_abort: bool,
+    pub aborted: bool,
+    // Principal variation of each ply
+    pub pv: Vec<Vec<ChessMove>>,
+    killers: Vec<[Option<ChessMove>; 2]>,
+    // Indexed by `Color::to_index`, `Piece::to_index` and the target's `Position::to_index`
+    history: Box<[[[i32; 64]; 6]; 2]>,
+}
+
+impl<'a> Searcher<'a> {
+    pub fn new(
+        board: Board,
+        tt: &'a mut TranspositionTable,
+        stop: &'a std::sync::atomic::AtomicBool,
+        limits: super::Limits,
+        profile: &'a super::Profile,
+    ) -> Self {
+        Self {
+            board,
+            tt,
+            stop,
+            limits,
+            profile,
+            start: std::time::Instant::now(),
+            nodes: 0,
+            can_abort: false,
+            aborted: false,
+            pv: vec![Vec::new(); MAX_PLY + 1],
+            killers: vec![[None; 2]; MAX_PLY],
+            history: Box::new([[[0; 64]; 6]; 2]),
+        }
+    }
+
+    pub fn negamax(&mut self, depth: u32, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv[ply].clear();
+
+        if ply > 0 {
+            if let Some(score) = self.variant_score(ply) {
+                return score;
+            }
+        }
+
+        if ply > 0
+            && (self.board.is_repetition()
+                || self.board.is_fifty_moves()
+                || self.board.is_insufficient_material())
+        {
+            return 0;
+        }
+
+        // Don't stop to look at the position when the king is attacked
+        let in_check = self.board.is_in_check();
+        let depth = if in_check { depth + 1 } else { depth };
+
+        if depth == 0 {
+            return self.quiescence(ply, alpha, beta);
+        }
+
+        if self.visit() {
+            return 0;
+        }
+
+        if ply >= MAX_PLY - 1 {
+            return self.evaluate();
+        }
+
+        let key = self.board.hash();
+        let mut tt_move = None;
+
+        if let Some(entry) = self.tt.get(key) {
+            tt_move = entry.best_move;
+
+            if ply > 0 && entry.depth >= depth {
+                let score = from_tt(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => (),
+                }
+            }
+        }
+
+        let mut moves = self.board.legal_moves();
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+
+        moves.sort_by_cached_key(|mv| std::cmp::Reverse(self.order(mv, tt_move, ply)));
+
+        let alpha_start = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+
+        for mv in moves {
+            let quiet = !self.is_tactical(&mv);
+
+            let undo = self.board.play(&mv);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha);
+            self.board.unmake_move(undo);
+
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+            }
+
+            if score > alpha {
+                alpha = score;
+
+                let child = std::mem::take(&mut self.pv[ply + 1]);
+                self.pv[ply].clear();
+                self.pv[ply].push(mv);
+                self.pv[ply].extend(&child);
+                self.pv[ply + 1] = child;
+            }
+
+            if alpha >= beta {
+                if quiet {
+                    self.remember_cut(mv, depth, ply);
+                }
+                break;
+            }
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > alpha_start {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+
+        self.tt.insert(Entry {
+            key,
+            depth,
+            score: to_tt(best_score, ply),
+            bound,
+            best_move,
+        });
+
+        best_score
+    }
+
+    /// Only looks at captures and promotions so the evaluation doesn't happen in the middle of an exchange
+    fn quiescence(&mut self, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.visit() {
+            return 0;
+        }
+
+        if let Some(score) = self.variant_score(ply) {
+            return score;
+        }
+
+        if ply >= MAX_PLY - 1 {
+            return self.evaluate();
+        }
+
+        // Every move has to be tried to get out of a check, there may be no good one
+        let in_check = self.board.is_in_check();
+
+        let mut best_score = -INFINITY;
+        if !in_check {
+            // The player can also choose to not take anything
+            best_score = self.evaluate();
+            if best_score >= beta {
+                return best_score;
+            }
+            alpha = alpha.max(best_score);
+        }
+
+        let mut moves = self.board.legal_moves();
+        if moves.is_empty() && in_check {
+            return -MATE + ply as i32;
+        }
+
+        if !in_check {
+            moves.retain(|mv| self.is_tactical(mv));
+        }
+        moves.sort_by_cached_key(|mv| std::cmp::Reverse(self.order(mv, None, ply)));
+
+        for mv in moves {
+            let undo = self.board.play(&mv);
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.board.unmake_move(undo);
+
+            if self.aborted {
+                return 0;
+            }
+
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best_score
+    }
+
+    /// A game won by the rules of the variant scores like a mate, for the player that won
+    fn variant_score(&self, ply: usize) -> Option<i32> {
+        let (_, winner) = self.board.variant().win(&self.board)?;
+
+        Some(if winner == self.board.next_to_play() {
+            MATE - ply as i32
+        } else {
+            -MATE + ply as i32
+        })
+    }
+
+    fn evaluate(&self) -> i32 {
+        super::evaluate_with(&self.board, &self.profile.style)
+            + self.profile.noise(self.board.hash())
+    }
+
+    /// Counts the node and tells if the search has to stop
+    fn visit(&mut self) -> bool {
+        self.nodes += 1;
+
+        if !self.can_abort {
+            return false;
+        }
+
+        if self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes) {
+            self.aborted = true;
+        }
+
+        if self.nodes & (CHECK_INTERVAL - 1) == 0
+            && (self.stop.load(std::sync::atomic::Ordering::Relaxed)
+                || self
+                    .limits
+                    .time
+                    .is_some_and(|time| self.start.elapsed() >= time))
+        {
+            self.aborted = true;
+        }
+
+        self.aborted
+    }
+
+    /// Captures and queen promotions
+    fn is_tactical(&self, mv: &ChessMove) -> bool {
+        self.board.piece_at(mv.target).is_some()
+            || mv.is_en_passant(&self.board)
+            || mv.promotion == Some(Piece::Queen)
+    }
+
+    fn order(&self, mv: &ChessMove, tt_move: Option<ChessMove>, ply: usize) -> i32 {
+        if tt_move == Some(*mv) {
+            return 1_000_000;
+        }
+
+        let victim = if mv.is_en_passant(&self.board) {
+            Some(Piece::Pawn)
+        } else {
+            self.board.piece_at(mv.target).map(|(_, piece)| piece)
+        };
+
+        if let Some(victim) = victim {
+            return 100_000 + victim.to_index() as i32 * 10 - mv.piece.to_index() as i32;
+        }
+
+        if let Some(promotion) = mv.promotion {
+            return 90_000 + super::piece_value(promotion);
+        }
+
+        let killers = self.killers[ply];
+        if killers[0] == Some(*mv) {
+            return 80_000;
+        }
+        if killers[1] == Some(*mv) {
+            return 70_000;
+        }
+
+        self.history[mv.color.to_index()][mv.piece.to_index()][mv.target.to_index() as usize]
+    }
+
+    fn remember_cut(&mut self, mv: ChessMove, depth: u32, ply: usize) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+
+        let history = &mut self.history[mv.color.to_index()][mv.piece.to_index()]
+            [mv.target.to_index() as usize];
+        *history += (depth * depth) as i32;
+
+        // Stay under the killers
+        if *history > 60_000 {
+            self.history
+                .iter_mut()
+                .flatten()
+                .flatten()
+                .for_each(|history| *history /= 2);
+        }
+    }
+}
+
+// Mates are stored relative to the position, not to the root, so they stay right when found from another path
+fn to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
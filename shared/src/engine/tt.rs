@@ -0,0 +1,123 @@
+// Transposition table: remembers what the search found about a position, keyed by its Zobrist hash
+// A fixed number of slots, a new entry replaces the old one unless the old one was searched deeper
+
+use crate::chess::ChessMove;
+
+/// What the stored score means, a cut-off search only knows a bound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    // The real score is at least this much
+    Lower,
+    // The real score is at most this much
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: u32,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<ChessMove>,
+}
+
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    /// A table that takes around `megabytes` of memory, rounded down to a power of two of entries
+    pub fn new(megabytes: usize) -> Self {
+        let wanted = (megabytes * 1024 * 1024 / std::mem::size_of::<Option<Entry>>()).max(1);
+        let len = 1 << wanted.ilog2();
+
+        Self {
+            entries: vec![None; len],
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        // The length is a power of two
+        (key as usize) & (self.entries.len() - 1)
+    }
+
+    pub fn get(&self, key: u64) -> Option<Entry> {
+        self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    pub fn insert(&mut self, entry: Entry) {
+        let index = self.index(entry.key);
+        let slot = &mut self.entries[index];
+
+        // Keep deeper results of the same position
+        if let Some(old) = slot {
+            if old.key == entry.key && old.depth > entry.depth {
+                return;
+            }
+        }
+
+        *slot = Some(entry);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+
+    /// Number of entries the table can hold
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// How full the table is, in permille like UCI's hashfull
+    pub fn usage(&self) -> u32 {
+        let sample = self.entries.len().min(1000);
+        let used = self.entries[..sample].iter().flatten().count();
+        (used * 1000 / sample) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bound, Entry, TranspositionTable};
+
+    fn entry(key: u64, depth: u32) -> Entry {
+        Entry {
+            key,
+            depth,
+            score: depth as i32,
+            bound: Bound::Exact,
+            best_move: None,
+        }
+    }
+
+    #[test]
+    fn size() {
+        let tt = TranspositionTable::new(1);
+        println!("{} entries", tt.capacity());
+        assert!(tt.capacity().is_power_of_two());
+        assert!(tt.capacity() * std::mem::size_of::<Option<Entry>>() <= 1024 * 1024);
+    }
+
+    #[test]
+    fn replacement() {
+        let mut tt = TranspositionTable::new(1);
+        let len = tt.capacity() as u64;
+
+        tt.insert(entry(5, 4));
+        assert_eq!(tt.get(5).unwrap().depth, 4);
+        assert!(tt.get(5 + len).is_none());
+
+        // Shallower result of the same position
+        tt.insert(entry(5, 2));
+        assert_eq!(tt.get(5).unwrap().depth, 4);
+
+        // Another position on the same slot
+        tt.insert(entry(5 + len, 1));
+        assert!(tt.get(5).is_none());
+        assert_eq!(tt.get(5 + len).unwrap().depth, 1);
+
+        tt.clear();
+        assert_eq!(tt.usage(), 0);
+    }
+}
@@ -0,0 +1,12 @@
+#[derive(thiserror::Error, Debug)]
+pub enum BookError {
+    #[error("Could not read the book: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("A book is made of 16 bytes entries, this one has {0} bytes")]
+    Size(usize),
+    #[error("Could not read the games of {}: {error}", file.display())]
+    Pgn {
+        file: std::path::PathBuf,
+        error: super::pgn::PgnError,
+    },
+}
@@ -0,0 +1,15 @@
+#[derive(thiserror::Error, Debug)]
+pub enum ExternalEngineError {
+    #[error("Could not start the engine: {0}")]
+    Spawn(std::io::Error),
+    #[error("Could not talk to the engine: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The engine has exited")]
+    Exited,
+    #[error("The engine took too long to answer '{0}'")]
+    Timeout(&'static str),
+    #[error("The engine sent a move that can't be played: '{0}'")]
+    BestMove(String),
+    #[error("The engine can't play {0}")]
+    Unsupported(String),
+}
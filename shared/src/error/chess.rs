@@ -0,0 +1,56 @@
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum FenError {
+    #[error("A FEN string needs 4 to 6 fields, got {0}")]
+    FieldCount(usize),
+    #[error("Invalid piece placement: {0}")]
+    PiecePlacement(String),
+    #[error("Invalid active color '{0}'")]
+    ActiveColor(String),
+    #[error("Invalid castling rights '{0}'")]
+    CastlingRights(String),
+    #[error("Invalid en passant square '{0}'")]
+    EnPassant(String),
+    #[error("Invalid halfmove clock '{0}'")]
+    HalfmoveClock(String),
+    #[error("Invalid fullmove number '{0}'")]
+    FullmoveNumber(String),
+    #[error("Invalid remaining checks '{0}'")]
+    RemainingChecks(String),
+    #[error("Invalid pocket '{0}'")]
+    Pocket(String),
+    #[error("{0} has the wrong number of kings")]
+    KingCount(crate::chess::Color),
+    #[error("There is a pawn on {0}, pawns can't be on the first or last rank")]
+    PawnOnBackRank(crate::chess::Position),
+    #[error("{0} is in check but it's not their turn")]
+    OpponentInCheck(crate::chess::Color),
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum NotationError {
+    #[error("Could not read the move '{0}'")]
+    Invalid(String),
+    #[error("The move '{0}' is not legal in this position")]
+    Illegal(String),
+    #[error("The move '{0}' could be played by more than one piece")]
+    Ambiguous(String),
+}
+
+/// The parts of a [`Board`](crate::chess::Board) disagree with each other
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum BoardError {
+    #[error("{0} is both white and black")]
+    ColorOverlap(crate::chess::Position),
+    #[error("{0} has more than one piece")]
+    PieceOverlap(crate::chess::Position),
+    #[error("{0} has a piece without a color, or a color without a piece")]
+    Incomplete(crate::chess::Position),
+    #[error("The mailbox has {found:?} on {pos} but the bitboards say {expected:?}")]
+    Mailbox {
+        pos: crate::chess::Position,
+        expected: Option<(crate::chess::Color, crate::chess::Piece)>,
+        found: Option<(crate::chess::Color, crate::chess::Piece)>,
+    },
+    #[error("The board was sent in a newer wire format (version {0})")]
+    WireVersion(u8),
+}
@@ -1,2 +1,6 @@
+pub mod book;
+pub mod bot;
+pub mod chess;
 pub mod client;
+pub mod pgn;
 pub mod server;
@@ -0,0 +1,24 @@
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum PgnError {
+    #[error("The file ended in the middle of {0}")]
+    UnexpectedEnd(&'static str),
+    #[error("Could not read the tag '{0}'")]
+    Tag(String),
+    #[error("Unexpected '{0}' in the move text")]
+    Unexpected(char),
+    #[error("Unbalanced variation parentheses")]
+    Variation,
+    #[error("Unknown variant '{0}'")]
+    Variant(String),
+    #[error("The FEN tag is invalid: {0}")]
+    Fen(#[from] super::chess::FenError),
+    #[error("Could not play {move_number}{} {san}: {error}", if *.color == crate::chess::Color::White { "." } else { "..." })]
+    IllegalMove {
+        move_number: u32,
+        color: crate::chess::Color,
+        san: String,
+        error: super::chess::NotationError,
+    },
+    #[error("No game found")]
+    NoGame,
+}
@@ -0,0 +1,688 @@
+// Chess clocks, the server runs one for each game and sends what is left on it with every update
+// The time comes from a `TimeSource`, so the tests can move it forward without waiting
+
+use std::time::{Duration, Instant};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+// The most a game can ask for, the time controls come from the clients
+const MAX_BASE: Duration = Duration::from_secs(3 * 60 * 60);
+const MAX_INCREMENT: Duration = Duration::from_secs(3 * 60);
+const MAX_DAYS: u32 = 14;
+
+/// How much time the players get, picked by the player that creates the game
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TimeControl {
+    #[default]
+    Unlimited,
+    /// Base time, and time given back after each move
+    Increment { base: Duration, increment: Duration },
+    /// Base time, the first `delay` of each move isn't taken from the clock
+    Delay { base: Duration, delay: Duration },
+    /// A number of days for each move, what isn't used is lost
+    Correspondence { days: u32 },
+}
+
+/// What is left for each player, as the clients see it
+#[derive(Default, Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Clock {
+    control: TimeControl,
+    // Indexed by `Color::to_index`
+    remaining: [Duration; 2],
+    // Whose time is going down, none before the game starts and after it ends
+    running: Option<crate::chess::Color>,
+}
+
+/// Where the clocks get the time from
+pub trait TimeSource: Send {
+    fn now(&self) -> Instant;
+}
+
+/// The real time
+#[derive(Default, Debug, Clone, Copy)]
+pub struct SystemTime;
+
+/// How much of the network's latency is given back to the players
+/// The server only sees a move when it arrives, about half a round trip after it was played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LagCompensation {
+    /// At most that much is given back for one move
+    pub per_move: Duration,
+    /// And at most that much over a whole game, so a slow link can't buy time forever
+    pub quota: Duration,
+}
+
+/// A clock that runs by itself, the server keeps one for each game
+pub struct Timer {
+    clock: Clock,
+    source: Box<dyn TimeSource>,
+    // When the running player's turn started
+    turn_start: Instant,
+    lag: LagCompensation,
+    // Indexed by `Color::to_index`, the last round trip time measured for each player
+    rtt: [Duration; 2],
+    // Indexed by `Color::to_index`, what is left of the lag quota this game
+    quota: [Duration; 2],
+}
+
+impl TimeControl {
+    /// Time on both clocks when the game starts, none without a limit
+    pub fn base(&self) -> Option<Duration> {
+        match self {
+            TimeControl::Unlimited => None,
+            TimeControl::Increment { base, .. } | TimeControl::Delay { base, .. } => Some(*base),
+            TimeControl::Correspondence { days } => Some(DAY.saturating_mul(*days)),
+        }
+    }
+
+    /// Time each move gets for free, the delay is never taken so it counts as one
+    pub fn increment(&self) -> Duration {
+        match self {
+            TimeControl::Increment { increment, .. } => *increment,
+            TimeControl::Delay { delay, .. } => *delay,
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// The same control, with its times brought within what the server accepts
+    pub fn clamped(self) -> Self {
+        match self {
+            TimeControl::Unlimited => TimeControl::Unlimited,
+            TimeControl::Increment { base, increment } => TimeControl::Increment {
+                base: base.min(MAX_BASE),
+                increment: increment.min(MAX_INCREMENT),
+            },
+            TimeControl::Delay { base, delay } => TimeControl::Delay {
+                base: base.min(MAX_BASE),
+                delay: delay.min(MAX_INCREMENT),
+            },
+            TimeControl::Correspondence { days } => TimeControl::Correspondence {
+                days: days.clamp(1, MAX_DAYS),
+            },
+        }
+    }
+
+    /// Games played in one sitting, the bots can think on the clock
+    pub fn is_realtime(&self) -> bool {
+        matches!(
+            self,
+            TimeControl::Increment { .. } | TimeControl::Delay { .. }
+        )
+    }
+
+    /// The PGN TimeControl tag, like "300+5" in seconds or "1/86400" for a move per day, none without a limit
+    pub fn to_pgn(&self) -> Option<String> {
+        match self {
+            TimeControl::Unlimited => None,
+            TimeControl::Increment { base, increment } => {
+                Some(format!("{}+{}", base.as_secs(), increment.as_secs()))
+            }
+            // The standard has no delay, it's written like an increment with a 'd'
+            TimeControl::Delay { base, delay } => {
+                Some(format!("{}d{}", base.as_secs(), delay.as_secs()))
+            }
+            TimeControl::Correspondence { days } => {
+                Some(format!("1/{}", DAY.saturating_mul(*days).as_secs()))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Base times in minutes, like "5+3"
+        let minutes = |base: &Duration| base.as_secs_f64() / 60.;
+
+        match self {
+            TimeControl::Unlimited => write!(f, "Unlimited"),
+            TimeControl::Increment { base, increment } => {
+                write!(f, "{}+{}", minutes(base), increment.as_secs())
+            }
+            TimeControl::Delay { base, delay } => {
+                write!(f, "{} delay {}", minutes(base), delay.as_secs())
+            }
+            TimeControl::Correspondence { days: 1 } => write!(f, "1 day per move"),
+            TimeControl::Correspondence { days } => write!(f, "{days} days per move"),
+        }
+    }
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        Self {
+            control,
+            remaining: [control.base().unwrap_or_default(); 2],
+            running: None,
+        }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// Time left to that player, none without a limit
+    pub fn remaining(&self, color: crate::chess::Color) -> Option<Duration> {
+        self.control
+            .base()
+            .map(|_| self.remaining[color.to_index()])
+    }
+
+    pub fn running(&self) -> Option<crate::chess::Color> {
+        self.running
+    }
+
+    /// The running player, if `elapsed` on their move made them run out of time
+    pub fn flagged(&self, elapsed: Duration) -> Option<crate::chess::Color> {
+        let color = self.running?;
+        let remaining = self.remaining(color)?;
+        (self.counted(elapsed) >= remaining).then_some(color)
+    }
+
+    /// The running player played after `elapsed`, the time is taken and the other player's clock starts
+    pub fn press(&mut self, elapsed: Duration) {
+        let Some(color) = self.running else {
+            return;
+        };
+
+        let counted = self.counted(elapsed);
+        let remaining = &mut self.remaining[color.to_index()];
+        *remaining = match self.control {
+            // Every move gets the same time
+            TimeControl::Correspondence { days } => DAY.saturating_mul(days),
+            TimeControl::Increment { increment, .. } => {
+                remaining.saturating_sub(counted).saturating_add(increment)
+            }
+            _ => remaining.saturating_sub(counted),
+        };
+
+        self.running = Some(!color);
+    }
+
+    /// How the clock reads after `elapsed` on the current move
+    pub fn at(&self, elapsed: Duration) -> Self {
+        let mut clock = *self;
+        if let Some(color) = self.running {
+            let remaining = &mut clock.remaining[color.to_index()];
+            *remaining = remaining.saturating_sub(self.counted(elapsed));
+        }
+        clock
+    }
+
+    /// Time taken from the clock for a move that took `elapsed`
+    fn counted(&self, elapsed: Duration) -> Duration {
+        match self.control {
+            TimeControl::Delay { delay, .. } => elapsed.saturating_sub(delay),
+            _ => elapsed,
+        }
+    }
+}
+
+impl LagCompensation {
+    /// Nothing is given back
+    pub const NONE: Self = Self {
+        per_move: Duration::ZERO,
+        quota: Duration::ZERO,
+    };
+
+    /// What a move gets back on a link with that round trip time, when `quota` is left
+    pub fn credit(&self, rtt: Duration, quota: Duration) -> Duration {
+        (rtt / 2).min(self.per_move).min(quota)
+    }
+}
+
+impl Default for LagCompensation {
+    fn default() -> Self {
+        Self {
+            per_move: Duration::from_millis(500),
+            quota: Duration::from_secs(10),
+        }
+    }
+}
+
+impl TimeSource for SystemTime {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+impl Timer {
+    pub fn new(control: TimeControl) -> Self {
+        Self::with_source(control, Box::new(SystemTime))
+    }
+
+    pub fn with_source(control: TimeControl, source: Box<dyn TimeSource>) -> Self {
+        let turn_start = source.now();
+        let lag = LagCompensation::default();
+        Self {
+            clock: Clock::new(control),
+            source,
+            turn_start,
+            lag,
+            rtt: [Duration::ZERO; 2],
+            quota: [lag.quota; 2],
+        }
+    }
+
+    pub fn with_lag_compensation(mut self, lag: LagCompensation) -> Self {
+        self.lag = lag;
+        self.quota = [lag.quota; 2];
+        self
+    }
+
+    /// The latest round trip time measured for that player, their next moves are compensated with it
+    pub fn set_rtt(&mut self, color: crate::chess::Color, rtt: Duration) {
+        self.rtt[color.to_index()] = rtt;
+    }
+
+    /// What is left of that player's lag quota
+    pub fn lag_quota(&self, color: crate::chess::Color) -> Duration {
+        self.quota[color.to_index()]
+    }
+
+    /// Puts the base time back on both clocks, refills the lag quotas and starts the clock of `color`
+    pub fn start(&mut self, color: crate::chess::Color) {
+        self.clock = Clock {
+            running: Some(color),
+            ..Clock::new(self.clock.control)
+        };
+        self.turn_start = self.source.now();
+        self.quota = [self.lag.quota; 2];
+    }
+
+    /// Freezes both clocks, when the game ends
+    pub fn stop(&mut self) {
+        self.clock = self.clock();
+        self.clock.running = None;
+    }
+
+    /// The player that ran out of time, if any
+    /// Their move could still be on its way, the flag only falls once the lag they would get back is over too
+    pub fn flagged(&self) -> Option<crate::chess::Color> {
+        self.clock
+            .flagged(self.elapsed().saturating_sub(self.credit()))
+    }
+
+    /// The running player made a move, the time it spent on the network is given back
+    pub fn press(&mut self) {
+        let now = self.source.now();
+        let credit = self.credit();
+        if let Some(color) = self.clock.running {
+            self.quota[color.to_index()] -= credit;
+        }
+        self.clock.press(
+            now.saturating_duration_since(self.turn_start)
+                .saturating_sub(credit),
+        );
+        self.turn_start = now;
+    }
+
+    /// What is left right now, to send to the players
+    pub fn clock(&self) -> Clock {
+        self.clock.at(self.elapsed())
+    }
+
+    // The lag the running player would get back for a move now, only games played in one sitting have any
+    fn credit(&self) -> Duration {
+        match self.clock.running {
+            Some(color) if self.clock.control.is_realtime() => self
+                .lag
+                .credit(self.rtt[color.to_index()], self.quota[color.to_index()]),
+            _ => Duration::ZERO,
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.source.now().saturating_duration_since(self.turn_start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, LagCompensation, TimeControl, TimeSource, Timer};
+    use crate::chess::Color;
+    use std::time::{Duration, Instant};
+
+    // Only moves when the test says so
+    #[derive(Clone)]
+    struct ManualTime(std::sync::Arc<std::sync::Mutex<Instant>>);
+
+    impl ManualTime {
+        fn new() -> Self {
+            Self(std::sync::Arc::new(std::sync::Mutex::new(Instant::now())))
+        }
+
+        fn advance(&self, secs: u64) {
+            self.advance_ms(secs * 1000);
+        }
+
+        fn advance_ms(&self, millis: u64) {
+            *self.0.lock().unwrap() += Duration::from_millis(millis);
+        }
+    }
+
+    impl TimeSource for ManualTime {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn timer(control: TimeControl) -> (Timer, ManualTime) {
+        let time = ManualTime::new();
+        let mut timer = Timer::with_source(control, Box::new(time.clone()));
+        timer.start(Color::White);
+        (timer, time)
+    }
+
+    #[test]
+    fn increment() {
+        let (mut timer, time) = timer(TimeControl::Increment {
+            base: secs(300),
+            increment: secs(3),
+        });
+
+        time.advance(10);
+        assert_eq!(timer.clock().remaining(Color::White), Some(secs(290)));
+        timer.press();
+        assert_eq!(timer.clock().remaining(Color::White), Some(secs(293)));
+        assert_eq!(timer.clock().running(), Some(Color::Black));
+
+        // Only the running clock goes down
+        time.advance(20);
+        let clock = timer.clock();
+        println!("{clock:?}");
+        assert_eq!(clock.remaining(Color::White), Some(secs(293)));
+        assert_eq!(clock.remaining(Color::Black), Some(secs(280)));
+    }
+
+    #[test]
+    fn delay() {
+        let (mut timer, time) = timer(TimeControl::Delay {
+            base: secs(60),
+            delay: secs(5),
+        });
+
+        // Nothing is taken under the delay, and nothing is given back either
+        time.advance(3);
+        timer.press();
+        assert_eq!(timer.clock().remaining(Color::White), Some(secs(60)));
+
+        time.advance(8);
+        timer.press();
+        assert_eq!(timer.clock().remaining(Color::Black), Some(secs(57)));
+    }
+
+    #[test]
+    fn correspondence() {
+        let day = 24 * 60 * 60;
+        let (mut timer, time) = timer(TimeControl::Correspondence { days: 2 });
+
+        time.advance(day);
+        assert_eq!(timer.clock().remaining(Color::White), Some(secs(day)));
+        timer.press();
+        assert_eq!(timer.clock().remaining(Color::White), Some(secs(2 * day)));
+
+        time.advance(2 * day);
+        assert_eq!(timer.flagged(), Some(Color::Black));
+    }
+
+    #[test]
+    fn flag() {
+        let (mut timer, time) = timer(TimeControl::Increment {
+            base: secs(60),
+            increment: secs(0),
+        });
+
+        time.advance(59);
+        assert_eq!(timer.flagged(), None);
+        time.advance(1);
+        assert_eq!(timer.flagged(), Some(Color::White));
+
+        // Stopped clocks don't fall anymore
+        timer.stop();
+        time.advance(60);
+        assert_eq!(timer.flagged(), None);
+        assert_eq!(timer.clock().remaining(Color::White), Some(secs(0)));
+        assert_eq!(timer.clock().running(), None);
+
+        // A new round starts from the base time
+        timer.start(Color::Black);
+        assert_eq!(timer.clock().remaining(Color::White), Some(secs(60)));
+        assert_eq!(timer.clock().running(), Some(Color::Black));
+    }
+
+    #[test]
+    fn unlimited() {
+        let (timer, time) = timer(TimeControl::Unlimited);
+        time.advance(1_000_000);
+        assert_eq!(timer.flagged(), None);
+        assert_eq!(timer.clock().remaining(Color::White), None);
+        assert_eq!(Clock::default().control(), TimeControl::Unlimited);
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn lag_profiles() {
+        // Round trip time, and what a 2 second move costs with it
+        let profiles = [
+            ("local", ms(0), ms(2000)),
+            ("fiber", ms(20), ms(1990)),
+            ("mobile", ms(300), ms(1850)),
+            ("satellite", ms(1400), ms(1500)),
+        ];
+
+        for (name, rtt, cost) in profiles {
+            let (mut timer, time) = timer(TimeControl::Increment {
+                base: secs(60),
+                increment: secs(0),
+            });
+            timer.set_rtt(Color::White, rtt);
+
+            time.advance(2);
+            timer.press();
+            let remaining = timer.clock().remaining(Color::White).unwrap();
+            println!("{name}: {remaining:?}");
+            assert_eq!(remaining, secs(60) - cost, "{name}");
+            assert_eq!(timer.lag_quota(Color::White), secs(10) - (secs(2) - cost));
+
+            // The other player's link doesn't matter
+            assert_eq!(timer.lag_quota(Color::Black), secs(10));
+        }
+    }
+
+    #[test]
+    fn lag_quota() {
+        let (timer, time) = timer(TimeControl::Increment {
+            base: secs(300),
+            increment: secs(0),
+        });
+        let mut timer = timer.with_lag_compensation(LagCompensation {
+            per_move: ms(500),
+            quota: secs(2),
+        });
+        timer.start(Color::White);
+        timer.set_rtt(Color::White, secs(2));
+        timer.set_rtt(Color::Black, ms(100));
+
+        // Half a second back on each move, until the 2 seconds of the quota are used
+        let mut white = secs(300);
+        for credit in [500, 500, 500, 500, 0, 0] {
+            time.advance(3);
+            timer.press();
+            white = white - secs(3) + ms(credit);
+            assert_eq!(timer.clock().remaining(Color::White), Some(white));
+
+            time.advance(1);
+            timer.press();
+        }
+        assert_eq!(timer.lag_quota(Color::White), Duration::ZERO);
+        assert_eq!(timer.lag_quota(Color::Black), secs(2) - ms(50) * 6);
+
+        // A new round gets a full quota
+        timer.start(Color::White);
+        assert_eq!(timer.lag_quota(Color::White), secs(2));
+    }
+
+    #[test]
+    fn lag_flag() {
+        let (mut timer, time) = timer(TimeControl::Increment {
+            base: secs(10),
+            increment: secs(0),
+        });
+        timer.set_rtt(Color::White, ms(600));
+
+        // The move could still be on its way for the 300ms the player would get back
+        time.advance(10);
+        assert_eq!(timer.flagged(), None);
+        time.advance_ms(299);
+        assert_eq!(timer.flagged(), None);
+        time.advance_ms(1);
+        assert_eq!(timer.flagged(), Some(Color::White));
+
+        // Without compensation, the flag falls on time
+        let time = ManualTime::new();
+        let mut timer = Timer::with_source(
+            TimeControl::Increment {
+                base: secs(10),
+                increment: secs(0),
+            },
+            Box::new(time.clone()),
+        )
+        .with_lag_compensation(LagCompensation::NONE);
+        timer.start(Color::White);
+        timer.set_rtt(Color::White, ms(600));
+        time.advance(10);
+        assert_eq!(timer.flagged(), Some(Color::White));
+    }
+
+    #[test]
+    fn lag_correspondence() {
+        // A day per move doesn't need the milliseconds back
+        let day = 24 * 60 * 60;
+        let (mut timer, time) = timer(TimeControl::Correspondence { days: 1 });
+        timer.set_rtt(Color::White, secs(1));
+
+        time.advance(day);
+        assert_eq!(timer.flagged(), Some(Color::White));
+        assert_eq!(timer.lag_quota(Color::White), secs(10));
+    }
+
+    #[test]
+    fn clamped() {
+        let huge = TimeControl::Increment {
+            base: Duration::MAX,
+            increment: Duration::MAX,
+        };
+        let clamped = huge.clamped();
+        println!("{clamped}");
+        assert_eq!(clamped.base(), Some(secs(3 * 60 * 60)));
+        assert_eq!(clamped.increment(), secs(3 * 60));
+
+        // Still doesn't overflow when it gets through without being clamped
+        let (mut timer, time) = timer(huge);
+        time.advance(1);
+        timer.press();
+        assert_eq!(timer.clock().remaining(Color::White), Some(Duration::MAX));
+
+        assert_eq!(
+            TimeControl::Correspondence { days: 0 }.clamped(),
+            TimeControl::Correspondence { days: 1 }
+        );
+        let five_three = TimeControl::Increment {
+            base: secs(300),
+            increment: secs(3),
+        };
+        assert_eq!(five_three.clamped(), five_three);
+    }
+
+    #[test]
+    fn pgn() {
+        let pgn = |control: TimeControl| control.to_pgn();
+
+        assert_eq!(pgn(TimeControl::Unlimited), None);
+        assert_eq!(
+            pgn(TimeControl::Increment {
+                base: secs(300),
+                increment: secs(5)
+            }),
+            Some(String::from("300+5"))
+        );
+        assert_eq!(
+            pgn(TimeControl::Delay {
+                base: secs(600),
+                delay: secs(2)
+            }),
+            Some(String::from("600d2"))
+        );
+        assert_eq!(
+            pgn(TimeControl::Correspondence { days: 3 }),
+            Some(String::from("1/259200"))
+        );
+
+        assert_eq!(
+            TimeControl::Increment {
+                base: secs(300),
+                increment: secs(3)
+            }
+            .to_string(),
+            "5+3"
+        );
+        assert_eq!(
+            TimeControl::Correspondence { days: 3 }.to_string(),
+            "3 days per move"
+        );
+    }
+
+    #[test]
+    fn timeout() {
+        use crate::game::EndReason;
+
+        // Black can't win with a lone king, that's a draw
+        let board = crate::chess::Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            EndReason::from_timeout(&board, Color::White),
+            (EndReason::Timeout, None)
+        );
+        assert_eq!(
+            EndReason::from_timeout(&board, Color::Black),
+            (EndReason::Timeout, Some(Color::White))
+        );
+
+        // Nor with a knight or a bishop alone
+        for fen in [
+            "4k1n1/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k1b1/8/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            let board = crate::chess::Board::from_fen(fen).unwrap();
+            assert_eq!(
+                EndReason::from_timeout(&board, Color::White),
+                (EndReason::Timeout, None),
+                "{fen}"
+            );
+        }
+
+        // Unless White has something that could block their own king
+        let board = crate::chess::Board::from_fen("4k1n1/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            EndReason::from_timeout(&board, Color::White),
+            (EndReason::Timeout, Some(Color::Black))
+        );
+
+        // The king is just a piece in antichess, losing it all can still win
+        let board = crate::chess::Board::from_variant_fen(
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            crate::chess::Variant::Antichess,
+        )
+        .unwrap();
+        assert_eq!(
+            EndReason::from_timeout(&board, Color::White),
+            (EndReason::Timeout, Some(Color::Black))
+        );
+    }
+}
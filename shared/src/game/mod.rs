@@ -1,8 +1,35 @@
+mod clock;
+
+pub use clock::{Clock, LagCompensation, SystemTime, TimeControl, TimeSource, Timer};
+
 #[derive(Default, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Game {
     pub id: crate::id::Id,
     pub players: [Option<Player>; 2],
     pub state: State,
+    pub options: Options,
+    /// What was left to each player when the server sent the game
+    pub clock: Clock,
+}
+
+/// Chosen by the player that creates the game
+#[derive(Default, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Options {
+    /// How the bot of this game has to play, if it has one
+    pub bot: Option<crate::engine::Profile>,
+    pub setup: Setup,
+    /// The rules of the game, the variants with their own starting position ignore the setup
+    pub variant: crate::chess::Variant,
+    pub time_control: TimeControl,
+}
+
+/// Where the pieces stand when the game starts
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Setup {
+    #[default]
+    Standard,
+    /// Fischer random, by the number of the setup, the server picks one when there is none
+    Chess960(Option<u16>),
 }
 
 #[derive(Default, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -32,12 +59,51 @@ pub enum State {
     },
     GameEnd {
         winner: Option<crate::id::Id>,
+        reason: EndReason,
     },
 }
 
+/// Why a game stopped
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    enum_variant_name::VariantName,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum EndReason {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    /// A player ran out of time
+    Timeout,
+    /// Won by the rules of the variant
+    Variant(crate::chess::VariantWin),
+}
+
 impl Game {
     pub fn new(id: crate::id::Id, players: [Option<Player>; 2], state: State) -> Self {
-        Self { id, players, state }
+        Self {
+            id,
+            players,
+            state,
+            options: Options::default(),
+            clock: Clock::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
     }
 
     pub fn id(&self) -> crate::id::Id {
@@ -65,6 +131,89 @@ impl Game {
     pub fn state_mut(&mut self) -> &mut State {
         &mut self.state
     }
+
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    pub fn variant(&self) -> crate::chess::Variant {
+        self.options.variant
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+}
+
+impl Options {
+    /// The starting position of the game, none for a Chess960 setup that wasn't picked yet or doesn't exist
+    pub fn start_board(&self) -> Option<crate::chess::Board> {
+        if self.variant.has_own_setup() {
+            return Some(crate::chess::Board::from_variant(self.variant));
+        }
+
+        let board = self.setup.board()?;
+        crate::chess::Board::from_variant_fen(&board.to_fen(), self.variant).ok()
+    }
+}
+
+impl Setup {
+    /// The starting position, none for a Chess960 setup that wasn't picked yet or doesn't exist
+    pub fn board(&self) -> Option<crate::chess::Board> {
+        match self {
+            Setup::Standard => Some(crate::chess::Board::default()),
+            Setup::Chess960(number) => crate::chess::Board::chess960((*number)?),
+        }
+    }
+}
+
+impl EndReason {
+    /// Checks if the game is over on that board, with the color of the winner if it's not a draw
+    pub fn from_board(board: &crate::chess::Board) -> Option<(Self, Option<crate::chess::Color>)> {
+        if let Some((win, winner)) = board.variant().win(board) {
+            return Some((EndReason::Variant(win), Some(winner)));
+        }
+
+        if board.is_checkmate() {
+            return Some((EndReason::Checkmate, Some(!board.next_to_play())));
+        }
+
+        let draw = if board.is_stalemate() {
+            EndReason::Stalemate
+        } else if board.is_insufficient_material() {
+            EndReason::InsufficientMaterial
+        } else if board.is_fifty_moves() {
+            EndReason::FiftyMoveRule
+        } else if board.is_threefold_repetition() {
+            EndReason::ThreefoldRepetition
+        } else {
+            return None;
+        };
+
+        Some((draw, None))
+    }
+
+    /// The player that ran out of time loses, it's a draw if the other one could never win anyway, like with a lone king
+    pub fn from_timeout(
+        board: &crate::chess::Board,
+        flagged: crate::chess::Color,
+    ) -> (Self, Option<crate::chess::Color>) {
+        let winner = !flagged;
+
+        (
+            EndReason::Timeout,
+            (!board.has_insufficient_material(winner)).then_some(winner),
+        )
+    }
+}
+
+impl std::fmt::Display for EndReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndReason::Variant(win) => write!(f, "{}", win.variant_name()),
+            _ => write!(f, "{}", self.variant_name()),
+        }
+    }
 }
 
 impl Player {
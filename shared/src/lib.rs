@@ -6,9 +6,11 @@ pub const DEFAULT_ADDRESS: std::net::SocketAddr = std::net::SocketAddr::V4(
 );
 
 pub mod chess;
+pub mod engine;
 pub mod error;
 pub mod file;
 pub mod game;
 pub mod id;
 pub mod maths;
 pub mod message;
+pub mod pgn;
@@ -9,10 +9,13 @@ pub enum ClientMessage {
     RequestGames,
     GameJoinRequest(super::id::Id),
     GameInfoRequest(super::id::Id),
-    GameCreateRequest,
+    GameCreateRequest(super::game::Options),
+    // The server plays the other side, with the options' bot profile if there is one
+    BotGameCreateRequest(super::game::Options),
     LeaveGameRequest,
 
     // Gaming time
+    // Crazyhouse drops are moves that start and end on the square the piece is dropped on
     MakeMove(super::chess::ChessMove),
 }
 
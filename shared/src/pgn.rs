@@ -0,0 +1,683 @@
+// Portable Game Notation, used to save the games the server hosts and to load them in other tools
+
+use crate::chess::{Board, ChessMove, Color, Variant};
+use crate::error::{chess::NotationError, pgn::PgnError};
+
+/// Tags every PGN game has, in that order
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// The spec asks for lines shorter than 80 characters
+const MAX_LINE_LENGTH: usize = 79;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    #[default]
+    Ongoing,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pgn {
+    tags: Vec<(String, String)>,
+    start: Board,
+    board: Board,
+    moves: Vec<ChessMove>,
+    outcome: Outcome,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Symbol(String),
+    VariationStart,
+    VariationEnd,
+}
+
+impl Outcome {
+    pub fn from_winner(winner: Option<Color>) -> Self {
+        match winner {
+            Some(Color::White) => Outcome::WhiteWins,
+            Some(Color::Black) => Outcome::BlackWins,
+            None => Outcome::Draw,
+        }
+    }
+
+    pub fn to_pgn(&self) -> &'static str {
+        match self {
+            Outcome::WhiteWins => "1-0",
+            Outcome::BlackWins => "0-1",
+            Outcome::Draw => "1/2-1/2",
+            Outcome::Ongoing => "*",
+        }
+    }
+
+    pub fn from_pgn(result: &str) -> Option<Self> {
+        match result {
+            "1-0" => Some(Outcome::WhiteWins),
+            "0-1" => Some(Outcome::BlackWins),
+            "1/2-1/2" => Some(Outcome::Draw),
+            "*" => Some(Outcome::Ongoing),
+            _ => None,
+        }
+    }
+}
+
+impl Pgn {
+    pub fn new(white: &crate::game::Player, black: &crate::game::Player) -> Self {
+        Self::from_position(white, black, Board::default())
+    }
+
+    /// For games that don't start from the usual position, the FEN is saved in the tags
+    pub fn from_position(
+        white: &crate::game::Player,
+        black: &crate::game::Player,
+        start: Board,
+    ) -> Self {
+        let tags = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|name| (name.to_string(), String::from("?")))
+            .collect();
+
+        let mut pgn = Self::with_start(tags, start);
+
+        pgn.set_tag("Event", "Chess game");
+        pgn.set_tag("Date", &today());
+        pgn.set_tag("White", &white.name);
+        pgn.set_tag("Black", &black.name);
+        pgn.set_outcome(Outcome::Ongoing);
+
+        let fen = pgn.start.to_fen();
+        if fen != crate::chess::STARTING_FEN {
+            pgn.set_tag("SetUp", "1");
+            pgn.set_tag("FEN", &fen);
+        }
+
+        let variant = pgn.start.variant();
+        if variant != Variant::Standard {
+            pgn.set_tag("Variant", &variant.to_string());
+        }
+
+        pgn
+    }
+
+    fn with_start(tags: Vec<(String, String)>, start: Board) -> Self {
+        Self {
+            tags,
+            board: start.clone(),
+            start,
+            moves: Vec::new(),
+            outcome: Outcome::Ongoing,
+        }
+    }
+
+    pub fn with_tag(mut self, name: &str, value: &str) -> Self {
+        self.set_tag(name, value);
+        self
+    }
+
+    /// Written like "300+5", base time and increment in seconds
+    pub fn with_time_control(self, time_control: &str) -> Self {
+        self.with_tag("TimeControl", time_control)
+    }
+
+    /// Replaces the tag if it's already there
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    pub fn moves(&self) -> &[ChessMove] {
+        &self.moves
+    }
+
+    /// Position the game started from
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    /// Position after the last move
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+
+    pub fn set_outcome(&mut self, outcome: Outcome) {
+        self.outcome = outcome;
+        self.set_tag("Result", outcome.to_pgn());
+    }
+
+    /// Plays the move on the game's board, it has to be legal
+    pub fn push(&mut self, mv: ChessMove) -> Result<(), PgnError> {
+        if self.board.make_move(&mv).is_err() {
+            return Err(self.illegal(mv.to_uci(), NotationError::Illegal(mv.to_uci())));
+        }
+
+        self.moves.push(mv);
+        Ok(())
+    }
+
+    fn push_san(&mut self, san: &str) -> Result<(), PgnError> {
+        let mv = ChessMove::from_san(san, &self.board)
+            .map_err(|error| self.illegal(san.to_string(), error))?;
+        self.push(mv)
+    }
+
+    fn illegal(&self, san: String, error: NotationError) -> PgnError {
+        PgnError::IllegalMove {
+            move_number: self.board.fullmove_number(),
+            color: self.board.next_to_play(),
+            san,
+            error,
+        }
+    }
+
+    /// Reads the first game of the text
+    pub fn parse(text: &str) -> Result<Self, PgnError> {
+        let mut tokens = tokenize(text)?.into_iter().peekable();
+
+        if tokens.peek().is_none() {
+            return Err(PgnError::NoGame);
+        }
+
+        Self::read_game(&mut tokens)
+    }
+
+    /// Reads every game of a PGN file
+    pub fn parse_all(text: &str) -> Result<Vec<Self>, PgnError> {
+        let mut tokens = tokenize(text)?.into_iter().peekable();
+        let mut games = Vec::new();
+
+        while tokens.peek().is_some() {
+            games.push(Self::read_game(&mut tokens)?);
+        }
+
+        Ok(games)
+    }
+
+    fn read_game(
+        tokens: &mut std::iter::Peekable<std::vec::IntoIter<Token>>,
+    ) -> Result<Self, PgnError> {
+        let mut tags = Vec::new();
+        while let Some(Token::Tag(name, value)) =
+            tokens.next_if(|token| matches!(token, Token::Tag(..)))
+        {
+            tags.push((name, value));
+        }
+
+        let tag = |tag: &str| {
+            tags.iter()
+                .find(|(name, _)| name == tag)
+                .map(|(_, value)| value)
+        };
+
+        // Chess960 only changes the start, which the FEN already gives
+        let variant = match tag("Variant").map(|name| name.as_str()) {
+            None | Some("Chess960" | "Fischerandom" | "From Position") => Variant::default(),
+            Some(name) => name
+                .parse::<Variant>()
+                .map_err(|_| PgnError::Variant(name.to_string()))?,
+        };
+
+        let start = match tag("FEN") {
+            Some(fen) => Board::from_variant_fen(fen, variant)?,
+            None => Board::from_variant(variant),
+        };
+
+        let mut pgn = Self::with_start(tags, start);
+        let mut outcome = pgn.tag("Result").and_then(Outcome::from_pgn);
+
+        // Variations are skipped, only the main line is kept
+        let mut depth = 0usize;
+
+        // A game without result ends where the next one's tags start
+        while let Some(token) = tokens.next_if(|token| !matches!(token, Token::Tag(..))) {
+            let symbol = match token {
+                Token::VariationStart => {
+                    depth += 1;
+                    continue;
+                }
+                Token::VariationEnd => {
+                    depth = depth.checked_sub(1).ok_or(PgnError::Variation)?;
+                    continue;
+                }
+                Token::Symbol(_) if depth > 0 => continue,
+                Token::Symbol(symbol) => symbol,
+                Token::Tag(..) => unreachable!(),
+            };
+
+            if let Some(result) = Outcome::from_pgn(&symbol) {
+                outcome = Some(result);
+                break;
+            }
+
+            let san = strip_move_number(&symbol);
+
+            // Move numbers alone, or annotations written apart from their move
+            if san.chars().all(|c| c == '!' || c == '?') {
+                continue;
+            }
+
+            pgn.push_san(san)?;
+        }
+
+        if depth != 0 {
+            return Err(PgnError::Variation);
+        }
+
+        pgn.set_outcome(outcome.unwrap_or_default());
+
+        Ok(pgn)
+    }
+}
+
+impl std::fmt::Display for Pgn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(
+                f,
+                "[{name} \"{}\"]",
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            )?;
+        }
+        writeln!(f)?;
+
+        let mut words = Vec::new();
+        let mut board = self.start.clone();
+
+        for (i, mv) in self.moves.iter().enumerate() {
+            let number = board.fullmove_number();
+            if board.next_to_play() == Color::White {
+                words.push(format!("{number}."));
+            } else if i == 0 {
+                words.push(format!("{number}..."));
+            }
+
+            words.push(mv.to_san(&board));
+
+            // Moves are checked when they are added
+            let _ = board.make_move(mv);
+        }
+        words.push(self.outcome.to_pgn().to_string());
+
+        let mut line_length = 0;
+        for word in words {
+            if line_length > 0 && line_length + 1 + word.len() > MAX_LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{word}")?;
+            line_length += word.len();
+        }
+
+        writeln!(f)
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            // Rest of the line comments, '%' is an escape used by some tools
+            ';' => {
+                chars.by_ref().find(|c| *c == '\n');
+                line_start = true;
+                continue;
+            }
+            '%' if line_start => {
+                chars.by_ref().find(|c| *c == '\n');
+                line_start = true;
+                continue;
+            }
+            '{' => {
+                chars
+                    .by_ref()
+                    .find(|c| *c == '}')
+                    .ok_or(PgnError::UnexpectedEnd("a comment"))?;
+            }
+            '[' => tokens.push(read_tag(&mut chars)?),
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            // Numeric Annotation Glyph, like $1 for '!'
+            '$' => while chars.next_if(|c| c.is_ascii_digit()).is_some() {},
+            c if c.is_whitespace() => {}
+            c if is_symbol_char(c) => {
+                let mut symbol = String::from(c);
+                while let Some(c) = chars.next_if(|c| is_symbol_char(*c)) {
+                    symbol.push(c);
+                }
+                tokens.push(Token::Symbol(symbol));
+            }
+            c => return Err(PgnError::Unexpected(c)),
+        }
+
+        line_start = c == '\n';
+    }
+
+    Ok(tokens)
+}
+
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "+#=:-/*!?._".contains(c)
+}
+
+/// Reads a `[Name "value"]` tag, the '[' is already consumed
+fn read_tag(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Token, PgnError> {
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '"' && *c != ']') {
+        name.push(c);
+    }
+
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+    if name.is_empty() || chars.next() != Some('"') {
+        return Err(PgnError::Tag(name));
+    }
+
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('\\') => match chars.next() {
+                Some(c) => value.push(c),
+                None => return Err(PgnError::UnexpectedEnd("a tag")),
+            },
+            Some('"') => break,
+            Some(c) => value.push(c),
+            None => return Err(PgnError::UnexpectedEnd("a tag")),
+        }
+    }
+
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+    match chars.next() {
+        Some(']') => Ok(Token::Tag(name, value)),
+        Some(_) => Err(PgnError::Tag(name)),
+        None => Err(PgnError::UnexpectedEnd("a tag")),
+    }
+}
+
+/// "12." and "12..." are move numbers, they can also be glued to the move like in "12...Nf3"
+/// Castles written with zeros ("0-0") are left alone
+fn strip_move_number(symbol: &str) -> &str {
+    let rest = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+
+    if rest.is_empty() || rest.starts_with('.') {
+        rest.trim_start_matches('.')
+    } else {
+        symbol
+    }
+}
+
+/// Today's date as written in PGN dates, "2024.03.02"
+fn today() -> String {
+    let days = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / 86_400)
+        .unwrap_or(0);
+
+    date_from_days(days as i64)
+}
+
+/// Days since 1970-01-01 to a date, from Howard Hinnant's `civil_from_days`
+fn date_from_days(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Outcome, Pgn};
+    use crate::chess::{Board, ChessMove, Color, Variant, VariantWin};
+    use crate::error::{chess::NotationError, pgn::PgnError};
+
+    fn players() -> (crate::game::Player, crate::game::Player) {
+        (
+            crate::game::Player::new(crate::id::Id::new(), String::from("Alice"), None),
+            crate::game::Player::new(crate::id::Id::new(), String::from("Bob"), None),
+        )
+    }
+
+    fn play(pgn: &mut Pgn, sans: &[&str]) {
+        for san in sans {
+            let mv = ChessMove::from_san(san, pgn.board()).unwrap();
+            pgn.push(mv).unwrap();
+        }
+    }
+
+    #[test]
+    fn export() {
+        let (white, black) = players();
+
+        let mut pgn = Pgn::new(&white, &black)
+            .with_tag("Date", "2024.03.02")
+            .with_time_control("300+5");
+
+        play(&mut pgn, &["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+
+        let text = pgn.to_string();
+        println!("{text}");
+        assert_eq!(
+            text,
+            "[Event \"Chess game\"]
+[Site \"?\"]
+[Date \"2024.03.02\"]
+[Round \"?\"]
+[White \"Alice\"]
+[Black \"Bob\"]
+[Result \"*\"]
+[TimeControl \"300+5\"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 *
+"
+        );
+
+        pgn.set_outcome(Outcome::from_winner(Some(Color::White)));
+        assert!(pgn.to_string().contains("[Result \"1-0\"]"));
+        assert!(pgn.to_string().ends_with("3. Bb5 1-0\n"));
+    }
+
+    #[test]
+    fn export_from_position() {
+        let (white, black) = players();
+
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 12";
+        let mut pgn = Pgn::from_position(&white, &black, Board::from_fen(fen).unwrap());
+        play(&mut pgn, &["Kd7", "e4"]);
+
+        let text = pgn.to_string();
+        println!("{text}");
+        assert!(text.contains("[SetUp \"1\"]"));
+        assert!(text.contains(&format!("[FEN \"{fen}\"]")));
+        assert!(text.ends_with("12... Kd7 13. e4 *\n"));
+
+        let parsed = Pgn::parse(&text).unwrap();
+        assert_eq!(parsed.moves(), pgn.moves());
+        assert_eq!(parsed.board().to_fen(), pgn.board().to_fen());
+    }
+
+    #[test]
+    fn variant() {
+        let (white, black) = players();
+
+        let mut pgn = Pgn::from_position(&white, &black, Board::from_variant(Variant::Horde));
+        play(&mut pgn, &["a5", "e5"]);
+
+        let text = pgn.to_string();
+        println!("{text}");
+        assert!(text.contains("[Variant \"Horde\"]"));
+
+        let parsed = Pgn::parse(&text).unwrap();
+        assert_eq!(parsed.board().variant(), Variant::Horde);
+        assert_eq!(parsed.board().to_fen(), pgn.board().to_fen());
+
+        // Read with the rules of the variant, the king reaching e5 won
+        let king_of_the_hill =
+            "[Variant \"King of the Hill\"]\n\n1. d4 e5 2. dxe5 Ke7 3. Kd2 Ke6 4. Kd3 Kxe5 *";
+        let parsed = Pgn::parse(king_of_the_hill).unwrap();
+        assert_eq!(
+            Variant::KingOfTheHill.win(parsed.board()),
+            Some((VariantWin::KingInTheCenter, Color::Black))
+        );
+
+        assert_eq!(
+            Pgn::parse("[Variant \"Atomic\"]\n\n1. e4 *").unwrap_err(),
+            PgnError::Variant(String::from("Atomic"))
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let (white, black) = players();
+
+        let mut pgn = Pgn::new(&white, &black);
+        // Long enough to wrap the lines
+        play(
+            &mut pgn,
+            &[
+                "e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6", "Nc3", "Bc5", "d3", "d6", "O-O", "O-O",
+                "Bg5", "h6", "Bh4", "g5", "Bg3", "Bg4", "h3", "Bh5", "a3", "a6",
+            ],
+        );
+
+        let text = pgn.to_string();
+        println!("{text}");
+        assert!(text.lines().all(|line| line.len() < 80));
+
+        let parsed = Pgn::parse(&text).unwrap();
+        assert_eq!(parsed.moves(), pgn.moves());
+        assert_eq!(parsed.tags(), pgn.tags());
+        assert_eq!(parsed.outcome(), Outcome::Ongoing);
+    }
+
+    #[test]
+    fn import() {
+        let text = r#"
+% Exported by some tool
+[Event "Casual \"blitz\""]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 {The best by test} e5 $1 2.Bc4 (2. Nf3 Nc6 (2... d6) 3. Bb5) 2...Nc6 ; a comment
+3. Qh5 Nf6?? 4. Qxf7# 1-0
+"#;
+
+        let pgn = Pgn::parse(text).unwrap();
+
+        assert_eq!(pgn.tag("Event"), Some("Casual \"blitz\""));
+        assert_eq!(pgn.tag("White"), Some("Alice"));
+        assert_eq!(pgn.moves().len(), 7);
+        assert_eq!(pgn.outcome(), Outcome::WhiteWins);
+        assert!(pgn.board().is_checkmate());
+    }
+
+    #[test]
+    fn import_several_games() {
+        let text = "[Event \"1\"]\n\n1. e4 e5 1/2-1/2\n\n[Event \"2\"]\n\n1. d4 0-0-0 *\n";
+        assert!(matches!(
+            Pgn::parse_all(text),
+            Err(PgnError::IllegalMove { .. })
+        ));
+
+        // The second game has no result
+        let text =
+            "[Event \"1\"]\n\n1. e4 e5 1/2-1/2\n\n[Event \"2\"]\n\n1. d4 d5\n\n[Event \"3\"]\n";
+        let games = Pgn::parse_all(text).unwrap();
+
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[0].outcome(), Outcome::Draw);
+        assert_eq!(games[1].moves().len(), 2);
+        assert_eq!(games[1].outcome(), Outcome::Ongoing);
+        assert_eq!(games[2].tag("Event"), Some("3"));
+        assert!(games[2].moves().is_empty());
+    }
+
+    #[test]
+    fn import_errors() {
+        assert_eq!(
+            Pgn::parse("1. e4 e5 2. Ke3 Nc6 *").unwrap_err(),
+            PgnError::IllegalMove {
+                move_number: 2,
+                color: Color::White,
+                san: String::from("Ke3"),
+                error: NotationError::Illegal(String::from("Ke3")),
+            }
+        );
+        assert_eq!(
+            Pgn::parse("1. e4 e5 2. Nf3 Nc6 3. Bb5 Nf6 4. Bxc6 dxc6 5. Bc4 *")
+                .unwrap_err()
+                .to_string(),
+            "Could not play 5. Bc4: The move 'Bc4' is not legal in this position"
+        );
+        assert!(matches!(
+            Pgn::parse("1. e4 e5 2. Nf3 Ng6"),
+            Err(PgnError::IllegalMove {
+                move_number: 2,
+                color: Color::Black,
+                ..
+            })
+        ));
+
+        assert_eq!(
+            Pgn::parse("1. e4 (1. d4 *").unwrap_err(),
+            PgnError::Variation
+        );
+        assert_eq!(Pgn::parse("1. e4 ) *").unwrap_err(), PgnError::Variation);
+        assert_eq!(
+            Pgn::parse("1. e4 {unfinished *").unwrap_err(),
+            PgnError::UnexpectedEnd("a comment")
+        );
+        assert_eq!(
+            Pgn::parse("[Event Casual]").unwrap_err(),
+            PgnError::Tag(String::from("Event"))
+        );
+        assert!(matches!(
+            Pgn::parse("[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]"),
+            Err(PgnError::Fen(_))
+        ));
+        assert_eq!(Pgn::parse("  \n").unwrap_err(), PgnError::NoGame);
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(super::date_from_days(0), "1970.01.01");
+        assert_eq!(super::date_from_days(19_723), "2024.01.01");
+        assert_eq!(super::date_from_days(19_782), "2024.02.29");
+        println!("{}", super::today());
+    }
+}
@@ -0,0 +1,244 @@
+// What the GUI can send, unknown commands and tokens are ignored like the protocol asks
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Uci,
+    IsReady,
+    UciNewGame,
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
+    Position {
+        // None for the starting position
+        fen: Option<String>,
+        moves: Vec<String>,
+    },
+    Go(Go),
+    Stop,
+    Quit,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Go {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<std::time::Duration>,
+    pub wtime: Option<std::time::Duration>,
+    pub btime: Option<std::time::Duration>,
+    pub winc: Option<std::time::Duration>,
+    pub binc: Option<std::time::Duration>,
+    pub movestogo: Option<u32>,
+    pub infinite: bool,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut tokens = line.split_whitespace();
+
+        // The GUI is allowed to send junk before the command
+        let command = loop {
+            match tokens.next()? {
+                "uci" => break Self::Uci,
+                "isready" => break Self::IsReady,
+                "ucinewgame" => break Self::UciNewGame,
+                "setoption" => break parse_setoption(tokens),
+                "position" => break parse_position(tokens)?,
+                "go" => break Self::Go(parse_go(tokens)),
+                "stop" => break Self::Stop,
+                "quit" => break Self::Quit,
+                _ => continue,
+            }
+        };
+
+        Some(command)
+    }
+}
+
+// setoption name <id> [value <x>], both can have spaces
+fn parse_setoption<'a>(tokens: impl Iterator<Item = &'a str>) -> Command {
+    let mut name = Vec::new();
+    let mut value = None::<Vec<&str>>;
+
+    for token in tokens.skip_while(|token| *token != "name").skip(1) {
+        match (token, value.as_mut()) {
+            ("value", None) => value = Some(Vec::new()),
+            (_, Some(value)) => value.push(token),
+            (_, None) => name.push(token),
+        }
+    }
+
+    Command::SetOption {
+        name: name.join(" "),
+        value: value.map(|value| value.join(" ")),
+    }
+}
+
+// position [fen <fenstring> | startpos] moves <move1> .... <movei>
+fn parse_position<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Option<Command> {
+    let fen = match tokens.next()? {
+        "startpos" => None,
+        "fen" => Some(
+            tokens
+                .by_ref()
+                .take_while(|token| *token != "moves")
+                .collect::<Vec<&str>>()
+                .join(" "),
+        ),
+        _ => return None,
+    };
+
+    // With a FEN, `take_while` already ate the "moves"
+    let moves = tokens
+        .skip_while(|token| *token == "moves")
+        .map(String::from)
+        .collect();
+
+    Some(Command::Position { fen, moves })
+}
+
+fn parse_go<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Go {
+    let mut go = Go::default();
+
+    while let Some(token) = tokens.next() {
+        let mut number = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+        let mut millis = || number().map(std::time::Duration::from_millis);
+
+        match token {
+            "depth" => go.depth = number().map(|depth| depth as u32),
+            "nodes" => go.nodes = number(),
+            "movetime" => go.movetime = millis(),
+            "wtime" => go.wtime = millis(),
+            "btime" => go.btime = millis(),
+            "winc" => go.winc = millis(),
+            "binc" => go.binc = millis(),
+            "movestogo" => go.movestogo = number().map(|moves| moves as u32),
+            "infinite" => go.infinite = true,
+            _ => (),
+        }
+    }
+
+    go
+}
+
+impl Go {
+    /// What the engine should stop at, for the player that has to play
+    pub fn limits(&self, color: shared::chess::Color) -> shared::engine::Limits {
+        let mut limits = shared::engine::Limits {
+            depth: self.depth,
+            nodes: self.nodes,
+            time: self.movetime,
+        };
+
+        if self.infinite {
+            return limits;
+        }
+
+        let (remaining, increment) = match color {
+            shared::chess::Color::White => (self.wtime, self.winc),
+            shared::chess::Color::Black => (self.btime, self.binc),
+        };
+
+        if let (None, Some(remaining)) = (limits.time, remaining) {
+            limits.time = shared::engine::Limits::clock(
+                remaining,
+                increment.unwrap_or_default(),
+                self.movestogo,
+            )
+            .time;
+        }
+
+        limits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, Go};
+    use std::time::Duration;
+
+    #[test]
+    fn simple() {
+        assert_eq!(Command::parse("uci"), Some(Command::Uci));
+        assert_eq!(Command::parse("  isready "), Some(Command::IsReady));
+        assert_eq!(Command::parse("joho quit"), Some(Command::Quit));
+        assert_eq!(Command::parse("hello"), None);
+        assert_eq!(Command::parse(""), None);
+    }
+
+    #[test]
+    fn setoption() {
+        assert_eq!(
+            Command::parse("setoption name Hash value 32"),
+            Some(Command::SetOption {
+                name: String::from("Hash"),
+                value: Some(String::from("32"))
+            })
+        );
+        assert_eq!(
+            Command::parse("setoption name Clear Hash"),
+            Some(Command::SetOption {
+                name: String::from("Clear Hash"),
+                value: None
+            })
+        );
+    }
+
+    #[test]
+    fn position() {
+        assert_eq!(
+            Command::parse("position startpos"),
+            Some(Command::Position {
+                fen: None,
+                moves: Vec::new()
+            })
+        );
+        assert_eq!(
+            Command::parse("position startpos moves e2e4 e7e5"),
+            Some(Command::Position {
+                fen: None,
+                moves: vec![String::from("e2e4"), String::from("e7e5")]
+            })
+        );
+        assert_eq!(
+            Command::parse("position fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 moves e2e4"),
+            Some(Command::Position {
+                fen: Some(String::from("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1")),
+                moves: vec![String::from("e2e4")]
+            })
+        );
+        assert_eq!(Command::parse("position"), None);
+    }
+
+    #[test]
+    fn go() {
+        assert_eq!(
+            Command::parse("go depth 6 movetime 1500"),
+            Some(Command::Go(Go {
+                depth: Some(6),
+                movetime: Some(Duration::from_millis(1500)),
+                ..Default::default()
+            }))
+        );
+
+        let Some(Command::Go(go)) =
+            Command::parse("go wtime 60000 btime 30000 winc 1000 binc 1000 movestogo 20")
+        else {
+            panic!()
+        };
+        println!("{go:?}");
+
+        let white = go.limits(shared::chess::Color::White).time.unwrap();
+        let black = go.limits(shared::chess::Color::Black).time.unwrap();
+        assert!(white > black);
+
+        let Some(Command::Go(go)) = Command::parse("go infinite") else {
+            panic!()
+        };
+        assert!(go.infinite);
+        assert_eq!(
+            go.limits(shared::chess::Color::White),
+            shared::engine::Limits::default()
+        );
+    }
+}
@@ -0,0 +1,24 @@
+// Universal Chess Interface front end for the engine, so it can play in chess GUIs and tournament managers
+// https://backscattering.de/chess/uci/
+//
+// Nothing else may be written to stdout, the GUI reads every line of it
+
+mod command;
+mod uci;
+
+fn main() {
+    let mut uci = uci::Uci::new(Box::new(std::io::stdout()));
+
+    for line in std::io::stdin().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        if !uci.handle(&line) {
+            return;
+        }
+    }
+
+    // The input was closed without a quit
+    uci.finish();
+}
@@ -0,0 +1,283 @@
+// Keeps the state between the commands, the searches run on their own thread so `stop` can still be read
+
+use crate::command::{Command, Go};
+use shared::{
+    chess::{Board, ChessMove},
+    engine::{Book, Engine, Score, SearchInfo},
+};
+use std::{
+    io::Write,
+    sync::{atomic::AtomicBool, Arc, Mutex},
+};
+
+const NAME: &str = "Chess engine";
+const AUTHOR: &str = "Bowarc";
+
+const DEFAULT_HASH: usize = 16;
+const MAX_HASH: usize = 1024;
+
+pub type Output = Arc<Mutex<Box<dyn Write + Send>>>;
+
+type SearchResult = Option<(ChessMove, SearchInfo)>;
+
+struct Search {
+    handle: std::thread::JoinHandle<(Engine, SearchResult)>,
+    stop: Arc<AtomicBool>,
+    // The GUI waits for `stop` before reading the best move
+    infinite: bool,
+}
+
+pub struct Uci {
+    out: Output,
+    board: Board,
+    // Lent to the search thread while it runs
+    engine: Option<Engine>,
+    search: Option<Search>,
+    // Kept to give it to the engine again when the hash size changes
+    book: Option<Arc<Book>>,
+}
+
+impl Uci {
+    pub fn new(out: Box<dyn Write + Send>) -> Self {
+        Self {
+            out: Arc::new(Mutex::new(out)),
+            board: Board::default(),
+            engine: Some(Engine::new(DEFAULT_HASH)),
+            search: None,
+            book: None,
+        }
+    }
+
+    /// Returns false once the GUI asked to quit
+    pub fn handle(&mut self, line: &str) -> bool {
+        let Some(command) = Command::parse(line) else {
+            return true;
+        };
+
+        match command {
+            Command::Uci => {
+                send(&self.out, &format!("id name {NAME}"));
+                send(&self.out, &format!("id author {AUTHOR}"));
+                send(
+                    &self.out,
+                    &format!(
+                        "option name Hash type spin default {DEFAULT_HASH} min 1 max {MAX_HASH}"
+                    ),
+                );
+                send(
+                    &self.out,
+                    "option name BookFile type string default <empty>",
+                );
+                send(&self.out, "uciok");
+            }
+            Command::IsReady => send(&self.out, "readyok"),
+            Command::UciNewGame => {
+                self.stop();
+                self.engine_mut().new_game();
+                self.board = Board::default();
+            }
+            Command::SetOption { name, value } => self.set_option(&name, value.as_deref()),
+            Command::Position { fen, moves } => {
+                self.stop();
+                self.set_position(fen.as_deref(), &moves);
+            }
+            Command::Go(go) => {
+                self.stop();
+                self.go(go);
+            }
+            Command::Stop => self.stop(),
+            Command::Quit => {
+                self.stop();
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Called when the input is closed, the running search finishes first
+    pub fn finish(&mut self) {
+        if self.search.as_ref().is_some_and(|search| search.infinite) {
+            self.stop();
+        } else {
+            self.wait();
+        }
+    }
+
+    fn set_option(&mut self, name: &str, value: Option<&str>) {
+        if name.eq_ignore_ascii_case("hash") {
+            self.set_hash(value);
+        } else if name.eq_ignore_ascii_case("bookfile") {
+            self.set_book(value);
+        } else {
+            send(&self.out, &format!("info string Unknown option '{name}'"));
+        }
+    }
+
+    fn set_hash(&mut self, value: Option<&str>) {
+        let Some(hash) = value
+            .and_then(|value| value.parse::<usize>().ok())
+            .filter(|hash| (1..=MAX_HASH).contains(hash))
+        else {
+            send(
+                &self.out,
+                "info string The hash size has to be a number of megabytes between 1 and 1024",
+            );
+            return;
+        };
+
+        self.stop();
+        let mut engine = Engine::new(hash);
+        engine.set_book(self.book.clone());
+        self.engine = Some(engine);
+    }
+
+    /// The path is relative to `resources/external`, no path or `<empty>` plays without a book
+    fn set_book(&mut self, value: Option<&str>) {
+        let book = match value.filter(|path| *path != "<empty>") {
+            None => None,
+            Some(path) => match Book::load(path) {
+                Ok(book) => Some(Arc::new(book)),
+                Err(e) => {
+                    send(
+                        &self.out,
+                        &format!("info string Could not load the book: {e}"),
+                    );
+                    return;
+                }
+            },
+        };
+
+        self.stop();
+        self.engine_mut().set_book(book.clone());
+        self.book = book;
+    }
+
+    fn set_position(&mut self, fen: Option<&str>, moves: &[String]) {
+        let board = match fen {
+            Some(fen) => Board::from_fen(fen),
+            None => Ok(Board::default()),
+        };
+
+        let mut board = match board {
+            Ok(board) => board,
+            Err(e) => {
+                send(&self.out, &format!("info string Invalid FEN: {e}"));
+                return;
+            }
+        };
+
+        for uci in moves {
+            let played = ChessMove::from_uci(uci, &board)
+                .map_err(|e| e.to_string())
+                .and_then(|mv| {
+                    board
+                        .make_move(&mv)
+                        .map_err(|()| format!("The move '{uci}' could not be played"))
+                });
+
+            if let Err(e) = played {
+                send(&self.out, &format!("info string {e}"));
+                return;
+            }
+        }
+
+        self.board = board;
+    }
+
+    fn go(&mut self, go: Go) {
+        let mut engine = self
+            .engine
+            .take()
+            .expect("The engine is lent to a running search");
+        let stop = engine.stop_flag();
+
+        let board = self.board.clone();
+        let limits = go.limits(board.next_to_play());
+        let infinite = go.infinite;
+        let out = self.out.clone();
+
+        let handle = std::thread::spawn(move || {
+            let result = engine.search_with(&board, limits, |info| send(&out, &info_line(info)));
+
+            if !infinite {
+                send(&out, &bestmove_line(&result));
+            }
+            (engine, result)
+        });
+
+        self.search = Some(Search {
+            handle,
+            stop,
+            infinite,
+        });
+    }
+
+    fn stop(&mut self) {
+        if let Some(search) = &self.search {
+            search
+                .stop
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+        }
+        self.wait();
+    }
+
+    fn wait(&mut self) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+
+        let (engine, result) = search.handle.join().expect("The search thread panicked");
+        if search.infinite {
+            send(&self.out, &bestmove_line(&result));
+        }
+        self.engine = Some(engine);
+    }
+
+    fn engine_mut(&mut self) -> &mut Engine {
+        self.engine
+            .as_mut()
+            .expect("The engine is lent to a running search")
+    }
+}
+
+fn send(out: &Output, line: &str) {
+    let mut out = out.lock().unwrap();
+    // The GUI is gone if this fails, the next `quit` or end of input will stop us
+    let _ = writeln!(out, "{line}").and_then(|()| out.flush());
+}
+
+fn info_line(info: &SearchInfo) -> String {
+    let score = match info.score {
+        Score::Centipawns(cp) => format!("cp {cp}"),
+        Score::Mate(moves) => format!("mate {moves}"),
+    };
+
+    let millis = info.time.as_millis().max(1);
+    let nps = info.nodes as u128 * 1000 / millis;
+
+    let pv = info
+        .pv
+        .iter()
+        .map(|mv| mv.to_uci())
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    format!(
+        "info depth {} score {score} nodes {} nps {nps} time {} pv {pv}",
+        info.depth,
+        info.nodes,
+        info.time.as_millis()
+    )
+}
+
+fn bestmove_line(result: &SearchResult) -> String {
+    match result {
+        Some((mv, info)) => match info.pv.get(1) {
+            Some(ponder) => format!("bestmove {} ponder {}", mv.to_uci(), ponder.to_uci()),
+            None => format!("bestmove {}", mv.to_uci()),
+        },
+        // No legal move, the protocol has a null move for that
+        None => String::from("bestmove 0000"),
+    }
+}
@@ -0,0 +1,175 @@
+// Scripted sessions sent through the binary's stdin, the way a GUI would talk to it
+
+use std::io::Write as _;
+
+fn spawn() -> std::process::Child {
+    spawn_in(std::path::Path::new("."))
+}
+
+// The books are looked for from there
+fn spawn_in(dir: &std::path::Path) -> std::process::Child {
+    std::process::Command::new(env!("CARGO_BIN_EXE_uci_engine"))
+        .current_dir(dir)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap()
+}
+
+fn output(child: std::process::Child) -> Vec<String> {
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let lines = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect::<Vec<String>>();
+
+    for line in &lines {
+        println!("< {line}");
+    }
+    lines
+}
+
+/// Sends the whole script and closes the input
+fn run(script: &str) -> Vec<String> {
+    run_in(std::path::Path::new("."), script)
+}
+
+fn run_in(dir: &std::path::Path, script: &str) -> Vec<String> {
+    let mut child = spawn_in(dir);
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+    output(child)
+}
+
+fn bestmove(lines: &[String]) -> &str {
+    let bestmoves = lines
+        .iter()
+        .filter(|line| line.starts_with("bestmove"))
+        .collect::<Vec<_>>();
+    assert_eq!(bestmoves.len(), 1, "{lines:?}");
+
+    bestmoves[0].split_whitespace().nth(1).unwrap()
+}
+
+#[test]
+fn handshake() {
+    let lines = run("uci\nisready\nquit\n");
+
+    assert!(lines[0].starts_with("id name"));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("option name Hash")));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("option name BookFile")));
+    assert_eq!(lines[lines.len() - 2], "uciok");
+    assert_eq!(lines[lines.len() - 1], "readyok");
+}
+
+#[test]
+fn mate_in_one() {
+    let lines = run("position fen 6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1\ngo depth 3\n");
+
+    assert!(lines.iter().any(|line| line.contains("score mate 1")));
+    assert_eq!(bestmove(&lines), "a1a8");
+}
+
+#[test]
+fn moves() {
+    let lines =
+        run("uci\nucinewgame\nisready\nposition startpos moves e2e4 e7e5 g1f3\ngo depth 3\n");
+
+    let mut board = shared::chess::Board::default();
+    for uci in ["e2e4", "e7e5", "g1f3"] {
+        let mv = shared::chess::ChessMove::from_uci(uci, &board).unwrap();
+        board.make_move(&mv).unwrap();
+    }
+
+    // Black to play
+    let mv = shared::chess::ChessMove::from_uci(bestmove(&lines), &board).unwrap();
+    assert_eq!(mv.color, shared::chess::Color::Black);
+
+    let infos = lines
+        .iter()
+        .filter(|line| line.starts_with("info depth"))
+        .count();
+    assert_eq!(infos, 3);
+}
+
+#[test]
+fn clock() {
+    let start = std::time::Instant::now();
+    let lines = run("position startpos\ngo wtime 2000 btime 2000 winc 0 binc 0\n");
+
+    bestmove(&lines);
+    assert!(start.elapsed() < std::time::Duration::from_secs(2));
+}
+
+#[test]
+fn infinite() {
+    let mut child = spawn();
+    let mut stdin = child.stdin.take().unwrap();
+
+    stdin
+        .write_all(b"position startpos\ngo infinite\nisready\n")
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    stdin.write_all(b"stop\nquit\n").unwrap();
+    drop(stdin);
+
+    let lines = output(child);
+    // Still answers while searching
+    assert!(lines.contains(&String::from("readyok")));
+    assert!(lines.last().unwrap().starts_with("bestmove"));
+    bestmove(&lines);
+}
+
+#[test]
+fn no_move() {
+    let lines = run("position fen 7k/5Q2/6K1/8/8/8/8/8 b - - 0 1\ngo depth 2\n");
+    assert_eq!(bestmove(&lines), "0000");
+}
+
+#[test]
+fn errors() {
+    let lines = run(
+        "position startpos moves e2e5\nposition fen not a fen\nsetoption name Hash value 0\nhello\nisready\n",
+    );
+
+    let infos = lines
+        .iter()
+        .filter(|line| line.starts_with("info string"))
+        .count();
+    assert_eq!(infos, 3);
+    assert_eq!(lines.last().unwrap(), "readyok");
+}
+
+#[test]
+fn book() {
+    let dir = std::env::temp_dir().join(format!("uci_engine_book_{}", std::process::id()));
+    let books = dir.join("resources/external");
+    std::fs::create_dir_all(&books).unwrap();
+
+    // A move the search would not play
+    let mut builder = shared::engine::BookBuilder::new(1);
+    builder.add_game(&shared::pgn::Pgn::parse("1. a3 1-0").unwrap());
+    std::fs::write(books.join("test.bin"), builder.build().to_bytes()).unwrap();
+
+    let lines = run_in(
+        &dir,
+        "setoption name BookFile value test.bin\nposition startpos\ngo depth 3\nsetoption name BookFile value missing.bin\nisready\n",
+    );
+    assert_eq!(bestmove(&lines), "a2a3");
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("info string Could not load the book")));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}